@@ -1,26 +1,42 @@
 use std::env;
 use std::path::PathBuf;
 
+/// Links the bundled PaToH library if the feature `patoh` is enabled (it is by default) and the
+/// target platform is supported. Otherwise, no link directives are emitted and the crate is built
+/// with the greedy partitioner written in Rust. The cfg `patoh_linked` tells the crate which
+/// partitioner is available.
 fn main() {
-    // Get the target platform (e.g., x86_64-unknown-linux-gnu)
-    let target = env::var("TARGET").unwrap();
+    println!("cargo:rustc-check-cfg=cfg(patoh_linked)");
 
-    // Path to your bundled PaToH library
-    let lib_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("lib/patoh");
+    // Cargo sets this variable for every enabled feature
+    if env::var_os("CARGO_FEATURE_PATOH").is_none() {
+        return;
+    }
 
-    // Add the directory containing the library to the linker search path
-    println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    // Get the target platform (e.g., x86_64-unknown-linux-gnu)
+    let target = env::var("TARGET").unwrap();
 
     // If you're using a static library (libpatoh.a), link statically
-    if target.contains("linux") || target.contains("darwin") {
+    let link_kind = if target.contains("linux") || target.contains("darwin") {
         // Linux or macOS, static linking
-        println!("cargo:rustc-link-lib=static=patoh");
+        "static"
     } else if target.contains("windows") {
         // Windows, dynamic linking
-        println!("cargo:rustc-link-lib=dylib=patoh");
+        "dylib"
     } else {
-        panic!("Unsupported platform");
-    }
+        println!(
+            "cargo:warning=PaToH is not available for {target}, the greedy partitioner is used instead"
+        );
+        return;
+    };
+
+    // Path to your bundled PaToH library
+    let lib_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("lib/patoh");
+
+    // Add the directory containing the library to the linker search path
+    println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    println!("cargo:rustc-link-lib={link_kind}=patoh");
+    println!("cargo:rustc-cfg=patoh_linked");
 
     // Rebuild if the library changes
     println!("cargo:rerun-if-changed=libs/libpatoh.a");
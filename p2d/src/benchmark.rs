@@ -0,0 +1,144 @@
+use crate::read_formula;
+use crate::solving::solver::{Solver, Statistics};
+use num_bigint::BigUint;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The outcome of counting the models of one instance of a suite
+#[derive(Debug)]
+pub struct BenchResult {
+    /// The path of the instance
+    pub path: PathBuf,
+    /// The model count, None if the solver reached the timeout or the instance could not be read
+    pub model_count: Option<BigUint>,
+    /// The time needed to read, build and solve the instance
+    pub time: Duration,
+    /// The statistics of the solver, None if the instance could not be read
+    pub statistics: Option<Statistics>,
+    /// Why the instance could not be read
+    pub error: Option<String>,
+}
+
+impl BenchResult {
+    /// Checks if the solver was stopped by the timeout
+    pub fn timed_out(&self) -> bool {
+        self.model_count.is_none() && self.error.is_none()
+    }
+
+    /// Number of subformulas whose result was taken from the cache, 0 if the instance could not be
+    /// read
+    pub fn cache_hits(&self) -> u32 {
+        self.statistics
+            .as_ref()
+            .map_or(0, |statistics| statistics.cache_hits())
+    }
+}
+
+/// Counts the models of several instances one after another, like the batch mode of the command
+/// line, but returns one row per instance instead of printing it. The instances are read like the
+/// input files of the command line, so CNFs, WCNFs and compressed files are supported. An instance
+/// that cannot be read is reported in its row and does not stop the suite. No d-DNNF is built and
+/// no progress is printed.
+/// # Arguments
+/// * `paths` - The paths of the instances
+/// * `timeout` - The time the solver may spend on each instance
+/// # Returns
+/// One result per instance in the order of the paths
+pub fn run_suite(paths: &[PathBuf], timeout: Duration) -> Vec<BenchResult> {
+    paths
+        .iter()
+        .map(|path| run_instance(path, timeout))
+        .collect()
+}
+
+fn run_instance(path: &Path, timeout: Duration) -> BenchResult {
+    let start = Instant::now();
+    let formula = match read_formula(&path.display().to_string()) {
+        Ok(formula) => formula,
+        Err(error) => {
+            return BenchResult {
+                path: path.to_path_buf(),
+                model_count: None,
+                time: start.elapsed(),
+                statistics: None,
+                error: Some(error),
+            }
+        }
+    };
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let mut solver = Solver::with_cancel(formula, Arc::clone(&cancel_flag));
+    solver.set_count_only(true);
+    solver.set_progress_callback(Box::new(|_| {}));
+
+    // the timer stops as soon as the solver is done, which drops the sender
+    let (done_sender, done_receiver) = mpsc::channel::<()>();
+    let timer = thread::spawn(move || {
+        if let Err(RecvTimeoutError::Timeout) = done_receiver.recv_timeout(timeout) {
+            cancel_flag.store(true, Ordering::Relaxed);
+        }
+    });
+    let model_count = solver.try_solve().ok().map(|result| result.model_count);
+    drop(done_sender);
+    timer.join().expect("timer thread panicked");
+
+    BenchResult {
+        path: path.to_path_buf(),
+        model_count,
+        time: start.elapsed(),
+        statistics: Some(solver.statistics),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::fs;
+
+    #[test]
+    #[serial]
+    fn test_run_suite() {
+        let first = crate::temp_file("benchmark_first.opb");
+        let second = crate::temp_file("benchmark_second.cnf");
+        fs::write(&first, "#variable= 3 #constraint= 1\nx1 + x2 + x3 >= 2;\n").unwrap();
+        fs::write(&second, "p cnf 2 1\n1 2 0\n").unwrap();
+        let missing = crate::temp_file("benchmark_missing.opb");
+
+        let results = run_suite(
+            &[first.clone(), second.clone(), missing.clone()],
+            Duration::from_secs(60),
+        );
+        fs::remove_file(&first).unwrap();
+        fs::remove_file(&second).unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].path, first);
+        assert_eq!(results[0].model_count, Some(BigUint::from(4_u32)));
+        assert_eq!(results[1].path, second);
+        assert_eq!(results[1].model_count, Some(BigUint::from(3_u32)));
+        for result in &results[..2] {
+            assert!(result.statistics.is_some());
+            assert!(result.error.is_none());
+            assert!(!result.timed_out());
+        }
+        assert_eq!(results[2].model_count, None);
+        assert!(results[2].statistics.is_none());
+        assert!(results[2].error.is_some());
+        assert!(!results[2].timed_out());
+    }
+
+    #[test]
+    #[serial]
+    fn test_run_suite_timeout() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_models/automotive01.opb");
+        let results = run_suite(&[path], Duration::from_millis(50));
+        assert!(results[0].timed_out());
+        assert!(results[0].statistics.is_some());
+        assert!(results[0].time < Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,95 @@
+use crate::solving::pseudo_boolean_datastructure::{PseudoBooleanFormula, MAX_UNUSED_VARIABLES};
+use flate2::read::GzDecoder;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+pub mod benchmark;
+
+pub mod solving {
+    pub mod ddnnf;
+    pub mod lru_cache;
+    pub mod pseudo_boolean_datastructure;
+    pub mod solver;
+}
+
+mod partitioning {
+    pub mod disconnected_component_datastructure;
+    #[cfg(any(test, not(patoh_linked)))]
+    pub mod greedy_partitioning;
+    pub mod hypergraph;
+    #[cfg(patoh_linked)]
+    pub mod hypergraph_partitioning;
+    #[cfg(patoh_linked)]
+    pub mod patoh_api;
+}
+
+/// Reads and parses an input file, CNFs are detected by the extensions .cnf and .dimacs and the
+/// hard clauses of a WCNF by the extension .wcnf. The header may declare at most
+/// `MAX_UNUSED_VARIABLES` unused variables, see `read_formula_with_max_unused_variables`.
+/// # Arguments
+/// * `input_path` - The path of the input file
+/// # Returns
+/// The formula of the file or an error that names the file
+pub fn read_formula(input_path: &str) -> Result<PseudoBooleanFormula, String> {
+    read_formula_with_max_unused_variables(input_path, MAX_UNUSED_VARIABLES)
+}
+
+/// Reads and parses an input file like `read_formula`.
+/// # Arguments
+/// * `input_path` - The path of the input file
+/// * `max_unused_variables` - The number of variables the header may declare in addition to the
+///   ones used in the equations, see `PseudoBooleanFormula::try_new_with_max_unused_variables`
+/// # Returns
+/// The formula of the file or an error that names the file
+pub fn read_formula_with_max_unused_variables(
+    input_path: &str,
+    max_unused_variables: u32,
+) -> Result<PseudoBooleanFormula, String> {
+    let file_content =
+        read_input(input_path).map_err(|e| format!("cannot read file {input_path}: {e}"))?;
+    // the format is given by the extension in front of .gz for compressed files
+    let path = Path::new(input_path);
+    let uncompressed_path = if is_gzip(path) {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    };
+    let extension = uncompressed_path
+        .extension()
+        .and_then(|extension| extension.to_str());
+    let opb_file = match extension {
+        Some("cnf" | "dimacs") => p2d_opb::parse_cnf(file_content.as_str()),
+        Some("wcnf") => p2d_opb::parse_wcnf(file_content.as_str()),
+        _ => p2d_opb::parse(file_content.as_str()),
+    }
+    .map_err(|e| format!("cannot parse file {input_path}: {e}"))?;
+    PseudoBooleanFormula::try_new_with_max_unused_variables(&opb_file, max_unused_variables)
+        .map_err(|e| format!("cannot build formula from {input_path}: {e}"))
+}
+
+/// Reads the whole input file, files with the extension .gz are decompressed while reading.
+/// # Arguments
+/// * `input_path` - The path of the input file
+/// # Returns
+/// The (decompressed) content of the file
+fn read_input(input_path: &str) -> std::io::Result<String> {
+    if !is_gzip(Path::new(input_path)) {
+        return fs::read_to_string(input_path);
+    }
+    let mut file_content = String::new();
+    GzDecoder::new(fs::File::open(input_path)?).read_to_string(&mut file_content)?;
+    Ok(file_content)
+}
+
+/// Checks if a path has the extension .gz
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "gz")
+}
+
+/// Returns a path in the temporary directory for a file of a test. The process id keeps the files
+/// of concurrent test runs apart, the test removes the file when it is done.
+#[cfg(test)]
+fn temp_file(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("p2d_test_{}_{name}", std::process::id()))
+}
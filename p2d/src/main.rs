@@ -1,22 +1,13 @@
-use crate::solving::ddnnf::DDNNFPrinter;
-use crate::solving::pseudo_boolean_datastructure::PseudoBooleanFormula;
-use crate::solving::solver::Solver;
-use clap::{Arg, Command};
-use std::collections::HashMap;
+use clap::{Arg, ArgAction, Command};
+use num_bigint::BigUint;
+use p2d::read_formula_with_max_unused_variables;
+use p2d::solving::ddnnf::{DDNNFPrinter, DDNNF};
+use p2d::solving::pseudo_boolean_datastructure::MAX_UNUSED_VARIABLES;
+use p2d::solving::solver::Solver;
 use std::fs;
-
-mod solving {
-    pub mod ddnnf;
-    pub mod pseudo_boolean_datastructure;
-    pub mod solver;
-}
-
-mod partitioning {
-    pub mod disconnected_component_datastructure;
-    pub mod hypergraph;
-    pub mod hypergraph_partitioning;
-    pub mod patoh_api;
-}
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 fn main() {
     let matches = Command::new("p2d")
@@ -25,8 +16,9 @@ fn main() {
         .arg(
             Arg::new("input")
                 .required(true)
+                .num_args(1..)
                 .value_name("INPUT_FILE")
-                .help("Path to the input file"),
+                .help("Path to the input file, several files or directories are counted one after another in mode mc"),
         )
         .arg(
             Arg::new("mode")
@@ -44,38 +36,289 @@ fn main() {
                 .value_name("OUTPUT_FILE")
                 .help("Path to the output file (required if mode is ddnnf)"),
         )
+        .arg(
+            Arg::new("format")
+                .short('f')
+                .long("format")
+                .value_name("FORMAT")
+                .help("Format of the d-DNNF in mode ddnnf: d4 (default), dot or json")
+                .value_parser(["d4", "dot", "json"]),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help("Print the model count and the statistics as a single JSON object in mode mc")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("verify")
+                .long("verify")
+                .help("Recounts the models on the d-DNNF and checks its properties, fails if the count differs or the d-DNNF is invalid")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("stats-only")
+                .long("stats-only")
+                .help("Explores the search space of mode mc only for the statistics, without counting the models")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .value_name("JOBS")
+                .help("Number of input files that are counted at the same time if there are several")
+                .default_value("1")
+                .value_parser(clap::value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("max-unused-variables")
+                .long("max-unused-variables")
+                .value_name("VARIABLES")
+                .help("Number of variables the header of an input file may declare in addition to the ones used in its constraints (default 1048576), every variable needs memory")
+                .value_parser(clap::value_parser!(u32)),
+        )
         .get_matches();
 
-    let input_file = matches.get_one::<String>("input").unwrap();
+    let input_paths: Vec<&String> = matches.get_many::<String>("input").unwrap().collect();
     let mode = matches.get_one::<String>("mode").unwrap();
     let optional_output_file = matches.get_one::<String>("output");
+    let optional_format = matches.get_one::<String>("format");
+    let json = matches.get_flag("json");
+    let verify = matches.get_flag("verify");
+    let stats_only = matches.get_flag("stats-only");
+    let jobs = *matches.get_one::<u64>("jobs").unwrap() as usize;
+    let max_unused_variables = matches
+        .get_one::<u32>("max-unused-variables")
+        .copied()
+        .unwrap_or(MAX_UNUSED_VARIABLES);
 
-    run_not_rec(input_file, mode, optional_output_file);
+    if input_paths.len() > 1 || Path::new(input_paths[0]).is_dir() {
+        if stats_only {
+            eprintln!("error: the statistics can only be collected for a single input file");
+            std::process::exit(1);
+        }
+        if let Err(error) = run_batch(&input_paths, mode, json, verify, jobs, max_unused_variables)
+        {
+            eprintln!("error: {error}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Err(error) = run_not_rec(
+        input_paths[0],
+        mode,
+        optional_output_file,
+        optional_format,
+        json,
+        verify,
+        stats_only,
+        max_unused_variables,
+    ) {
+        eprintln!("error: {error}");
+        std::process::exit(1);
+    }
 }
 
-fn run_not_rec(input_path: &str, mode: &str, output_file: Option<&String>) {
-    let file_content = fs::read_to_string(input_path).expect("cannot read file");
-    let opb_file = p2d_opb::parse(file_content.as_str()).expect("error while parsing");
-    let formula = PseudoBooleanFormula::new(&opb_file);
+#[allow(clippy::too_many_arguments)]
+fn run_not_rec(
+    input_path: &str,
+    mode: &str,
+    output_file: Option<&String>,
+    format: Option<&String>,
+    json: bool,
+    verify: bool,
+    stats_only: bool,
+    max_unused_variables: u32,
+) -> Result<(), String> {
+    if mode != "ddnnf" && format.is_some() {
+        return Err("the format can only be chosen in mode ddnnf".to_string());
+    }
+    if mode != "mc" && json {
+        return Err("the JSON output can only be chosen in mode mc".to_string());
+    }
+    if stats_only && (mode != "mc" || verify) {
+        return Err("the statistics can only be collected in mode mc without --verify".to_string());
+    }
+    let output_file = match (mode, output_file) {
+        ("ddnnf", None) => {
+            return Err("the mode ddnnf requires an output file, use -o <OUTPUT_FILE>".to_string())
+        }
+        ("ddnnf", Some(output_file)) => Some(output_file),
+        ("mc", _) => None,
+        _ => return Err(format!("unknown mode {mode}")),
+    };
+    let formula = read_formula_with_max_unused_variables(input_path, max_unused_variables)?;
     let mut solver = Solver::new(formula);
+    // the d-DNNF is only built if it is written or verified
+    solver.set_count_only(output_file.is_none() && !verify);
+    if json {
+        // stdout must only contain the JSON object
+        solver.set_progress_callback(Box::new(|_| {}));
+    }
+    if stats_only {
+        solver.set_stats_only(true);
+        solver.solve();
+        if json {
+            println!("{{\"statistics\":{}}}", solver.statistics.to_json());
+        } else {
+            println!("{:#?}", solver.statistics);
+        }
+        return Ok(());
+    }
     let result = solver.solve();
+    if verify {
+        verify_result(&result.model_count, result.ddnnf.as_ref())?;
+    }
     let model_count = result.model_count;
+    if json {
+        println!(
+            "{{\"model_count\":\"{}\",\"statistics\":{}}}",
+            model_count,
+            solver.statistics.to_json()
+        );
+        return Ok(());
+    }
     println!("result: {}", model_count);
     println!("{:#?}", solver.statistics);
-    if mode == "ddnnf" {
-        if output_file.is_none() {
-            panic!("Missing output file!")
+    println!(
+        "estimated memory: {} bytes",
+        solver.statistics.estimated_bytes()
+    );
+    if let (Some(output_file), Some(ddnnf)) = (output_file, result.ddnnf) {
+        // the same circuit is always written in the same way
+        let canonical_ddnnf = ddnnf.canonicalize();
+        let write_error = |e| format!("cannot write output file {output_file}: {e}");
+        match format.map(String::as_str) {
+            Some("dot") => fs::write(output_file, canonical_ddnnf.to_dot()).map_err(write_error)?,
+            Some("json") => {
+                fs::write(output_file, canonical_ddnnf.to_json()).map_err(write_error)?
+            }
+            _ => {
+                // the d4 output of large circuits is streamed instead of built in memory
+                let mut writer =
+                    BufWriter::new(fs::File::create(output_file).map_err(write_error)?);
+                DDNNFPrinter::new(canonical_ddnnf)
+                    .write_to(&mut writer)
+                    .map_err(write_error)?;
+                writer.flush().map_err(write_error)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Counts the models of several input files, directories are replaced by the files they contain.
+/// One line with the path and the model count (or a JSON object) is printed per file as soon as
+/// it is counted, so the order of the lines depends on the number of jobs. An error in one file
+/// is reported and the other files are still counted.
+/// # Arguments
+/// * `input_paths` - The paths of the input files and directories
+/// * `mode` - The mode of operation, only mc is supported for several files
+/// * `json` - Whether a JSON object is printed per file
+/// * `verify` - Whether the count of each file is verified on its d-DNNF
+/// * `jobs` - The number of files that are counted at the same time
+/// * `max_unused_variables` - The number of variables the header of a file may declare in
+///   addition to the ones used in its constraints
+/// # Returns
+/// An error if the arguments are invalid or at least one file could not be counted
+fn run_batch(
+    input_paths: &[&String],
+    mode: &str,
+    json: bool,
+    verify: bool,
+    jobs: usize,
+    max_unused_variables: u32,
+) -> Result<(), String> {
+    if mode != "mc" {
+        return Err("several input files can only be counted in mode mc".to_string());
+    }
+    let mut files = Vec::new();
+    for input_path in input_paths {
+        let path = Path::new(input_path.as_str());
+        if path.is_dir() {
+            let mut entries = fs::read_dir(path)
+                .map_err(|e| format!("cannot read directory {input_path}: {e}"))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_file())
+                .collect::<Vec<_>>();
+            entries.sort();
+            files.extend(entries.iter().map(|path| path.display().to_string()));
+        } else {
+            files.push(input_path.to_string());
+        }
+    }
+
+    let next_file = AtomicUsize::new(0);
+    let failed_files = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(files.len()) {
+            scope.spawn(|| {
+                while let Some(file) = files.get(next_file.fetch_add(1, Ordering::Relaxed)) {
+                    match count_file(file, verify, max_unused_variables) {
+                        Ok((model_count, _)) if !json => println!("{file}: {model_count}"),
+                        Ok((model_count, statistics)) => println!(
+                            "{{\"file\":{:?},\"model_count\":\"{}\",\"statistics\":{}}}",
+                            file, model_count, statistics
+                        ),
+                        Err(error) => {
+                            eprintln!("error: {error}");
+                            failed_files.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+            });
         }
-        let mut printer = DDNNFPrinter {
-            true_sink_id: None,
-            false_sink_id: None,
-            ddnnf: result.ddnnf,
-            current_node_id: 0,
-            id_map: HashMap::new(),
-            edge_counter: 0,
-            node_counter: 0,
-        };
-        let ddnnf = printer.print();
-        fs::write(output_file.unwrap(), ddnnf).expect("Error while writing outputfile");
+    });
+    match failed_files.into_inner() {
+        0 => Ok(()),
+        failed_files => Err(format!(
+            "{failed_files} of {} files could not be counted",
+            files.len()
+        )),
+    }
+}
+
+/// Counts the models of a single file without printing the progress.
+/// # Returns
+/// The model count and the statistics of the solver as JSON
+fn count_file(
+    input_path: &str,
+    verify: bool,
+    max_unused_variables: u32,
+) -> Result<(BigUint, String), String> {
+    let mut solver = Solver::new(read_formula_with_max_unused_variables(
+        input_path,
+        max_unused_variables,
+    )?);
+    solver.set_count_only(!verify);
+    solver.set_progress_callback(Box::new(|_| {}));
+    let result = solver.solve();
+    if verify {
+        verify_result(&result.model_count, result.ddnnf.as_ref())
+            .map_err(|e| format!("{input_path}: {e}"))?;
+    }
+    Ok((result.model_count, solver.statistics.to_json()))
+}
+
+/// Checks the result of the solver against its d-DNNF: the circuit must be a valid d-DNNF and
+/// counting its models must give the same count as the solver.
+/// # Arguments
+/// * `model_count` - The model count reported by the solver
+/// * `ddnnf` - The d-DNNF built by the solver
+/// # Returns
+/// An error that describes the first failed check
+fn verify_result(model_count: &BigUint, ddnnf: Option<&DDNNF>) -> Result<(), String> {
+    let ddnnf = ddnnf.ok_or("verification failed: no d-DNNF was built")?;
+    ddnnf
+        .validate()
+        .map_err(|e| format!("verification failed: {e}"))?;
+    let circuit_count = ddnnf.model_count();
+    if &circuit_count != model_count {
+        return Err(format!(
+            "verification failed: the d-DNNF has {circuit_count} models, but {model_count} were counted"
+        ));
     }
+    Ok(())
 }
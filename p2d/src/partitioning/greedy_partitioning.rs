@@ -0,0 +1,126 @@
+use std::collections::{HashSet, VecDeque};
+
+/// Partitions a hypergraph into k parts without PaToH. The vertices are ordered by a breadth-first
+/// search that starts at a vertex far away from the first vertex, this order is then split into k
+/// parts of (almost) equal size. This keeps neighbouring vertices together and the parts balanced,
+/// but the cut is usually larger than the one found by PaToH.
+/// # Arguments
+/// * `number_vertices` - The number of vertices
+/// * `number_nets` - The number of nets
+/// * `nets` - The pins of all nets, the pins of net i are nets[x_pins[i]..x_pins[i + 1]]
+/// * `x_pins` - The start of the pins of each net
+/// * `k` - The number of parts
+/// * `_seed` - Unused, the partition does not depend on random choices
+/// # Returns
+/// The size of the cut (sum of the number of parts connected by each net minus one), the part of each
+/// vertex and the nets that connect more than one part
+pub fn partition(
+    number_vertices: u32,
+    number_nets: u32,
+    nets: &[u32],
+    x_pins: &[u32],
+    k: u32,
+    _seed: i32,
+) -> (u32, Vec<u32>, Vec<u32>) {
+    let mut nets_by_vertex = vec![Vec::new(); number_vertices as usize];
+    for net in 0..number_nets as usize {
+        for pin in &nets[x_pins[net] as usize..x_pins[net + 1] as usize] {
+            nets_by_vertex[*pin as usize].push(net);
+        }
+    }
+
+    let start_vertex = bfs_order(0, &nets_by_vertex, nets, x_pins)
+        .last()
+        .copied()
+        .unwrap_or(0);
+    let order = bfs_order(start_vertex, &nets_by_vertex, nets, x_pins);
+
+    let mut partition = vec![0; number_vertices as usize];
+    for (position, vertex) in order.iter().enumerate() {
+        partition[*vertex as usize] = (position * k as usize / order.len()) as u32;
+    }
+
+    let mut cut = 0;
+    let mut edges_to_remove = Vec::new();
+    for net in 0..number_nets as usize {
+        let connected_parts: HashSet<u32> = nets[x_pins[net] as usize..x_pins[net + 1] as usize]
+            .iter()
+            .map(|pin| partition[*pin as usize])
+            .collect();
+        if connected_parts.len() > 1 {
+            cut += connected_parts.len() as u32 - 1;
+            edges_to_remove.push(net as u32);
+        }
+    }
+
+    (cut, partition, edges_to_remove)
+}
+
+/// Visits all vertices in breadth-first order, starting a new search at the next unvisited vertex
+/// whenever a connected part of the hypergraph is finished.
+fn bfs_order(
+    start_vertex: u32,
+    nets_by_vertex: &[Vec<usize>],
+    nets: &[u32],
+    x_pins: &[u32],
+) -> Vec<u32> {
+    let mut visited = vec![false; nets_by_vertex.len()];
+    let mut order = Vec::with_capacity(nets_by_vertex.len());
+    let mut queue = VecDeque::new();
+    let start_vertices = std::iter::once(start_vertex).chain(0..nets_by_vertex.len() as u32);
+    for next_start in start_vertices {
+        if next_start as usize >= visited.len() || visited[next_start as usize] {
+            continue;
+        }
+        visited[next_start as usize] = true;
+        queue.push_back(next_start);
+        while let Some(vertex) = queue.pop_front() {
+            order.push(vertex);
+            for net in &nets_by_vertex[vertex as usize] {
+                for pin in &nets[x_pins[*net] as usize..x_pins[*net + 1] as usize] {
+                    if !visited[*pin as usize] {
+                        visited[*pin as usize] = true;
+                        queue.push_back(*pin);
+                    }
+                }
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balanced_bisection() {
+        // two chains 0-1-2 and 3-4-5 that are connected by net 2
+        let nets = vec![0, 1, 1, 2, 2, 3, 3, 4, 4, 5];
+        let x_pins = vec![0, 2, 4, 6, 8, 10];
+        let (cut, partition, edges_to_remove) = partition(6, 5, &nets, &x_pins, 2, 1);
+        assert_eq!(partition.len(), 6);
+        assert_eq!(partition.iter().filter(|p| **p == 0).count(), 3);
+        assert_eq!(partition[0], partition[1]);
+        assert_eq!(partition[1], partition[2]);
+        assert_eq!(partition[3], partition[4]);
+        assert_eq!(partition[4], partition[5]);
+        assert_eq!(edges_to_remove, vec![2]);
+        assert_eq!(cut, 1);
+    }
+
+    #[test]
+    fn test_three_parts() {
+        // a chain 0-1-2-3-4-5
+        let nets = vec![0, 1, 1, 2, 2, 3, 3, 4, 4, 5];
+        let x_pins = vec![0, 2, 4, 6, 8, 10];
+        let (cut, partition, edges_to_remove) = partition(6, 5, &nets, &x_pins, 3, 1);
+        let labels: HashSet<u32> = partition.iter().copied().collect();
+        assert_eq!(labels, HashSet::from([0, 1, 2]));
+        for label in labels {
+            assert_eq!(partition.iter().filter(|p| **p == label).count(), 2);
+        }
+        assert_eq!(edges_to_remove.len(), 2);
+        assert_eq!(cut, 2);
+    }
+}
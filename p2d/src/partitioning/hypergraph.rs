@@ -1,4 +1,7 @@
 use crate::partitioning::disconnected_component_datastructure::{Component, ComponentBasedFormula};
+#[cfg(not(patoh_linked))]
+use crate::partitioning::greedy_partitioning::partition;
+#[cfg(patoh_linked)]
 use crate::partitioning::hypergraph_partitioning::partition;
 use crate::solving::pseudo_boolean_datastructure::ConstraintIndex::NormalConstraintIndex;
 use crate::solving::solver::Solver;
@@ -50,7 +53,7 @@ impl Hypergraph {
                         .constraints
                         .get(*constraint_index)
                         .unwrap();
-                    if constraint.is_unsatisfied() {
+                    if !constraint.is_satisfied() {
                         if let NormalConstraintIndex(index) = constraint.index {
                             tmp_constraint_indexes.push(index);
                         }
@@ -123,8 +126,11 @@ impl Hypergraph {
                             .unwrap(),
                     )
                     .unwrap();
-                for (index, _) in &constraint.unassigned_literals {
-                    let hg_index = *self.variable_index_map_reverse.get(index).unwrap() as usize;
+                for index in constraint.unassigned_variables() {
+                    let hg_index = *self
+                        .variable_index_map_reverse
+                        .get(&(index as usize))
+                        .unwrap() as usize;
                     for i in
                         *self.x_pins.get(hg_index).unwrap()..*self.x_pins.get(hg_index + 1).unwrap()
                     {
@@ -153,21 +159,25 @@ impl Hypergraph {
         }
     }
 
-    pub fn get_variables_for_cut(&self) -> Vec<u32> {
+    /// Partitions the hypergraph into k parts and returns the variables whose nets connect different parts
+    /// together with the size of the cut. The seed is passed to the partitioner.
+    pub fn get_variables_for_cut(&self, k: u32, seed: i32) -> (Vec<u32>, u32) {
         if self.current_constraint_index <= 1 || self.current_variable_index <= 1 {
-            return Vec::new();
+            return (Vec::new(), 0);
         }
         let mut next_variables = Vec::new();
-        let (_, _, edges_to_remove) = partition(
+        let (cut, _, edges_to_remove) = partition(
             self.current_constraint_index,
             self.current_variable_index,
             &self.pins,
             &self.x_pins,
+            k,
+            seed,
         );
         for e in edges_to_remove {
             next_variables.push(*self.variable_index_map.get(e as usize).unwrap() as u32);
         }
-        next_variables
+        (next_variables, cut)
     }
 
     pub fn create_partition(&self, solver: &Solver, partvec: Vec<u32>) -> ComponentBasedFormula {
@@ -206,15 +216,14 @@ impl Hypergraph {
                 .get(*constraint_index)
                 .unwrap();
 
-            if constraint.is_unsatisfied() {
+            if !constraint.is_satisfied() {
                 component.number_unsat_constraints += 1;
                 component
                     .constraint_indexes_in_scope
                     .insert(*constraint_index);
-                for (i, _) in &constraint.unassigned_literals {
-                    if !component.variables.contains(i) {
+                for i in constraint.unassigned_variables() {
+                    if component.variables.insert(i as usize) {
                         component.number_unassigned_variables += 1;
-                        component.variables.insert(*i);
                     }
                 }
             }
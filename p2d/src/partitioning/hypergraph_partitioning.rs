@@ -5,18 +5,30 @@ use crate::partitioning::patoh_api::{
 use libc::{c_int, free, malloc};
 use std::ptr;
 
+/// Partitions a hypergraph into k parts with PaToH.
+/// # Arguments
+/// * `number_vertices` - The number of vertices
+/// * `number_nets` - The number of nets
+/// * `nets` - The pins of all nets, the pins of net i are nets[x_pins[i]..x_pins[i + 1]]
+/// * `x_pins` - The start of the pins of each net
+/// * `k` - The number of parts
+/// * `seed` - The seed of the random choices of PaToH
+/// # Returns
+/// The size of the cut, the part of each vertex and the nets that connect more than one part
 pub fn partition(
     number_vertices: u32,
     number_nets: u32,
     nets: &Vec<u32>,
     x_pins: &Vec<u32>,
+    k: u32,
+    seed: i32,
 ) -> (u32, Vec<u32>, Vec<u32>) {
     unsafe {
         let mut args: PaToH_Parameters = PaToH_Parameters {
             cuttype: 0,
-            _k: 2,
+            _k: k as c_int,
             outputdetail: 0,
-            seed: 1,
+            seed,
             doinitperm: 0,
             bisec_fixednetsizetrsh: 0,
             bisec_netsizetrsh: 0.0,
@@ -81,7 +93,7 @@ pub fn partition(
             malloc((c as usize * std::mem::size_of::<c_int>()) as libc::size_t) as *mut c_int;
         let mut cut: c_int = 0;
         let partweights: *mut c_int =
-            malloc(args._k as usize * std::mem::size_of::<c_int>() as libc::size_t) as *mut c_int;
+            malloc(k as usize * std::mem::size_of::<c_int>() as libc::size_t) as *mut c_int;
 
         for i in 0..c {
             *cwghts.wrapping_add(i as usize) = 1;
@@ -102,7 +114,8 @@ pub fn partition(
             PATOH_SUGPARAM_DEFAULT as c_int,
         );
 
-        args.seed = 1;
+        args.seed = seed as c_int;
+        args._k = k as c_int;
 
         PaToH_Alloc(&mut args, c, n, nconst, cwghts, nwghts, xpins, pins);
 
@@ -153,3 +166,25 @@ pub fn partition(
         (cut as u32, partition, edges_to_remove)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::collections::HashSet;
+
+    #[test]
+    #[serial]
+    fn test_three_parts() {
+        // a chain of nine vertices
+        let nets = vec![0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8];
+        let x_pins = vec![0, 2, 4, 6, 8, 10, 12, 14, 16];
+        let (_, partition, edges_to_remove) = partition(9, 8, &nets, &x_pins, 3, 1);
+        let labels: HashSet<u32> = partition.iter().copied().collect();
+        assert_eq!(labels, HashSet::from([0, 1, 2]));
+        for net in edges_to_remove {
+            let pins = &nets[x_pins[net as usize] as usize..x_pins[net as usize + 1] as usize];
+            assert_ne!(partition[pins[0] as usize], partition[pins[1] as usize]);
+        }
+    }
+}
@@ -1,72 +1,727 @@
-use std::collections::HashMap;
-use std::rc::Rc;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::rc::{Rc, Weak};
 
 pub struct DDNNF {
     pub root_node: Rc<DDNNFNode>,
     pub number_variables: u32,
 }
 
+/// Drops the nodes one after another instead of recursively, so dropping deep circuits does not
+/// overflow the call stack. Nodes that are still used elsewhere are kept.
+impl Drop for DDNNF {
+    fn drop(&mut self) {
+        let mut stack = vec![std::mem::replace(
+            &mut self.root_node,
+            Rc::new(DDNNFNode::TrueLeave),
+        )];
+        while let Some(node) = stack.pop() {
+            if let Ok(
+                DDNNFNode::AndNode(mut child_list, _) | DDNNFNode::OrNode(mut child_list, _),
+            ) = Rc::try_unwrap(node)
+            {
+                stack.append(&mut child_list);
+            }
+        }
+    }
+}
+
+impl DDNNF {
+    /// Calculates the weighted model count of the circuit. The weight of a model is the product of
+    /// the weights of its literals and the weighted model count is the sum over all models.
+    /// The circuit is smoothed on the fly: a variable that does not occur in a child of an or node
+    /// (or in the whole circuit) contributes the sum of both of its literal weights.
+    /// # Arguments
+    /// * `weights` - The weight of each literal as (variable_index, sign). The variable index is the
+    ///   internal index starting at 0. Literals without a weight have the weight 1.0.
+    pub fn weighted_count(&self, weights: &HashMap<(u32, bool), f64>) -> f64 {
+        let weight = |index: u32, positive: bool| *weights.get(&(index, positive)).unwrap_or(&1.0);
+        let free_weight = |scope: &BTreeSet<u32>, child_scope: &BTreeSet<u32>| {
+            scope
+                .difference(child_scope)
+                .map(|v| weight(*v, true) + weight(*v, false))
+                .product::<f64>()
+        };
+
+        let mut results: HashMap<*const DDNNFNode, (f64, BTreeSet<u32>)> = HashMap::new();
+        for node in self.nodes() {
+            let result = match node {
+                DDNNFNode::TrueLeave => (1.0, BTreeSet::new()),
+                DDNNFNode::FalseLeave => (0.0, BTreeSet::new()),
+                DDNNFNode::LiteralLeave(literal) => (
+                    weight(literal.index, literal.positive),
+                    BTreeSet::from([literal.index]),
+                ),
+                DDNNFNode::AndNode(child_list, _) => {
+                    let mut value = 1.0;
+                    let mut scope = BTreeSet::new();
+                    for child in child_list {
+                        let (child_value, child_scope) = &results[&Rc::as_ptr(child)];
+                        value *= child_value;
+                        scope.extend(child_scope);
+                    }
+                    (value, scope)
+                }
+                DDNNFNode::OrNode(child_list, _) => {
+                    let mut scope = BTreeSet::new();
+                    for child in child_list {
+                        scope.extend(&results[&Rc::as_ptr(child)].1);
+                    }
+                    let value = child_list
+                        .iter()
+                        .map(|child| {
+                            let (child_value, child_scope) = &results[&Rc::as_ptr(child)];
+                            child_value * free_weight(&scope, child_scope)
+                        })
+                        .sum();
+                    (value, scope)
+                }
+            };
+            results.insert(node as *const DDNNFNode, result);
+        }
+
+        let (value, scope) = &results[&Rc::as_ptr(&self.root_node)];
+        let all_variables = (0..self.number_variables).collect();
+        value * free_weight(&all_variables, scope)
+    }
+
+    /// Calculates the model count of the circuit independently of the count of the solver, e.g. to
+    /// verify it. The circuit is copied into an arena first, see `ArenaDDNNF::model_count`.
+    pub fn model_count(&self) -> BigUint {
+        self.to_arena().model_count()
+    }
+
+    /// Conditions the circuit on a literal: the models of the result are the models of this circuit
+    /// in which the literal is true. The leaves of the literal become true and the leaves of the
+    /// opposite literal false, the constants are then propagated to the inner nodes. The literal
+    /// itself is added to the root, so `model_count` of the result counts the models with the
+    /// literal and every other variable, even if it does not occur in the circuit, is still
+    /// counted.
+    /// # Arguments
+    /// * `literal` - The variable (index starting at 0) and its value
+    /// # Returns
+    /// The conditioned circuit, which shares no nodes with this circuit
+    /// # Panics
+    /// Panics if the circuit has no such variable
+    pub fn conditioned(&self, literal: (u32, bool)) -> DDNNF {
+        let (variable_index, positive) = literal;
+        assert!(
+            variable_index < self.number_variables,
+            "unknown variable {variable_index}"
+        );
+        let true_leave = Rc::new(DDNNFNode::TrueLeave);
+        let false_leave = Rc::new(DDNNFNode::FalseLeave);
+        let mut next_id = 0;
+        let mut conditioned_nodes: HashMap<*const DDNNFNode, Rc<DDNNFNode>> = HashMap::new();
+        for node in self.nodes() {
+            let conditioned_node = match node {
+                DDNNFNode::TrueLeave => Rc::clone(&true_leave),
+                DDNNFNode::FalseLeave => Rc::clone(&false_leave),
+                DDNNFNode::LiteralLeave(leave_literal) if leave_literal.index == variable_index => {
+                    if leave_literal.positive == positive {
+                        Rc::clone(&true_leave)
+                    } else {
+                        Rc::clone(&false_leave)
+                    }
+                }
+                DDNNFNode::LiteralLeave(leave_literal) => {
+                    Rc::new(DDNNFNode::LiteralLeave(Rc::clone(leave_literal)))
+                }
+                DDNNFNode::AndNode(child_list, _) => {
+                    let children: Vec<Rc<DDNNFNode>> = child_list
+                        .iter()
+                        .map(|child| Rc::clone(&conditioned_nodes[&Rc::as_ptr(child)]))
+                        .filter(|child| !matches!(**child, DDNNFNode::TrueLeave))
+                        .collect();
+                    if children
+                        .iter()
+                        .any(|child| matches!(**child, DDNNFNode::FalseLeave))
+                    {
+                        Rc::clone(&false_leave)
+                    } else if children.is_empty() {
+                        Rc::clone(&true_leave)
+                    } else if children.len() == 1 {
+                        Rc::clone(&children[0])
+                    } else {
+                        next_id += 1;
+                        Rc::new(DDNNFNode::AndNode(children, next_id - 1))
+                    }
+                }
+                DDNNFNode::OrNode(child_list, _) => {
+                    // the variables of a removed child are free in the remaining models of the
+                    // parent either way, so removing them from the scope keeps the count
+                    let children: Vec<Rc<DDNNFNode>> = child_list
+                        .iter()
+                        .map(|child| Rc::clone(&conditioned_nodes[&Rc::as_ptr(child)]))
+                        .filter(|child| !matches!(**child, DDNNFNode::FalseLeave))
+                        .collect();
+                    if children.is_empty() {
+                        Rc::clone(&false_leave)
+                    } else if children.len() == 1 {
+                        Rc::clone(&children[0])
+                    } else {
+                        next_id += 1;
+                        Rc::new(DDNNFNode::OrNode(children, next_id - 1))
+                    }
+                }
+            };
+            conditioned_nodes.insert(node as *const DDNNFNode, conditioned_node);
+        }
+
+        let root_node = Rc::clone(&conditioned_nodes[&Rc::as_ptr(&self.root_node)]);
+        let literal_leave = Rc::new(DDNNFNode::LiteralLeave(Rc::new(DDNNFLiteral {
+            index: variable_index,
+            positive,
+        })));
+        // a literal is only printed as a child of another node, so it is never the root
+        let root_node = match *root_node {
+            DDNNFNode::FalseLeave => root_node,
+            DDNNFNode::TrueLeave => Rc::new(DDNNFNode::AndNode(vec![literal_leave], next_id)),
+            _ => Rc::new(DDNNFNode::AndNode(vec![root_node, literal_leave], next_id)),
+        };
+        DDNNF {
+            root_node,
+            number_variables: self.number_variables,
+        }
+    }
+
+    /// Checks that the circuit is a d-DNNF. The children of an and node must not share a variable
+    /// (decomposability) and every two children of an or node must contain contradicting literals
+    /// that are true in all of their models (determinism). Determinism is only checked with these
+    /// literals, which is how the solver creates or nodes.
+    /// # Returns
+    /// An error that describes the first node that violates one of the properties
+    pub fn validate(&self) -> Result<(), String> {
+        // the literals that are true in all models of a node, None if the node has no model
+        let mut implied_literals: HashMap<*const DDNNFNode, Option<BTreeSet<(u32, bool)>>> =
+            HashMap::new();
+        let mut scopes: HashMap<*const DDNNFNode, BTreeSet<u32>> = HashMap::new();
+        for node in self.nodes() {
+            let (scope, implied) = match node {
+                DDNNFNode::TrueLeave => (BTreeSet::new(), Some(BTreeSet::new())),
+                DDNNFNode::FalseLeave => (BTreeSet::new(), None),
+                DDNNFNode::LiteralLeave(literal) => (
+                    BTreeSet::from([literal.index]),
+                    Some(BTreeSet::from([(literal.index, literal.positive)])),
+                ),
+                DDNNFNode::AndNode(child_list, node_id) => {
+                    let mut scope = BTreeSet::new();
+                    let mut implied = Some(BTreeSet::new());
+                    for child in child_list {
+                        let child_scope = &scopes[&Rc::as_ptr(child)];
+                        if let Some(variable) = child_scope.intersection(&scope).next() {
+                            return Err(format!(
+                                "the children of the and node {} share the variable {}",
+                                node_id,
+                                variable + 1
+                            ));
+                        }
+                        scope.extend(child_scope);
+                        implied = match (implied, &implied_literals[&Rc::as_ptr(child)]) {
+                            (Some(mut implied), Some(child_implied)) => {
+                                implied.extend(child_implied);
+                                Some(implied)
+                            }
+                            _ => None,
+                        };
+                    }
+                    (scope, implied)
+                }
+                DDNNFNode::OrNode(child_list, node_id) => {
+                    let mut scope = BTreeSet::new();
+                    let mut implied: Option<BTreeSet<(u32, bool)>> = None;
+                    for (position, child) in child_list.iter().enumerate() {
+                        scope.extend(&scopes[&Rc::as_ptr(child)]);
+                        let Some(child_implied) = &implied_literals[&Rc::as_ptr(child)] else {
+                            continue;
+                        };
+                        for (other_position, other_child) in
+                            child_list[..position].iter().enumerate()
+                        {
+                            if let Some(other_implied) = &implied_literals[&Rc::as_ptr(other_child)]
+                            {
+                                if !child_implied.iter().any(|(index, positive)| {
+                                    other_implied.contains(&(*index, !positive))
+                                }) {
+                                    return Err(format!(
+                                        "the children {} and {} of the or node {} are not disjoint",
+                                        other_position, position, node_id
+                                    ));
+                                }
+                            }
+                        }
+                        implied = Some(match implied {
+                            None => child_implied.clone(),
+                            Some(implied) => implied.intersection(child_implied).copied().collect(),
+                        });
+                    }
+                    (scope, implied)
+                }
+            };
+            scopes.insert(node as *const DDNNFNode, scope);
+            implied_literals.insert(node as *const DDNNFNode, implied);
+        }
+        Ok(())
+    }
+
+    /// Counts the distinct nodes and edges of the circuit. Shared nodes and their edges are counted
+    /// once.
+    pub fn size(&self) -> DDNNFSize {
+        let mut size = DDNNFSize {
+            and_nodes: 0,
+            or_nodes: 0,
+            leaves: 0,
+            edges: 0,
+        };
+        for node in self.nodes() {
+            match node {
+                DDNNFNode::TrueLeave | DDNNFNode::FalseLeave | DDNNFNode::LiteralLeave(_) => {
+                    size.leaves += 1
+                }
+                DDNNFNode::AndNode(child_list, _) => {
+                    size.and_nodes += 1;
+                    size.edges += child_list.len();
+                }
+                DDNNFNode::OrNode(child_list, _) => {
+                    size.or_nodes += 1;
+                    size.edges += child_list.len();
+                }
+            }
+        }
+        size
+    }
+
+    /// Iterates over the distinct nodes of the circuit in a topological order: every node is yielded
+    /// once and after all of its children, the root node last. Shared nodes are detected by their
+    /// address. The order only depends on the order of the children, so it is the same for every
+    /// call.
+    pub fn nodes(&self) -> impl Iterator<Item = &DDNNFNode> {
+        self.post_order().into_iter()
+    }
+
+    /// Returns the number of distinct nodes of the circuit. Shared nodes are counted once.
+    pub fn node_count(&self) -> usize {
+        self.post_order().len()
+    }
+
+    /// Returns one model of the circuit. The first satisfiable child of every or node and all
+    /// children of every and node are followed, variables that do not occur on this path are set
+    /// to false.
+    /// # Returns
+    /// The value of every variable (index starting at 0) or None if the circuit has no model
+    pub fn witness(&self) -> Option<Vec<(u32, bool)>> {
+        let mut satisfiable: HashMap<*const DDNNFNode, bool> = HashMap::new();
+        for node in self.nodes() {
+            let node_satisfiable = match node {
+                DDNNFNode::TrueLeave | DDNNFNode::LiteralLeave(_) => true,
+                DDNNFNode::FalseLeave => false,
+                DDNNFNode::AndNode(child_list, _) => child_list
+                    .iter()
+                    .all(|child| satisfiable[&Rc::as_ptr(child)]),
+                DDNNFNode::OrNode(child_list, _) => child_list
+                    .iter()
+                    .any(|child| satisfiable[&Rc::as_ptr(child)]),
+            };
+            satisfiable.insert(node as *const DDNNFNode, node_satisfiable);
+        }
+        if !satisfiable[&Rc::as_ptr(&self.root_node)] {
+            return None;
+        }
+
+        let mut assignment = vec![false; self.number_variables as usize];
+        let mut stack: Vec<&DDNNFNode> = vec![&self.root_node];
+        while let Some(node) = stack.pop() {
+            match node {
+                DDNNFNode::LiteralLeave(literal) => {
+                    assignment[literal.index as usize] = literal.positive;
+                }
+                DDNNFNode::AndNode(child_list, _) => {
+                    stack.extend(child_list.iter().map(|child| child.as_ref()));
+                }
+                DDNNFNode::OrNode(child_list, _) => {
+                    let child = child_list
+                        .iter()
+                        .find(|child| satisfiable[&Rc::as_ptr(child)])
+                        .unwrap();
+                    stack.push(child);
+                }
+                DDNNFNode::TrueLeave | DDNNFNode::FalseLeave => {}
+            }
+        }
+        Some(
+            assignment
+                .into_iter()
+                .enumerate()
+                .map(|(index, value)| (index as u32, value))
+                .collect(),
+        )
+    }
+
+    /// Creates an equivalent circuit that only depends on the structure of this circuit and not on
+    /// the order in which the solver created the nodes. Structurally equal subcircuits are merged
+    /// into one node, nested and nodes (or nodes) are merged into their parent, the children of
+    /// every node are sorted by a hash of their structure and the ids of the nodes are assigned in
+    /// post-order. Printing the result is therefore identical for circuits that only differ in the
+    /// order of the children, the sharing of nodes or the ids.
+    pub fn canonicalize(&self) -> DDNNF {
+        #[derive(Clone, Eq, Hash, PartialEq)]
+        enum Shape {
+            True,
+            False,
+            Literal(u32, bool),
+            And(Vec<usize>),
+            Or(Vec<usize>),
+        }
+
+        // every distinct structure gets a class, the children of a class are sorted by their hash
+        let mut shapes: Vec<Shape> = Vec::new();
+        let mut hashes: Vec<u64> = Vec::new();
+        let mut class_of_shape: HashMap<Shape, usize> = HashMap::new();
+        let mut class_of_node: HashMap<*const DDNNFNode, usize> = HashMap::new();
+        for node in self.nodes() {
+            // nested and nodes (or nodes) are merged into their parent
+            let merged_classes = |child_list: &Vec<Rc<DDNNFNode>>, and: bool| {
+                let mut classes = Vec::new();
+                for child in child_list {
+                    let class = class_of_node[&Rc::as_ptr(child)];
+                    match &shapes[class] {
+                        Shape::And(grandchildren) if and => classes.extend(grandchildren),
+                        Shape::Or(grandchildren) if !and => classes.extend(grandchildren),
+                        Shape::True if and => {}
+                        Shape::False if !and => {}
+                        _ => classes.push(class),
+                    }
+                }
+                classes.sort_by_key(|class| (hashes[*class], *class));
+                classes
+            };
+            let shape = match node {
+                DDNNFNode::TrueLeave => Shape::True,
+                DDNNFNode::FalseLeave => Shape::False,
+                DDNNFNode::LiteralLeave(literal) => Shape::Literal(literal.index, literal.positive),
+                DDNNFNode::AndNode(child_list, _) => {
+                    let classes = merged_classes(child_list, true);
+                    if classes.iter().any(|class| shapes[*class] == Shape::False) {
+                        Shape::False
+                    } else {
+                        Shape::And(classes)
+                    }
+                }
+                DDNNFNode::OrNode(child_list, _) => Shape::Or(merged_classes(child_list, false)),
+            };
+            let class = match class_of_shape.get(&shape) {
+                Some(class) => *class,
+                None => {
+                    let mut hasher = DefaultHasher::new();
+                    match &shape {
+                        Shape::True => 0u8.hash(&mut hasher),
+                        Shape::False => 1u8.hash(&mut hasher),
+                        Shape::Literal(index, positive) => (2u8, index, positive).hash(&mut hasher),
+                        Shape::And(classes) | Shape::Or(classes) => {
+                            (if matches!(shape, Shape::And(_)) {
+                                3u8
+                            } else {
+                                4u8
+                            })
+                            .hash(&mut hasher);
+                            for class in classes {
+                                hashes[*class].hash(&mut hasher);
+                            }
+                        }
+                    }
+                    hashes.push(hasher.finish());
+                    shapes.push(shape.clone());
+                    class_of_shape.insert(shape, shapes.len() - 1);
+                    shapes.len() - 1
+                }
+            };
+            class_of_node.insert(node as *const DDNNFNode, class);
+        }
+
+        let mut nodes: Vec<Option<Rc<DDNNFNode>>> = vec![None; shapes.len()];
+        let mut next_id = 0;
+        let mut stack = vec![(class_of_node[&Rc::as_ptr(&self.root_node)], false)];
+        while let Some((class, children_done)) = stack.pop() {
+            if nodes[class].is_some() {
+                continue;
+            }
+            let node = match &shapes[class] {
+                Shape::True => DDNNFNode::TrueLeave,
+                Shape::False => DDNNFNode::FalseLeave,
+                Shape::Literal(index, positive) => DDNNFNode::LiteralLeave(Rc::new(DDNNFLiteral {
+                    index: *index,
+                    positive: *positive,
+                })),
+                Shape::And(classes) | Shape::Or(classes) => {
+                    if !children_done {
+                        stack.push((class, true));
+                        for child_class in classes.iter().rev() {
+                            stack.push((*child_class, false));
+                        }
+                        continue;
+                    }
+                    let child_list = classes
+                        .iter()
+                        .map(|child_class| nodes[*child_class].clone().unwrap())
+                        .collect();
+                    next_id += 1;
+                    if matches!(shapes[class], Shape::And(_)) {
+                        DDNNFNode::AndNode(child_list, next_id - 1)
+                    } else {
+                        DDNNFNode::OrNode(child_list, next_id - 1)
+                    }
+                }
+            };
+            nodes[class] = Some(Rc::new(node));
+        }
+
+        DDNNF {
+            root_node: nodes[class_of_node[&Rc::as_ptr(&self.root_node)]]
+                .clone()
+                .unwrap(),
+            number_variables: self.number_variables,
+        }
+    }
+
+    /// Creates a representation of the circuit in the DOT language of Graphviz, see
+    /// `ArenaDDNNF::to_dot`.
+    pub fn to_dot(&self) -> String {
+        self.to_arena().to_dot()
+    }
+
+    /// Copies the circuit into an arena, in which the nodes are stored in post-order in one
+    /// vector and children are referenced by their position. Shared nodes are copied once and the
+    /// positions are the same as in `to_dot` and `to_json`.
+    pub fn to_arena(&self) -> ArenaDDNNF {
+        let nodes = self.post_order();
+        let ids = Self::node_ids(&nodes);
+        let mut arena = ArenaDDNNF {
+            nodes: Vec::with_capacity(nodes.len()),
+            children: Vec::new(),
+            number_variables: self.number_variables,
+        };
+        for node in nodes {
+            let arena_node = match node {
+                DDNNFNode::TrueLeave => ArenaDDNNFNode::TrueLeave,
+                DDNNFNode::FalseLeave => ArenaDDNNFNode::FalseLeave,
+                DDNNFNode::LiteralLeave(literal) => {
+                    ArenaDDNNFNode::LiteralLeave(literal.index, literal.positive)
+                }
+                DDNNFNode::AndNode(child_list, _) | DDNNFNode::OrNode(child_list, _) => {
+                    let first_child = arena.children.len() as u32;
+                    arena.children.extend(
+                        child_list
+                            .iter()
+                            .map(|child| ids[&Rc::as_ptr(child)] as u32),
+                    );
+                    let children = (first_child, arena.children.len() as u32);
+                    if matches!(node, DDNNFNode::AndNode(_, _)) {
+                        ArenaDDNNFNode::AndNode(children.0, children.1)
+                    } else {
+                        ArenaDDNNFNode::OrNode(children.0, children.1)
+                    }
+                }
+            };
+            arena.nodes.push(arena_node);
+        }
+        arena
+    }
+
+    /// Creates a JSON representation of the circuit. Every distinct node is listed once with its
+    /// type and either its literal (in the d4 notation) or the ids of its children.
+    pub fn to_json(&self) -> String {
+        let nodes = self.post_order();
+        let ids = Self::node_ids(&nodes);
+        let node_strings: Vec<String> = nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| match node {
+                DDNNFNode::TrueLeave => format!("{{\"id\":{},\"type\":\"true\"}}", id),
+                DDNNFNode::FalseLeave => format!("{{\"id\":{},\"type\":\"false\"}}", id),
+                DDNNFNode::LiteralLeave(literal) => format!(
+                    "{{\"id\":{},\"type\":\"literal\",\"literal\":{}}}",
+                    id,
+                    Self::literal_to_string(literal)
+                ),
+                DDNNFNode::AndNode(child_list, _) | DDNNFNode::OrNode(child_list, _) => {
+                    let children: Vec<String> = child_list
+                        .iter()
+                        .map(|child| ids[&Rc::as_ptr(child)].to_string())
+                        .collect();
+                    format!(
+                        "{{\"id\":{},\"type\":\"{}\",\"children\":[{}]}}",
+                        id,
+                        if matches!(node, DDNNFNode::AndNode(_, _)) {
+                            "and"
+                        } else {
+                            "or"
+                        },
+                        children.join(",")
+                    )
+                }
+            })
+            .collect();
+        format!(
+            "{{\"number_variables\":{},\"root\":{},\"nodes\":[{}]}}",
+            self.number_variables,
+            nodes.len() - 1,
+            node_strings.join(",")
+        )
+    }
+
+    /// Copies the circuit into a representation without reference counting that can be sent to
+    /// another thread. Shared nodes are copied once.
+    #[cfg(feature = "parallel")]
+    pub fn to_flat(&self) -> FlatDDNNF {
+        let mut flat_ddnnf = FlatDDNNF::new();
+        flat_ddnnf.add_node(&self.root_node, &mut HashMap::new());
+        flat_ddnnf
+    }
+
+    fn node_ids(nodes: &[&DDNNFNode]) -> HashMap<*const DDNNFNode, usize> {
+        nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| (*node as *const DDNNFNode, id))
+            .collect()
+    }
+
+    fn literal_to_string(literal: &DDNNFLiteral) -> String {
+        format!(
+            "{}{}",
+            if literal.positive { "" } else { "-" },
+            literal.index + 1
+        )
+    }
+
+    /// Returns every distinct node of the circuit exactly once with all children of a node
+    /// appearing before the node itself. Shared nodes are detected by their address.
+    fn post_order(&self) -> Vec<&DDNNFNode> {
+        let mut result = Vec::new();
+        let mut visited: HashSet<*const DDNNFNode> = HashSet::new();
+        let mut stack: Vec<(&DDNNFNode, bool)> = vec![(&self.root_node, false)];
+        while let Some((node, children_done)) = stack.pop() {
+            if children_done {
+                result.push(node);
+                continue;
+            }
+            if !visited.insert(node as *const DDNNFNode) {
+                continue;
+            }
+            stack.push((node, true));
+            if let DDNNFNode::AndNode(child_list, _) | DDNNFNode::OrNode(child_list, _) = node {
+                for child in child_list.iter().rev() {
+                    if !visited.contains(&Rc::as_ptr(child)) {
+                        stack.push((child, false));
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+/// A node that still has to be printed with the id of its parent and the literals on the edge
+type PrintTask<'a> = (&'a DDNNFNode, u32, Vec<(u32, bool)>);
+
 pub struct DDNNFPrinter {
     pub(crate) ddnnf: DDNNF,
     pub(crate) true_sink_id: Option<u32>,
     pub(crate) false_sink_id: Option<u32>,
     pub(crate) current_node_id: u32,
     pub(crate) id_map: HashMap<u32, u32>,
-    pub edge_counter: u32,
-    pub(crate) node_counter: u32,
+}
+
+/// The number of distinct nodes and edges of a circuit
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DDNNFSize {
+    pub and_nodes: usize,
+    pub or_nodes: usize,
+    /// True, false and literal leaves
+    pub leaves: usize,
+    /// The children of all and and or nodes
+    pub edges: usize,
 }
 
 impl DDNNFPrinter {
-    pub fn print(&mut self) -> String {
-        let mut result_string = String::new();
+    pub fn new(ddnnf: DDNNF) -> DDNNFPrinter {
+        DDNNFPrinter {
+            ddnnf,
+            true_sink_id: None,
+            false_sink_id: None,
+            current_node_id: 0,
+            id_map: HashMap::new(),
+        }
+    }
+
+    /// Creates the representation of the circuit in the d4 format.
+    /// # Returns
+    /// The circuit in the d4 format and the size of the circuit. The size counts the nodes of the
+    /// circuit and not the lines of the output, as the printer moves literals onto the edges.
+    pub fn print(&mut self) -> (String, DDNNFSize) {
+        let mut output = Vec::new();
+        let size = self
+            .write_to(&mut output)
+            .expect("writing to a vector cannot fail");
+        (
+            String::from_utf8(output).expect("the d4 format only contains ASCII"),
+            size,
+        )
+    }
+
+    /// Writes the circuit in the d4 format line by line, so the whole output never has to be kept
+    /// in memory. The output is identical to the one of `print`.
+    /// # Arguments
+    /// * `output` - The writer that receives the lines, e.g. a buffered file
+    /// # Returns
+    /// The size of the circuit (see `print`) or the first error of the writer
+    pub fn write_to<W: Write>(&mut self, output: &mut W) -> std::io::Result<DDNNFSize> {
+        let size = self.ddnnf.size();
         let root_node = &self.ddnnf.root_node.clone();
         if let DDNNFNode::FalseLeave = **root_node {
-            //result_string.push_str(&format!("nnf {} {} {}\n", 2, 1, self.ddnnf.number_variables));
-            self.node_counter += 2;
-            result_string.push_str("o 1 0\n");
-            result_string.push_str("f 2 0\n");
-            result_string.push_str("1 2 1 0\n");
+            writeln!(output, "o 1 0")?;
+            writeln!(output, "f 2 0")?;
+            writeln!(output, "1 2 1 0")?;
         } else {
-            let empty_vec: Vec<(u32, bool)> = Vec::new();
-            let result = self.print_node(root_node, 0, empty_vec);
-            result_string.push_str(&*result);
-            //TODO header: result_string.insert_str(0,&format!("nnf {} {} {}\n", self.current_node_id, self.edge_counter, self.ddnnf.number_variables));
+            // the nodes are printed in pre-order with an explicit stack instead of recursion, so
+            // deep circuits do not overflow the call stack
+            let mut stack: Vec<PrintTask> = vec![(root_node, 0, Vec::new())];
+            while let Some((node, parent_id, implied_literals)) = stack.pop() {
+                self.print_node(node, parent_id, implied_literals, output, &mut stack)?;
+            }
         }
-        println!("number_nodes: {}", self.node_counter);
-        result_string
+        Ok(size)
     }
 
-    fn print_node(
+    /// Prints a node and the edge from its parent and pushes the children that still have to be
+    /// printed onto the stack. The children are pushed in reverse order, so they are printed in
+    /// their order, and each child with its descendants before the next child.
+    fn print_node<'a, W: Write>(
         &mut self,
-        node: &DDNNFNode,
+        node: &'a DDNNFNode,
         parent_id: u32,
         implied_literals: Vec<(u32, bool)>,
-    ) -> String {
-        let mut result_string = String::new();
+        output: &mut W,
+        stack: &mut Vec<PrintTask<'a>>,
+    ) -> std::io::Result<()> {
         match node {
             DDNNFNode::TrueLeave => {
                 if self.true_sink_id.is_none() {
                     let id = self.current_node_id + 1;
                     self.current_node_id = id;
                     self.true_sink_id = Some(id);
-                    result_string.push_str(&format!("t {} 0\n", id));
+                    writeln!(output, "t {} 0", id)?;
                 }
                 if parent_id > 0 {
-                    result_string.push_str(&format!(
-                        "{} {} ",
-                        parent_id,
-                        self.true_sink_id.unwrap()
-                    ));
+                    write!(output, "{} {} ", parent_id, self.true_sink_id.unwrap())?;
                     for (id, sign) in &implied_literals {
-                        result_string.push_str(&format!(
-                            "{}{} ",
-                            if *sign { "" } else { "-" },
-                            *id
-                        ));
+                        write!(output, "{}{} ", if *sign { "" } else { "-" }, *id)?;
                     }
-                    result_string.push_str(&format!("0\n"));
-                    self.edge_counter += 1;
-                    self.node_counter += 1;
+                    writeln!(output, "0")?;
                 }
             }
             DDNNFNode::FalseLeave => {
@@ -74,32 +729,44 @@ impl DDNNFPrinter {
                     let id = self.current_node_id + 1;
                     self.current_node_id = id;
                     self.false_sink_id = Some(id);
-                    result_string.push_str(&format!("f {} 0\n", id));
-                    self.node_counter += 1;
+                    writeln!(output, "f {} 0", id)?;
                 }
                 if parent_id > 0 {
-                    result_string.push_str(&format!(
-                        "{} {} 0\n",
-                        parent_id,
-                        self.false_sink_id.unwrap()
-                    ));
-                    self.edge_counter += 1;
-                    self.node_counter += 1;
+                    writeln!(output, "{} {} 0", parent_id, self.false_sink_id.unwrap())?;
                 }
             }
-            DDNNFNode::LiteralLeave(_) => {
-                panic!("unreachable code");
+            DDNNFNode::LiteralLeave(literal_node) => {
+                // only the children of or nodes are printed on their own, as an edge to the true sink
+                assert_ne!(
+                    parent_id, 0,
+                    "a literal is only printed as a child of an or node"
+                );
+                if self.true_sink_id.is_none() {
+                    self.true_sink_id = Some(self.current_node_id + 1);
+                    self.current_node_id = self.true_sink_id.unwrap();
+                    writeln!(output, "t {} 0", self.true_sink_id.unwrap())?;
+                }
+                write!(output, "{} {} ", parent_id, self.true_sink_id.unwrap())?;
+                write!(
+                    output,
+                    "{}{} ",
+                    if literal_node.positive { "" } else { "-" },
+                    literal_node.index + 1
+                )?;
+                for (index, positive) in &implied_literals {
+                    write!(output, "{}{} ", if *positive { "" } else { "-" }, *index)?;
+                }
+                writeln!(output, "0")?;
             }
             DDNNFNode::AndNode(child_list, node_id) => {
                 let map_entry = self.id_map.get(node_id);
                 if let Some(existing_id) = map_entry {
-                    result_string.push_str(&format!("{} {} ", parent_id, existing_id));
+                    write!(output, "{} {} ", parent_id, existing_id)?;
                     for (id, sign) in implied_literals {
-                        result_string.push_str(&format!("{}{} ", if sign { "" } else { "-" }, id));
+                        write!(output, "{}{} ", if sign { "" } else { "-" }, id)?;
                     }
-                    result_string.push_str(&format!("0\n"));
-                    self.edge_counter += 1;
-                    return result_string;
+                    writeln!(output, "0")?;
+                    return Ok(());
                 }
                 let mut non_literal_children_counter = 0;
                 let mut local_implied_literals: Vec<(u32, bool)> = Vec::new();
@@ -114,29 +781,24 @@ impl DDNNFPrinter {
                     if self.true_sink_id.is_none() {
                         self.true_sink_id = Some(self.current_node_id + 1);
                         self.current_node_id = self.true_sink_id.unwrap();
-                        result_string.push_str(&format!("t {} 0\n", self.true_sink_id.unwrap()));
-                        self.node_counter += 1;
+                        writeln!(output, "t {} 0", self.true_sink_id.unwrap())?;
                     }
                     if parent_id == 0 {
                         let id = self.current_node_id + 1;
                         self.current_node_id = id;
                         self.id_map.insert(*node_id, id);
-                        result_string.push_str(&format!("a {} 0\n", id));
-                        result_string.push_str(&format!("{} {} ", id, self.true_sink_id.unwrap()));
+                        writeln!(output, "a {} 0", id)?;
+                        write!(output, "{} {} ", id, self.true_sink_id.unwrap())?;
                     } else {
-                        result_string.push_str(&format!(
-                            "{} {} ",
-                            parent_id,
-                            self.true_sink_id.unwrap()
-                        ));
+                        write!(output, "{} {} ", parent_id, self.true_sink_id.unwrap())?;
                     }
                     for (id, sign) in local_implied_literals {
-                        result_string.push_str(&format!("{}{} ", if sign { "" } else { "-" }, id));
+                        write!(output, "{}{} ", if sign { "" } else { "-" }, id)?;
                     }
                     for (id, sign) in implied_literals {
-                        result_string.push_str(&format!("{}{} ", if sign { "" } else { "-" }, id));
+                        write!(output, "{}{} ", if sign { "" } else { "-" }, id)?;
                     }
-                    result_string.push_str(&format!("0\n"));
+                    writeln!(output, "0")?;
                 } else if non_literal_children_counter == 1 {
                     let mut tmp_id = parent_id;
                     if parent_id == 0 {
@@ -144,39 +806,31 @@ impl DDNNFPrinter {
                         self.current_node_id = id;
                         self.id_map.insert(*node_id, id);
                         tmp_id = id;
-                        result_string.push_str(&format!("a {} 0\n", id));
+                        writeln!(output, "a {} 0", id)?;
                     }
-                    for child_node in child_list {
+                    for child_node in child_list.iter().rev() {
                         if !matches!(**child_node, DDNNFNode::LiteralLeave(_)) {
                             let mut combined = implied_literals.clone();
                             combined.extend(local_implied_literals.iter());
-                            result_string.push_str(&self.print_node(child_node, tmp_id, combined));
+                            stack.push((child_node, tmp_id, combined));
                         }
                     }
                 } else {
                     let id = self.current_node_id + 1;
                     self.current_node_id = id;
                     self.id_map.insert(*node_id, id);
-                    result_string.push_str(&format!("a {} 0\n", id));
+                    writeln!(output, "a {} 0", id)?;
                     if parent_id != 0 {
-                        result_string.push_str(&format!("{} {} ", parent_id, id));
+                        write!(output, "{} {} ", parent_id, id)?;
                         for (id, sign) in &implied_literals {
-                            result_string.push_str(&format!(
-                                "{}{} ",
-                                if *sign { "" } else { "-" },
-                                *id
-                            ));
+                            write!(output, "{}{} ", if *sign { "" } else { "-" }, *id)?;
                         }
-                        result_string.push_str(&format!("0\n"));
+                        writeln!(output, "0")?;
                     }
 
-                    for child_node in child_list {
+                    for child_node in child_list.iter().rev() {
                         if !matches!(**child_node, DDNNFNode::LiteralLeave(_)) {
-                            result_string.push_str(&self.print_node(
-                                child_node,
-                                id,
-                                local_implied_literals.clone(),
-                            ));
+                            stack.push((child_node, id, local_implied_literals.clone()));
                         }
                     }
                 }
@@ -184,67 +838,36 @@ impl DDNNFPrinter {
             DDNNFNode::OrNode(child_list, node_id) => {
                 let map_entry = self.id_map.get(node_id);
                 if let Some(existing_id) = map_entry {
-                    result_string.push_str(&format!("{} {} ", parent_id, existing_id));
+                    write!(output, "{} {} ", parent_id, existing_id)?;
                     for (id, sign) in implied_literals {
-                        result_string.push_str(&format!("{}{} ", if sign { "" } else { "-" }, id));
+                        write!(output, "{}{} ", if sign { "" } else { "-" }, id)?;
                     }
-                    result_string.push_str(&format!("0\n"));
-                    self.edge_counter += 1;
-                    return result_string;
+                    writeln!(output, "0")?;
+                    return Ok(());
                 }
                 let id = self.current_node_id + 1;
                 self.current_node_id = id;
                 self.id_map.insert(*node_id, id);
-                result_string.push_str(&format!("o {} 0\n", id));
+                writeln!(output, "o {} 0", id)?;
                 let mut local_implied_literals: Vec<(u32, bool)> = Vec::new();
                 if parent_id != 0 {
-                    result_string.push_str(&format!("{} {} ", parent_id, id));
+                    write!(output, "{} {} ", parent_id, id)?;
                     for (id, sign) in &implied_literals {
-                        result_string.push_str(&format!(
-                            "{}{} ",
-                            if *sign { "" } else { "-" },
-                            *id
-                        ));
+                        write!(output, "{}{} ", if *sign { "" } else { "-" }, *id)?;
                     }
-                    result_string.push_str(&format!("0\n"));
+                    writeln!(output, "0")?;
                 } else {
                     local_implied_literals = implied_literals.clone();
                 }
 
-                for child_node in &*child_list {
-                    if let DDNNFNode::LiteralLeave(ref literal_node) = **child_node {
-                        if self.true_sink_id.is_none() {
-                            self.true_sink_id = Some(self.current_node_id + 1);
-                            self.current_node_id = self.true_sink_id.unwrap();
-                            result_string
-                                .push_str(&format!("t {} 0\n", self.true_sink_id.unwrap()));
-                            self.node_counter += 1;
-                        }
-                        result_string.push_str(&format!("{} {} ", id, self.true_sink_id.unwrap()));
-                        result_string.push_str(&format!(
-                            "{}{} ",
-                            if literal_node.positive { "" } else { "-" },
-                            literal_node.index + 1
-                        ));
-                        for (index, positive) in &local_implied_literals {
-                            result_string.push_str(&format!(
-                                "{}{} ",
-                                if *positive { "" } else { "-" },
-                                *index
-                            ));
-                        }
-                        result_string.push_str(&format!("0\n"));
-                    } else {
-                        result_string.push_str(&self.print_node(
-                            child_node,
-                            id,
-                            local_implied_literals.clone(),
-                        ));
-                    }
+                // the literal children are printed in between the other children, so they are
+                // pushed as well
+                for child_node in child_list.iter().rev() {
+                    stack.push((child_node, id, local_implied_literals.clone()));
                 }
             }
         }
-        result_string
+        Ok(())
     }
 }
 
@@ -262,3 +885,702 @@ pub struct DDNNFLiteral {
     pub index: u32,
     pub positive: bool,
 }
+
+/// Creates the nodes of a circuit and reuses an existing node instead of creating a structurally
+/// identical one (hash-consing). Two nodes are identical if they are the same leaf or the same kind
+/// of inner node with the same children in the same order. As the children are reused as well,
+/// comparing their addresses is enough. The table only holds weak references, so it does not keep
+/// nodes alive that the circuit no longer uses.
+pub struct NodeTable {
+    nodes: HashMap<NodeKey, Weak<DDNNFNode>>,
+    enabled: bool,
+    /// The table is cleaned of dropped nodes once it grows beyond this size
+    cleanup_size: usize,
+}
+
+#[derive(Eq, Hash, PartialEq)]
+enum NodeKey {
+    True,
+    False,
+    Literal(u32, bool),
+    And(Vec<*const DDNNFNode>),
+    Or(Vec<*const DDNNFNode>),
+}
+
+const MIN_CLEANUP_SIZE: usize = 1024;
+
+impl Default for NodeTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodeTable {
+    pub fn new() -> NodeTable {
+        NodeTable {
+            nodes: HashMap::new(),
+            enabled: true,
+            cleanup_size: MIN_CLEANUP_SIZE,
+        }
+    }
+
+    /// Enables or disables the reuse of nodes. Disabling it clears the table.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.nodes.clear();
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns a node that is structurally identical to the given one, the given node is only
+    /// allocated if there is no such node yet.
+    pub fn node(&mut self, node: DDNNFNode) -> Rc<DDNNFNode> {
+        if !self.enabled {
+            return Rc::new(node);
+        }
+        let children_key = |children: &[Rc<DDNNFNode>]| children.iter().map(Rc::as_ptr).collect();
+        let key = match &node {
+            DDNNFNode::TrueLeave => NodeKey::True,
+            DDNNFNode::FalseLeave => NodeKey::False,
+            DDNNFNode::LiteralLeave(literal) => NodeKey::Literal(literal.index, literal.positive),
+            DDNNFNode::AndNode(children, _) => NodeKey::And(children_key(children)),
+            DDNNFNode::OrNode(children, _) => NodeKey::Or(children_key(children)),
+        };
+        // a live node keeps its children alive, so their addresses in the key were not reused
+        if let Some(existing_node) = self.nodes.get(&key).and_then(Weak::upgrade) {
+            return existing_node;
+        }
+        let node = Rc::new(node);
+        self.nodes.insert(key, Rc::downgrade(&node));
+        if self.nodes.len() > self.cleanup_size {
+            self.nodes.retain(|_, node| node.strong_count() > 0);
+            self.cleanup_size = (2 * self.nodes.len()).max(MIN_CLEANUP_SIZE);
+        }
+        node
+    }
+}
+
+/// One or more circuits whose nodes are stored in post-order, children are referenced by their
+/// position. Nodes that are shared between circuits are stored once.
+#[cfg(any(feature = "parallel", feature = "serde"))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlatDDNNF {
+    nodes: Vec<FlatDDNNFNode>,
+}
+
+#[cfg(any(feature = "parallel", feature = "serde"))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum FlatDDNNFNode {
+    TrueLeave,
+    FalseLeave,
+    LiteralLeave(u32, bool),
+    AndNode(Vec<usize>),
+    OrNode(Vec<usize>),
+}
+
+#[cfg(any(feature = "parallel", feature = "serde"))]
+impl Default for FlatDDNNF {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(feature = "parallel", feature = "serde"))]
+impl FlatDDNNF {
+    pub fn new() -> FlatDDNNF {
+        FlatDDNNF { nodes: Vec::new() }
+    }
+
+    /// Adds a node and all of its descendants that were not added before.
+    /// # Arguments
+    /// * `root_node` - The node to add
+    /// * `ids` - The position of every node that was already added, the new nodes are inserted
+    /// # Returns
+    /// The position of the node
+    pub fn add_node(
+        &mut self,
+        root_node: &Rc<DDNNFNode>,
+        ids: &mut HashMap<*const DDNNFNode, usize>,
+    ) -> usize {
+        let mut stack: Vec<(&Rc<DDNNFNode>, bool)> = vec![(root_node, false)];
+        while let Some((node, children_done)) = stack.pop() {
+            if ids.contains_key(&Rc::as_ptr(node)) {
+                continue;
+            }
+            if !children_done {
+                stack.push((node, true));
+                if let DDNNFNode::AndNode(child_list, _) | DDNNFNode::OrNode(child_list, _) =
+                    node.as_ref()
+                {
+                    for child in child_list.iter().rev() {
+                        stack.push((child, false));
+                    }
+                }
+                continue;
+            }
+            let children = |child_list: &Vec<Rc<DDNNFNode>>| {
+                child_list
+                    .iter()
+                    .map(|child| ids[&Rc::as_ptr(child)])
+                    .collect()
+            };
+            let flat_node = match node.as_ref() {
+                DDNNFNode::TrueLeave => FlatDDNNFNode::TrueLeave,
+                DDNNFNode::FalseLeave => FlatDDNNFNode::FalseLeave,
+                DDNNFNode::LiteralLeave(literal) => {
+                    FlatDDNNFNode::LiteralLeave(literal.index, literal.positive)
+                }
+                DDNNFNode::AndNode(child_list, _) => FlatDDNNFNode::AndNode(children(child_list)),
+                DDNNFNode::OrNode(child_list, _) => FlatDDNNFNode::OrNode(children(child_list)),
+            };
+            ids.insert(Rc::as_ptr(node), self.nodes.len());
+            self.nodes.push(flat_node);
+        }
+        ids[&Rc::as_ptr(root_node)]
+    }
+
+    /// Rebuilds the reference counted nodes, the positions of the nodes stay the same.
+    /// # Arguments
+    /// * `next_id` - Returns a new unique id for every and and or node, the ids of the original
+    ///   circuit are not kept as they are only unique within the solver that created it
+    pub fn into_nodes(self, mut next_id: impl FnMut() -> u32) -> Vec<Rc<DDNNFNode>> {
+        let mut nodes: Vec<Rc<DDNNFNode>> = Vec::with_capacity(self.nodes.len());
+        for flat_node in self.nodes {
+            let node = match flat_node {
+                FlatDDNNFNode::TrueLeave => DDNNFNode::TrueLeave,
+                FlatDDNNFNode::FalseLeave => DDNNFNode::FalseLeave,
+                FlatDDNNFNode::LiteralLeave(index, positive) => {
+                    DDNNFNode::LiteralLeave(Rc::new(DDNNFLiteral { index, positive }))
+                }
+                FlatDDNNFNode::AndNode(children) => DDNNFNode::AndNode(
+                    children
+                        .iter()
+                        .map(|child| Rc::clone(&nodes[*child]))
+                        .collect(),
+                    next_id(),
+                ),
+                FlatDDNNFNode::OrNode(children) => DDNNFNode::OrNode(
+                    children
+                        .iter()
+                        .map(|child| Rc::clone(&nodes[*child]))
+                        .collect(),
+                    next_id(),
+                ),
+            };
+            nodes.push(Rc::new(node));
+        }
+        nodes
+    }
+
+    /// Rebuilds the circuit of the last added node.
+    #[cfg(feature = "parallel")]
+    pub fn into_node(self, next_id: impl FnMut() -> u32) -> Rc<DDNNFNode> {
+        self.into_nodes(next_id)
+            .pop()
+            .expect("a circuit has at least one node")
+    }
+}
+
+/// A circuit whose nodes are stored in post-order in one vector instead of being reference
+/// counted, children are referenced by their position. The children of all inner nodes are stored
+/// in a second vector, so the whole circuit needs two allocations instead of one per node, child
+/// list and literal. The root node is the last node.
+pub struct ArenaDDNNF {
+    nodes: Vec<ArenaDDNNFNode>,
+    children: Vec<u32>,
+    pub number_variables: u32,
+}
+
+/// A node of an `ArenaDDNNF`. Inner nodes store the range of their children in the child vector
+/// of the arena as (start, end), use `ArenaDDNNF::children` to get them.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ArenaDDNNFNode {
+    TrueLeave,
+    FalseLeave,
+    LiteralLeave(u32, bool),
+    AndNode(u32, u32),
+    OrNode(u32, u32),
+}
+
+impl ArenaDDNNF {
+    /// Returns the nodes in post-order: every child appears before its parents.
+    pub fn nodes(&self) -> &[ArenaDDNNFNode] {
+        &self.nodes
+    }
+
+    /// Returns the position of the root node.
+    pub fn root(&self) -> u32 {
+        self.nodes.len() as u32 - 1
+    }
+
+    /// Returns the positions of the children of a node, which are empty for leaves.
+    pub fn children(&self, node: &ArenaDDNNFNode) -> &[u32] {
+        match *node {
+            ArenaDDNNFNode::AndNode(start, end) | ArenaDDNNFNode::OrNode(start, end) => {
+                &self.children[start as usize..end as usize]
+            }
+            _ => &[],
+        }
+    }
+
+    /// Calculates the model count of the circuit. The circuit is smoothed on the fly like in
+    /// `DDNNF::weighted_count`: a variable that does not occur in a child of an or node (or in the
+    /// whole circuit) doubles the count. As the children appear before their parents, the results
+    /// are kept in a vector indexed by the position of the node instead of a map.
+    pub fn model_count(&self) -> BigUint {
+        let free_count = |scope: &BTreeSet<u32>, child_scope: &BTreeSet<u32>| {
+            BigUint::one() << scope.difference(child_scope).count()
+        };
+
+        let mut results: Vec<(BigUint, BTreeSet<u32>)> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let result = match node {
+                ArenaDDNNFNode::TrueLeave => (BigUint::one(), BTreeSet::new()),
+                ArenaDDNNFNode::FalseLeave => (BigUint::zero(), BTreeSet::new()),
+                ArenaDDNNFNode::LiteralLeave(index, _) => {
+                    (BigUint::one(), BTreeSet::from([*index]))
+                }
+                ArenaDDNNFNode::AndNode(_, _) => {
+                    let mut value = BigUint::one();
+                    let mut scope = BTreeSet::new();
+                    for child in self.children(node) {
+                        let (child_value, child_scope) = &results[*child as usize];
+                        value *= child_value;
+                        scope.extend(child_scope);
+                    }
+                    (value, scope)
+                }
+                ArenaDDNNFNode::OrNode(_, _) => {
+                    let mut scope = BTreeSet::new();
+                    for child in self.children(node) {
+                        scope.extend(&results[*child as usize].1);
+                    }
+                    let value = self
+                        .children(node)
+                        .iter()
+                        .map(|child| {
+                            let (child_value, child_scope) = &results[*child as usize];
+                            child_value * free_count(&scope, child_scope)
+                        })
+                        .sum();
+                    (value, scope)
+                }
+            };
+            results.push(result);
+        }
+
+        let (value, scope) = &results[self.root() as usize];
+        let all_variables = (0..self.number_variables).collect();
+        value * free_count(&all_variables, scope)
+    }
+
+    /// Creates a representation of the circuit in the DOT language of Graphviz. Every distinct
+    /// node is printed once, literals are labeled like in the d4 format and the ids of the nodes
+    /// are their positions.
+    pub fn to_dot(&self) -> String {
+        let mut result_string = String::from("digraph ddnnf {\n");
+        for (id, node) in self.nodes.iter().enumerate() {
+            let label = match node {
+                ArenaDDNNFNode::TrueLeave => "true".to_string(),
+                ArenaDDNNFNode::FalseLeave => "false".to_string(),
+                ArenaDDNNFNode::LiteralLeave(index, positive) => {
+                    format!("{}{}", if *positive { "" } else { "-" }, index + 1)
+                }
+                ArenaDDNNFNode::AndNode(_, _) => "and".to_string(),
+                ArenaDDNNFNode::OrNode(_, _) => "or".to_string(),
+            };
+            result_string.push_str(&format!("  n{} [label=\"{}\"];\n", id, label));
+            for child in self.children(node) {
+                result_string.push_str(&format!("  n{} -> n{};\n", id, child));
+            }
+        }
+        result_string.push_str("}\n");
+        result_string
+    }
+
+    /// Rebuilds the reference counted circuit, e.g. to print it with the `DDNNFPrinter`. The and
+    /// and or nodes get their position as id.
+    pub fn to_ddnnf(&self) -> DDNNF {
+        let mut nodes: Vec<Rc<DDNNFNode>> = Vec::with_capacity(self.nodes.len());
+        for (id, arena_node) in self.nodes.iter().enumerate() {
+            let children = || {
+                self.children(arena_node)
+                    .iter()
+                    .map(|child| Rc::clone(&nodes[*child as usize]))
+                    .collect()
+            };
+            let node = match *arena_node {
+                ArenaDDNNFNode::TrueLeave => DDNNFNode::TrueLeave,
+                ArenaDDNNFNode::FalseLeave => DDNNFNode::FalseLeave,
+                ArenaDDNNFNode::LiteralLeave(index, positive) => {
+                    DDNNFNode::LiteralLeave(Rc::new(DDNNFLiteral { index, positive }))
+                }
+                ArenaDDNNFNode::AndNode(_, _) => DDNNFNode::AndNode(children(), id as u32),
+                ArenaDDNNFNode::OrNode(_, _) => DDNNFNode::OrNode(children(), id as u32),
+            };
+            nodes.push(Rc::new(node));
+        }
+        DDNNF {
+            root_node: nodes.pop().expect("a circuit has at least one node"),
+            number_variables: self.number_variables,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solving::pseudo_boolean_datastructure::PseudoBooleanFormula;
+    use crate::solving::solver::Solver;
+    use p2d_opb::parse;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_weighted_count() {
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 >= 1;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        let mut weights = HashMap::new();
+        for index in 0..2 {
+            weights.insert((index, true), 0.5);
+            weights.insert((index, false), 0.5);
+        }
+        assert_eq!(
+            result.ddnnf.as_ref().unwrap().weighted_count(&weights),
+            0.75
+        );
+        assert_eq!(
+            result
+                .ddnnf
+                .as_ref()
+                .unwrap()
+                .weighted_count(&HashMap::new()),
+            3.0
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_model_count() {
+        for input in [
+            "#variable= 2 #constraint= 1\nx1 + x2 >= 1;",
+            "#variable= 4 #constraint= 2\nx1 + x2 >= 1;\nx1 + ~x2 + 2 x3 >= 2;",
+            "#variable= 2 #constraint= 2\nx1 >= 1;\n~x1 >= 1;",
+        ] {
+            let opb_file = parse(input).expect("error while parsing");
+            let result = Solver::new(PseudoBooleanFormula::new(&opb_file)).solve();
+            assert_eq!(
+                result.ddnnf.as_ref().unwrap().model_count(),
+                result.model_count
+            );
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_conditioned() {
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 >= 1;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let x1 = *formula.name_map.get_by_left("x1").unwrap();
+        let ddnnf = Solver::new(formula).solve().ddnnf.unwrap();
+        let conditioned = ddnnf.conditioned((x1, false));
+        assert_eq!(conditioned.model_count(), BigUint::one());
+        assert!(conditioned.validate().is_ok());
+        assert_eq!(
+            ddnnf.conditioned((x1, true)).model_count(),
+            BigUint::from(2_u32)
+        );
+
+        // the fourth declared variable does not occur in the circuit, but is still counted
+        let opb_file = parse("#variable= 4 #constraint= 2\nx1 + x2 >= 1;\nx1 + ~x2 + 2 x4 >= 2;")
+            .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let x2 = *formula.name_map.get_by_left("x2").unwrap();
+        let ddnnf = Solver::new(formula).solve().ddnnf.unwrap();
+        let positive = ddnnf.conditioned((x2, true)).model_count();
+        let negative = ddnnf.conditioned((x2, false)).model_count();
+        assert_eq!(positive, BigUint::from(4_u32));
+        assert_eq!(negative, BigUint::from(4_u32));
+        assert_eq!(positive + negative, ddnnf.model_count());
+        for literal in [(x2, true), (x2, false)] {
+            assert!(ddnnf.conditioned(literal).validate().is_ok());
+        }
+
+        // conditioning on a literal without models gives a circuit without models
+        let unsat = ddnnf.conditioned((x2, false)).conditioned((x2, true));
+        assert!(unsat.model_count().is_zero());
+
+        // the conditioned circuits can be printed, also if only the literal is left
+        let opb_file = parse("#variable= 1 #constraint= 1\nx1 >= 1;").expect("error while parsing");
+        let ddnnf = Solver::new(PseudoBooleanFormula::new(&opb_file))
+            .solve()
+            .ddnnf
+            .unwrap();
+        for (ddnnf, literal) in [
+            (&ddnnf, (0, true)),
+            (&ddnnf, (0, false)),
+            (&unsat, (x2, true)),
+        ] {
+            let conditioned = ddnnf.conditioned(literal);
+            assert!(conditioned.validate().is_ok());
+            let (output, _) = DDNNFPrinter::new(conditioned).print();
+            assert!(!output.is_empty());
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_print_deep_circuit() {
+        // every and node has the next one and the true leave as children
+        let depth = 100_000;
+        let true_leave = Rc::new(DDNNFNode::TrueLeave);
+        let mut node = Rc::new(DDNNFNode::AndNode(
+            vec![
+                Rc::new(DDNNFNode::LiteralLeave(Rc::new(DDNNFLiteral {
+                    index: 0,
+                    positive: true,
+                }))),
+                Rc::clone(&true_leave),
+            ],
+            0,
+        ));
+        for id in 1..depth {
+            node = Rc::new(DDNNFNode::AndNode(vec![node, Rc::clone(&true_leave)], id));
+        }
+        let ddnnf = DDNNF {
+            root_node: node,
+            number_variables: 1,
+        };
+        let (printed, size) = DDNNFPrinter::new(ddnnf).print();
+        assert_eq!(size.and_nodes, depth as usize);
+        let lines: Vec<&str> = printed.lines().collect();
+        // the innermost and node only has one child besides its literal, so it is printed as an
+        // edge with the literal, the true leaves are printed after all and nodes
+        assert_eq!(lines[..5], ["a 1 0", "a 2 0", "1 2 0", "a 3 0", "2 3 0"]);
+        assert!(lines.contains(&format!("t {depth} 0").as_str()));
+        assert!(lines.contains(&format!("{} {depth} 1 0", depth - 1).as_str()));
+        assert_eq!(*lines.last().unwrap(), format!("1 {depth} 0"));
+        assert_eq!(lines.len(), 3 * depth as usize - 2);
+    }
+
+    #[test]
+    #[serial]
+    fn test_dot_and_json() {
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 >= 1;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        assert_eq!(
+            result.ddnnf.as_ref().unwrap().to_dot(),
+            "digraph ddnnf {\n  n0 [label=\"2\"];\n  n1 [label=\"-1\"];\n  n2 [label=\"and\"];\n  n2 -> n0;\n  n2 -> n1;\n  n3 [label=\"1\"];\n  n4 [label=\"or\"];\n  n4 -> n2;\n  n4 -> n3;\n}\n"
+        );
+        assert_eq!(
+            result.ddnnf.as_ref().unwrap().to_json(),
+            "{\"number_variables\":2,\"root\":4,\"nodes\":[{\"id\":0,\"type\":\"literal\",\"literal\":2},{\"id\":1,\"type\":\"literal\",\"literal\":-1},{\"id\":2,\"type\":\"and\",\"children\":[0,1]},{\"id\":3,\"type\":\"literal\",\"literal\":1},{\"id\":4,\"type\":\"or\",\"children\":[2,3]}]}"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_write_to() {
+        let file_content =
+            std::fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let ddnnf = solver.solve().ddnnf.unwrap();
+
+        let (printed, printed_size) = DDNNFPrinter::new(ddnnf.canonicalize()).print();
+        let mut output = Vec::new();
+        let written_size = DDNNFPrinter::new(ddnnf.canonicalize())
+            .write_to(&mut output)
+            .expect("cannot write to vector");
+        assert_eq!(output, printed.into_bytes());
+        assert_eq!(written_size, printed_size);
+    }
+
+    #[test]
+    #[serial]
+    fn test_canonicalize() {
+        let print = |ddnnf: DDNNF| DDNNFPrinter::new(ddnnf).print().0;
+        let opb_file = parse(
+            "#variable= 6 #constraint= 3\n3 x5 + 3 x4 + 2 x6 + 2 x3 >= 1;\n3 x1 + 2 x6 + x3 + x4 >= 1;\nx5 + x3 + x6 >= 2;",
+        )
+        .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let with_cache = solver.solve().ddnnf.unwrap();
+        assert!(solver.statistics.cache_hits() > 0);
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_cache_capacity(0);
+        // without any sharing during the construction
+        solver.set_hash_consing(false);
+        let without_cache = solver.solve().ddnnf.unwrap();
+
+        let canonical_with_cache = with_cache.canonicalize();
+        let canonical_without_cache = without_cache.canonicalize();
+        assert!(canonical_without_cache.node_count() < without_cache.node_count());
+        assert_eq!(
+            canonical_with_cache.weighted_count(&HashMap::new()),
+            with_cache.weighted_count(&HashMap::new())
+        );
+        assert_ne!(print(with_cache), print(without_cache));
+        assert_eq!(
+            print(canonical_with_cache.canonicalize()),
+            print(canonical_without_cache)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let literal = |index: u32, positive: bool| {
+            Rc::new(DDNNFNode::LiteralLeave(Rc::new(DDNNFLiteral {
+                index,
+                positive,
+            })))
+        };
+        let ddnnf = |root_node: DDNNFNode| DDNNF {
+            root_node: Rc::new(root_node),
+            number_variables: 3,
+        };
+
+        let valid = ddnnf(DDNNFNode::OrNode(
+            vec![
+                Rc::new(DDNNFNode::AndNode(
+                    vec![literal(0, true), literal(1, true)],
+                    1,
+                )),
+                Rc::new(DDNNFNode::AndNode(
+                    vec![literal(0, false), literal(2, true)],
+                    2,
+                )),
+                Rc::new(DDNNFNode::FalseLeave),
+            ],
+            3,
+        ));
+        assert_eq!(valid.validate(), Ok(()));
+
+        let not_decomposable = ddnnf(DDNNFNode::AndNode(
+            vec![
+                Rc::new(DDNNFNode::AndNode(
+                    vec![literal(0, true), literal(1, true)],
+                    1,
+                )),
+                Rc::new(DDNNFNode::AndNode(
+                    vec![literal(1, false), literal(2, true)],
+                    2,
+                )),
+            ],
+            3,
+        ));
+        assert_eq!(
+            not_decomposable.validate(),
+            Err("the children of the and node 3 share the variable 2".to_string())
+        );
+
+        let not_deterministic = ddnnf(DDNNFNode::OrNode(
+            vec![
+                Rc::new(DDNNFNode::AndNode(
+                    vec![literal(0, true), literal(1, true)],
+                    1,
+                )),
+                Rc::new(DDNNFNode::AndNode(
+                    vec![literal(1, true), literal(2, true)],
+                    2,
+                )),
+            ],
+            3,
+        ));
+        assert_eq!(
+            not_deterministic.validate(),
+            Err("the children 0 and 1 of the or node 3 are not disjoint".to_string())
+        );
+    }
+
+    #[test]
+    fn test_nodes() {
+        let literal = |index: u32, positive: bool| {
+            Rc::new(DDNNFNode::LiteralLeave(Rc::new(DDNNFLiteral {
+                index,
+                positive,
+            })))
+        };
+        let shared = literal(1, true);
+        let ddnnf = DDNNF {
+            root_node: Rc::new(DDNNFNode::OrNode(
+                vec![
+                    Rc::new(DDNNFNode::AndNode(
+                        vec![literal(0, true), Rc::clone(&shared)],
+                        1,
+                    )),
+                    Rc::new(DDNNFNode::AndNode(vec![literal(0, false), shared], 2)),
+                ],
+                3,
+            )),
+            number_variables: 2,
+        };
+
+        let nodes: Vec<&DDNNFNode> = ddnnf.nodes().collect();
+        assert_eq!(nodes.len(), 6);
+        assert_eq!(nodes.len(), ddnnf.node_count());
+        assert!(std::ptr::eq(
+            *nodes.last().unwrap(),
+            ddnnf.root_node.as_ref()
+        ));
+        let positions: HashMap<*const DDNNFNode, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(position, node)| (*node as *const DDNNFNode, position))
+            .collect();
+        assert_eq!(positions.len(), nodes.len());
+        for (position, node) in nodes.iter().enumerate() {
+            if let DDNNFNode::AndNode(child_list, _) | DDNNFNode::OrNode(child_list, _) = node {
+                for child in child_list {
+                    assert!(positions[&Rc::as_ptr(child)] < position);
+                }
+            }
+        }
+        assert!(ddnnf
+            .nodes()
+            .zip(nodes.iter())
+            .all(|(node, other)| std::ptr::eq(node, *other)));
+    }
+
+    #[test]
+    #[serial]
+    fn test_arena() {
+        let file_content =
+            std::fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let result = solver.solve();
+        let ddnnf = result.ddnnf.unwrap();
+
+        let arena = ddnnf.to_arena();
+        assert_eq!(arena.nodes().len(), ddnnf.node_count());
+        assert_eq!(arena.root() as usize, arena.nodes().len() - 1);
+        for (position, node) in arena.nodes().iter().enumerate() {
+            assert!(arena
+                .children(node)
+                .iter()
+                .all(|child| (*child as usize) < position));
+        }
+        assert_eq!(arena.model_count(), result.model_count);
+        assert_eq!(arena.to_dot(), ddnnf.to_dot());
+
+        let rebuilt = arena.to_ddnnf();
+        assert_eq!(rebuilt.size(), ddnnf.size());
+        assert_eq!(rebuilt.model_count(), result.model_count);
+        assert_eq!(
+            DDNNFPrinter::new(rebuilt).print(),
+            DDNNFPrinter::new(ddnnf).print()
+        );
+    }
+}
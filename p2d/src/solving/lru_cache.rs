@@ -0,0 +1,124 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// A cache that evicts the least recently used entry as soon as it holds more entries than its capacity.
+/// Every entry stores the time of its last use, the entries ordered by that time are kept in a
+/// separate map, so the least recently used one can be found quickly.
+pub struct LruCache<V> {
+    entries: HashMap<u64, (V, u64)>,
+    usage_order: BTreeMap<u64, u64>,
+    capacity: Option<usize>,
+    time: u64,
+}
+
+impl<V> LruCache<V> {
+    /// Creates a new cache without a limit on the number of entries.
+    pub fn new() -> LruCache<V> {
+        LruCache {
+            entries: HashMap::with_capacity(100),
+            usage_order: BTreeMap::new(),
+            capacity: None,
+            time: 0,
+        }
+    }
+
+    /// Sets the maximum number of entries. If the cache already contains more entries,
+    /// the least recently used ones are evicted.
+    /// # Returns
+    /// The number of evicted entries
+    pub fn set_capacity(&mut self, capacity: usize) -> usize {
+        self.capacity = Some(capacity);
+        self.evict()
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the value of the given key and marks the entry as most recently used.
+    pub fn get(&mut self, key: u64) -> Option<&V> {
+        let time = self.tick();
+        match self.entries.get_mut(&key) {
+            None => None,
+            Some((value, last_used)) => {
+                self.usage_order.remove(last_used);
+                self.usage_order.insert(time, key);
+                *last_used = time;
+                Some(value)
+            }
+        }
+    }
+
+    /// Inserts an entry and evicts the least recently used entries if the capacity is exceeded.
+    /// # Returns
+    /// The number of evicted entries
+    pub fn insert(&mut self, key: u64, value: V) -> usize {
+        let time = self.tick();
+        if let Some((_, last_used)) = self.entries.insert(key, (value, time)) {
+            self.usage_order.remove(&last_used);
+        }
+        self.usage_order.insert(time, key);
+        self.evict()
+    }
+
+    /// Returns all entries ordered from the least to the most recently used one.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &V)> {
+        self.usage_order
+            .values()
+            .map(|key| (*key, &self.entries[key].0))
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.time += 1;
+        self.time
+    }
+
+    fn evict(&mut self) -> usize {
+        let mut evicted = 0;
+        if let Some(capacity) = self.capacity {
+            while self.entries.len() > capacity {
+                let (_, key) = self.usage_order.pop_first().unwrap();
+                self.entries.remove(&key);
+                evicted += 1;
+            }
+        }
+        evicted
+    }
+}
+
+impl<V> Default for LruCache<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = LruCache::new();
+        assert!(cache.is_empty());
+        cache.set_capacity(2);
+        assert_eq!(cache.insert(1, "a"), 0);
+        assert_eq!(cache.insert(2, "b"), 0);
+        assert_eq!(cache.get(1), Some(&"a"));
+        assert_eq!(cache.insert(3, "c"), 1);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(2), None);
+        assert_eq!(cache.get(1), Some(&"a"));
+        assert_eq!(cache.get(3), Some(&"c"));
+        assert_eq!(cache.iter().collect::<Vec<_>>(), vec![(1, &"a"), (3, &"c")]);
+        assert_eq!(cache.set_capacity(1), 1);
+        assert_eq!(cache.get(1), None);
+        assert_eq!(cache.get(3), Some(&"c"));
+    }
+}
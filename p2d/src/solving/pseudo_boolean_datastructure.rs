@@ -1,8 +1,7 @@
 use crate::solving::pseudo_boolean_datastructure::ConstraintIndex::NormalConstraintIndex;
 use crate::solving::pseudo_boolean_datastructure::ConstraintType::{GreaterEqual, NotEqual};
 use crate::solving::pseudo_boolean_datastructure::PropagationResult::{
-    AlreadySatisfied, ImpliedLiteral, ImpliedLiteralList, NothingToPropagated, Satisfied,
-    Unsatisfied,
+    AlreadySatisfied, Conflict, ImpliedLiteral, ImpliedLiteralList, NothingToPropagated, Satisfied,
 };
 use crate::solving::solver::AssignmentKind;
 use bimap::BiMap;
@@ -10,6 +9,7 @@ use p2d_opb::EquationKind::{Eq, Le, G, L};
 use p2d_opb::{Equation, EquationKind, OPBFile, Summand};
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::{Display, Formatter};
 use std::hash::{DefaultHasher, Hash, Hasher};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -18,6 +18,14 @@ pub struct PseudoBooleanFormula {
     pub number_variables: u32,
     pub constraints_by_variable: Vec<Vec<usize>>,
     pub name_map: BiMap<String, u32>,
+    /// Whether a constraint that can never be satisfied was found while building the formula
+    pub contradictory: bool,
+    /// The indexes of the equations that can never be satisfied, see `contradictory`
+    pub contradictory_equations: Vec<usize>,
+    /// The number of equations that were added, the next equation gets this index
+    pub number_equations: usize,
+    /// The objective function of the input file, it does not change the models
+    pub objective: Option<Vec<Summand>>,
 }
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Constraint {
@@ -27,12 +35,17 @@ pub struct Constraint {
     pub degree: i128,
     pub sum_true: u128,
     pub sum_unassigned: u128,
-    pub assignments: BTreeMap<usize, (bool, AssignmentKind, u32)>,
+    pub(crate) assignments: BTreeMap<usize, (bool, AssignmentKind, u32)>,
     pub factor_sum: u128,
     pub hash_value: u64,
     pub hash_value_old: bool,
     pub constraint_type: ConstraintType,
+    /// The index of the equation the constraint was built from, in the order of the input file
+    /// followed by the equations of `add_constraint`. None for a learned clause.
+    pub equation_index: Option<usize>,
     pub max_literal: Literal,
+    /// How often propagating a literal made the constraint imply literals or become violated
+    pub propagations: u32,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -61,7 +74,8 @@ pub struct Literal {
 
 pub enum PropagationResult {
     Satisfied,
-    Unsatisfied,
+    /// The constraint can no longer be satisfied, carries the index of the constraint
+    Conflict(ConstraintIndex),
     ImpliedLiteral(Literal),
     ImpliedLiteralList(Vec<Literal>),
     NothingToPropagated,
@@ -80,116 +94,580 @@ impl PartialOrd for Literal {
     }
 }
 
+impl TryFrom<&OPBFile> for PseudoBooleanFormula {
+    type Error = String;
+
+    fn try_from(opb_file: &OPBFile) -> Result<Self, Self::Error> {
+        Self::try_new(opb_file)
+    }
+}
+
+impl TryFrom<&str> for PseudoBooleanFormula {
+    type Error = String;
+
+    /// Parses the content of an OPB file and creates its formula.
+    fn try_from(opb: &str) -> Result<Self, Self::Error> {
+        Self::try_new(&p2d_opb::parse(opb)?)
+    }
+}
+
 impl Ord for Literal {
     fn cmp(&self, other: &Self) -> Ordering {
         self.factor.cmp(&other.factor)
     }
 }
 
+/// The number of variables a header may declare in addition to the ones used in the equations
+/// unless another limit is given, see `PseudoBooleanFormula::try_new_with_max_unused_variables`
+pub const MAX_UNUSED_VARIABLES: u32 = 1 << 20;
+
 impl PseudoBooleanFormula {
+    /// Creates the formula of an OPB file. Unlike `try_new`, the header may declare any number of
+    /// unused variables.
+    /// # Panics
+    /// Panics if the coefficients of a constraint are too large, see `try_new`
     pub fn new(opb_file: &OPBFile) -> PseudoBooleanFormula {
-        let mut equation_list: Vec<Equation> = opb_file
-            .equations
-            .iter()
-            .flat_map(|x| replace_equal_equations(x))
-            .collect();
-        equation_list = equation_list
-            .iter()
-            .map(|x| replace_le_equations(x))
-            .collect();
-        equation_list = equation_list
-            .iter()
-            .map(|x| replace_l_equations(x))
-            .collect();
-        equation_list = equation_list
-            .iter()
-            .map(|x| replace_g_equations(x))
-            .collect();
-        equation_list = equation_list
-            .iter()
-            .map(|x| add_up_same_variables(x))
-            .collect();
-        equation_list = equation_list
-            .iter()
-            .map(|x| replace_negative_factors(x))
-            .collect();
-        equation_list.iter().for_each(|e| {
-            if e.lhs
-                .iter()
-                .filter(|s| s.factor < 0)
-                .collect::<Vec<&Summand>>()
-                .len()
-                > 0
-            {
-                panic!("Factors must be negative to create a PseudoBooleanFormula")
-            }
-        });
+        Self::try_new_with_max_unused_variables(opb_file, u32::MAX)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Creates the formula of an OPB file whose header declares at most `MAX_UNUSED_VARIABLES`
+    /// unused variables, see `try_new_with_max_unused_variables`.
+    pub fn try_new(opb_file: &OPBFile) -> Result<PseudoBooleanFormula, String> {
+        Self::try_new_with_max_unused_variables(opb_file, MAX_UNUSED_VARIABLES)
+    }
+
+    /// Creates the formula of an OPB file.
+    /// The variables keep the indexes of the file, which the parser assigns in the order the names
+    /// first appear. If the header declares more variables than appear in the equations, the
+    /// formula still has the declared number of variables: the remaining ones get the next indexes,
+    /// have no name and are free in every model. As every variable needs memory, the number of
+    /// these unused variables is limited.
+    /// # Arguments
+    /// * `opb_file` - The parsed file
+    /// * `max_unused_variables` - The number of variables the header may declare in addition to
+    ///   the ones used in the equations
+    /// # Returns
+    /// The formula or an error if the coefficients of a constraint overflow during normalization
+    /// or their sum does not fit into an u128, or if the header declares too many variables
+    pub fn try_new_with_max_unused_variables(
+        opb_file: &OPBFile,
+        max_unused_variables: u32,
+    ) -> Result<PseudoBooleanFormula, String> {
+        let number_variables = opb_file
+            .max_name_index
+            .max(u32::try_from(opb_file.number_variables).unwrap_or(u32::MAX));
+        if number_variables - opb_file.max_name_index > max_unused_variables {
+            return Err(format!(
+                "the header declares {} variables, but only {} are used, at most {} unused \
+                 variables are accepted (see --max-unused-variables)",
+                opb_file.number_variables, opb_file.max_name_index, max_unused_variables
+            ));
+        }
         let mut pseudo_boolean_formula = PseudoBooleanFormula {
             constraints: Vec::with_capacity(opb_file.number_constraints),
-            number_variables: opb_file.max_name_index,
-            constraints_by_variable: Vec::with_capacity((opb_file.max_name_index - 1) as usize),
+            number_variables,
+            constraints_by_variable: Vec::with_capacity(number_variables as usize),
             name_map: opb_file.name_map.clone(),
+            contradictory: false,
+            contradictory_equations: Vec::new(),
+            number_equations: 0,
+            objective: opb_file.objective.clone(),
         };
 
-        for _ in 0..opb_file.max_name_index {
+        for _ in 0..number_variables {
             pseudo_boolean_formula
                 .constraints_by_variable
                 .push(Vec::new());
         }
 
-        let mut constraint_counter = 0;
-        for equation in equation_list {
-            let mut constraint = Constraint {
-                degree: if equation.rhs < 0 { 0 } else { equation.rhs },
-                sum_true: 0,
-                sum_unassigned: equation.lhs.iter().map(|s| s.factor).sum::<i128>() as u128,
-                literals: BTreeMap::new(),
-                unassigned_literals: BTreeMap::new(),
-                assignments: BTreeMap::new(),
-                factor_sum: equation.lhs.iter().map(|s| s.factor).sum::<i128>() as u128,
-                index: NormalConstraintIndex(constraint_counter),
-                hash_value: 0,
-                hash_value_old: true,
-                constraint_type: get_constraint_type_from_equation(&equation),
-                max_literal: Literal {
-                    index: 0,
-                    factor: 0,
-                    positive: false,
+        for equation in &opb_file.equations {
+            pseudo_boolean_formula.add_constraint(equation)?;
+        }
+        Ok(pseudo_boolean_formula)
+    }
+
+    /// Normalizes an equation and appends the resulting constraints (an equation with = becomes
+    /// two constraints). Variables with an index outside of the formula are added to it.
+    /// Summands whose factor is 0, also after merging the summands of the same variable, are
+    /// dropped, but their variables are still added.
+    /// Constraints that are satisfied by every assignment are not added. If a constraint can not
+    /// be satisfied by any assignment, it is not added either, but the formula is marked as
+    /// contradictory and the index of the equation is recorded. The equations are numbered in
+    /// the order in which they are added.
+    /// # Arguments
+    /// * `equation` - The equation to add, its variable indexes must use the name map of the formula
+    /// # Returns
+    /// An error if the coefficients overflow, the formula is unchanged in this case
+    pub fn add_constraint(&mut self, equation: &Equation) -> Result<(), String> {
+        let equation_list = normalize_equation(equation)?;
+        let factor_sums = equation_list
+            .iter()
+            .map(get_factor_sum)
+            .collect::<Result<Vec<u128>, String>>()?;
+        let equation_index = self.number_equations;
+        self.number_equations += 1;
+        for (equation, factor_sum) in equation_list.into_iter().zip(factor_sums) {
+            self.push_constraint(equation, factor_sum, equation_index);
+        }
+        Ok(())
+    }
+
+    /// Checks without any search whether a single constraint can never be satisfied, i.e. whether
+    /// a constraint with >= can not reach its degree even if all its literals are true. This also
+    /// covers the contradictions that were found while building the formula.
+    pub fn is_trivially_unsat(&self) -> bool {
+        self.contradictory
+            || self
+                .constraints
+                .iter()
+                .any(|c| c.constraint_type == GreaterEqual && c.factor_sum < c.degree as u128)
+    }
+
+    /// Returns the literals that are forced by a single constraint without any search, e.g. `x1`
+    /// for `2 x1 >= 2`. A literal of a constraint with >= is forced if the other literals can not
+    /// reach the degree on their own, a constraint with != that consists of one literal forbids
+    /// the value of the literal that makes the sum equal to the degree. Contradicting literals are
+    /// returned as they are, the solver finds the conflict when assigning them.
+    /// # Returns
+    /// The forced literals as (variable_index, value, constraint) in the order of the constraints,
+    /// a literal forced by several constraints is returned once per constraint
+    pub fn unit_literals(&self) -> Vec<(u32, bool, ConstraintIndex)> {
+        let mut unit_literals = Vec::new();
+        for constraint in &self.constraints {
+            match constraint.constraint_type {
+                GreaterEqual => {
+                    for literal in constraint.literals.values() {
+                        if constraint.factor_sum - literal.factor < constraint.degree as u128 {
+                            unit_literals.push((literal.index, literal.positive, constraint.index));
+                        }
+                    }
+                }
+                NotEqual => {
+                    if constraint.literals.len() != 1 {
+                        continue;
+                    }
+                    let literal = constraint.literals.values().next().unwrap();
+                    if constraint.degree == 0 {
+                        unit_literals.push((literal.index, literal.positive, constraint.index));
+                    } else if literal.factor == constraint.degree as u128 {
+                        unit_literals.push((literal.index, !literal.positive, constraint.index));
+                    }
+                }
+            }
+        }
+        unit_literals
+    }
+
+    /// Returns the number of normalized constraints, an equation of the input file may result in
+    /// none, one or two constraints.
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// Returns the number of variables including those that occur in no constraint.
+    pub fn num_variables(&self) -> u32 {
+        self.number_variables
+    }
+
+    /// Returns the index of a variable by its name in the input file.
+    /// # Returns
+    /// The index (starting at 0) or None if no variable has this name
+    pub fn index_of(&self, name: &str) -> Option<u32> {
+        self.name_map.get_by_left(name).copied()
+    }
+
+    /// Returns the name of a variable in the input file.
+    /// # Returns
+    /// The name or None if there is no such variable or it only appears in the header
+    pub fn name_of(&self, variable_index: u32) -> Option<&str> {
+        self.name_map
+            .get_by_right(&variable_index)
+            .map(String::as_str)
+    }
+
+    /// Returns the constraints that contain a variable, in the order of their indexes.
+    /// # Arguments
+    /// * `variable_index` - The index of the variable
+    /// # Returns
+    /// An iterator over the constraints of the variable, empty if the formula has no such variable
+    pub fn constraints_of(&self, variable_index: u32) -> impl Iterator<Item = &Constraint> {
+        self.constraints_by_variable
+            .get(variable_index as usize)
+            .into_iter()
+            .flatten()
+            .map(|constraint_index| &self.constraints[*constraint_index])
+    }
+
+    /// Returns the constraints that contain a variable for modification, see `constraints_of`.
+    pub fn constraints_of_mut(
+        &mut self,
+        variable_index: u32,
+    ) -> impl Iterator<Item = &mut Constraint> {
+        // the indexes of a variable are sorted, as constraints are only appended
+        let constraint_indexes = self
+            .constraints_by_variable
+            .get(variable_index as usize)
+            .map_or(&[][..], Vec::as_slice);
+        self.constraints
+            .iter_mut()
+            .enumerate()
+            .filter(move |(index, _)| constraint_indexes.binary_search(index).is_ok())
+            .map(|(_, constraint)| constraint)
+    }
+
+    /// Splits the variables into the connected components of the constraint graph, in which two
+    /// variables are connected if they occur in a common constraint. Unlike the components of the
+    /// search (see `Hypergraph::find_disconnected_components`), no variable is assigned, so this
+    /// is the connectivity of the whole formula. A variable without constraints is a component of
+    /// its own.
+    /// # Returns
+    /// The variables of each component in ascending order, the components ordered by their
+    /// smallest variable
+    pub fn connected_components(&self) -> Vec<Vec<u32>> {
+        let mut visited = vec![false; self.number_variables as usize];
+        let mut components = Vec::new();
+        for variable_index in 0..self.number_variables {
+            if visited[variable_index as usize] {
+                continue;
+            }
+            visited[variable_index as usize] = true;
+            let mut component = Vec::new();
+            let mut to_visit = vec![variable_index];
+            while let Some(current) = to_visit.pop() {
+                component.push(current);
+                for constraint in self.constraints_of(current) {
+                    for neighbour in constraint.variables() {
+                        if !visited[neighbour as usize] {
+                            visited[neighbour as usize] = true;
+                            to_visit.push(neighbour);
+                        }
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+
+    /// Checks whether two variables are in the same connected component of the constraint graph,
+    /// see `connected_components`. The search stops as soon as the second variable is reached.
+    /// # Returns
+    /// true if the variables are connected, a variable is connected to itself. false if the
+    /// formula has no such variable.
+    pub fn are_connected(&self, a: u32, b: u32) -> bool {
+        if a >= self.number_variables || b >= self.number_variables {
+            return false;
+        }
+        let mut visited = vec![false; self.number_variables as usize];
+        visited[a as usize] = true;
+        let mut to_visit = vec![a];
+        while let Some(current) = to_visit.pop() {
+            if current == b {
+                return true;
+            }
+            for constraint in self.constraints_of(current) {
+                for neighbour in constraint.variables() {
+                    if !visited[neighbour as usize] {
+                        visited[neighbour as usize] = true;
+                        to_visit.push(neighbour);
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn push_constraint(&mut self, mut equation: Equation, factor_sum: u128, equation_index: usize) {
+        for summand in &equation.lhs {
+            while self.number_variables <= summand.variable_index {
+                self.constraints_by_variable.push(Vec::new());
+                self.number_variables += 1;
+            }
+        }
+        // a summand with the factor 0 does not change the sum, its variable stays in the formula
+        // but is not tied to the constraint
+        equation.lhs.retain(|summand| summand.factor != 0);
+        let constraint_type = get_constraint_type_from_equation(&equation);
+        match constraint_type {
+            GreaterEqual => {
+                if equation.rhs <= 0 {
+                    return;
+                }
+                if factor_sum < equation.rhs as u128 {
+                    self.mark_contradictory(equation_index);
+                    return;
+                }
+            }
+            NotEqual => {
+                // the sum of the true literals is always between 0 and the sum of all factors
+                if equation.rhs < 0 || equation.rhs as u128 > factor_sum {
+                    return;
+                }
+                if factor_sum == 0 {
+                    self.mark_contradictory(equation_index);
+                    return;
+                }
+            }
+        }
+        if self.is_duplicate(&equation, &constraint_type) {
+            return;
+        }
+        let constraint_counter = self.constraints.len();
+        let mut constraint = Constraint {
+            degree: equation.rhs,
+            sum_true: 0,
+            sum_unassigned: factor_sum,
+            literals: BTreeMap::new(),
+            unassigned_literals: BTreeMap::new(),
+            assignments: BTreeMap::new(),
+            factor_sum,
+            index: NormalConstraintIndex(constraint_counter),
+            hash_value: 0,
+            hash_value_old: true,
+            constraint_type,
+            equation_index: Some(equation_index),
+            max_literal: Literal {
+                index: 0,
+                factor: 0,
+                positive: false,
+            },
+            propagations: 0,
+        };
+        for summand in equation.lhs {
+            constraint.literals.insert(
+                summand.variable_index as usize,
+                Literal {
+                    index: summand.variable_index,
+                    factor: summand.factor as u128,
+                    positive: summand.positive,
+                },
+            );
+            constraint.unassigned_literals.insert(
+                summand.variable_index as usize,
+                Literal {
+                    index: summand.variable_index,
+                    factor: summand.factor as u128,
+                    positive: summand.positive,
                 },
-            };
-            for summand in equation.lhs {
-                constraint.literals.insert(
-                    summand.variable_index as usize,
-                    Literal {
-                        index: summand.variable_index,
-                        factor: summand.factor as u128,
-                        positive: summand.positive,
-                    },
-                );
-                constraint.unassigned_literals.insert(
-                    summand.variable_index as usize,
-                    Literal {
-                        index: summand.variable_index,
-                        factor: summand.factor as u128,
-                        positive: summand.positive,
-                    },
-                );
-                pseudo_boolean_formula
-                    .constraints_by_variable
-                    .get_mut(summand.variable_index as usize)
-                    .unwrap()
-                    .push(constraint_counter as usize);
+            );
+            self.constraints_by_variable
+                .get_mut(summand.variable_index as usize)
+                .unwrap()
+                .push(constraint_counter);
+        }
+        constraint.saturate();
+        self.constraints.push(constraint);
+    }
+
+    /// Marks the formula as contradictory because of an equation, an equation with = is recorded
+    /// once even if both of its constraints can not be satisfied.
+    fn mark_contradictory(&mut self, equation_index: usize) {
+        self.contradictory = true;
+        if self.contradictory_equations.last() != Some(&equation_index) {
+            self.contradictory_equations.push(equation_index);
+        }
+    }
+
+    /// Checks if a constraint with the same literals, degree and type already exists. Such a
+    /// constraint would only cost propagation work without changing the models of the formula.
+    /// Only the constraints of the first variable of the equation have to be compared. The factors
+    /// of the equation are compared after saturation, see `Constraint::saturate`.
+    /// # Arguments
+    /// * `equation` - The normalized equation
+    /// * `constraint_type` - The type of the constraint built from the equation
+    /// # Returns
+    /// true if the formula already contains an identical constraint
+    fn is_duplicate(&self, equation: &Equation, constraint_type: &ConstraintType) -> bool {
+        let Some(first_summand) = equation.lhs.first() else {
+            return false;
+        };
+        self.constraints_by_variable[first_summand.variable_index as usize]
+            .iter()
+            .map(|index| &self.constraints[*index])
+            .any(|constraint| {
+                constraint.degree == equation.rhs
+                    && constraint.constraint_type == *constraint_type
+                    && constraint.literals.len() == equation.lhs.len()
+                    && equation.lhs.iter().all(|summand| {
+                        constraint
+                            .literals
+                            .get(&(summand.variable_index as usize))
+                            .is_some_and(|literal| {
+                                literal.factor == saturated_factor(summand, constraint)
+                                    && literal.positive == summand.positive
+                            })
+                    })
+            })
+    }
+}
+
+/// Writes the formula as the solver sees it after the normalization and saturation: the header as
+/// a comment and one constraint per line, see `Constraint::to_equation`. Variables without a name
+/// are written as `_x` followed by their index starting at 1, with underscores appended until the
+/// name is not used by another variable.
+impl Display for PseudoBooleanFormula {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "* #variable= {} #constraint= {}",
+            self.number_variables,
+            self.constraints.len()
+        )?;
+        let mut name_map = self.name_map.clone();
+        for constraint in &self.constraints {
+            for index in constraint.variables() {
+                if !name_map.contains_right(&index) {
+                    let mut name = format!("_x{}", index + 1);
+                    while name_map.contains_left(&name) {
+                        name.push('_');
+                    }
+                    name_map.insert(name, index);
+                }
             }
-            constraint.max_literal = constraint.get_max_literal();
-            pseudo_boolean_formula.constraints.push(constraint);
-            constraint_counter += 1;
         }
-        pseudo_boolean_formula
+        for constraint in &self.constraints {
+            writeln!(f, "{}", constraint.to_equation(&name_map))?;
+        }
+        Ok(())
+    }
+}
+
+/// The factor of a summand of a normalized equation after the saturation of the constraint.
+fn saturated_factor(summand: &Summand, constraint: &Constraint) -> u128 {
+    match constraint.constraint_type {
+        GreaterEqual => (summand.factor as u128).min(constraint.degree as u128),
+        NotEqual => summand.factor as u128,
     }
 }
 
+/// Transforms an equation into equations with >= or != and positive factors only.
+/// The right-hand side of a strict comparison is shifted by one only once, before it is negated.
+fn normalize_equation(equation: &Equation) -> Result<Vec<Equation>, String> {
+    let mut equation_list = Vec::new();
+    for equation in replace_equal_equations(equation) {
+        let equation = replace_strict_equations(&equation)?;
+        let equation = replace_le_equations(&equation)?;
+        let equation = add_up_same_variables(&equation)?;
+        equation_list.push(replace_negative_factors(&equation)?);
+    }
+    if equation_list
+        .iter()
+        .any(|e| e.lhs.iter().any(|s| s.factor < 0))
+    {
+        return Err("the factors of a normalized constraint must not be negative".to_string());
+    }
+    Ok(equation_list)
+}
+
+/// Sums up the (positive) factors of a normalized equation. As the sum of the true literals of a
+/// constraint never exceeds this sum, the counters of a constraint can not overflow during solving
+/// if this sum fits into an u128.
+fn get_factor_sum(equation: &Equation) -> Result<u128, String> {
+    equation
+        .lhs
+        .iter()
+        .try_fold(0u128, |sum, s| sum.checked_add(s.factor as u128))
+        .ok_or_else(|| {
+            format!(
+                "the sum of the coefficients of a constraint exceeds {}",
+                u128::MAX
+            )
+        })
+}
+
 impl Constraint {
-    pub fn propagate(
+    /// Returns the indexes of all variables of the constraint in ascending order, assigned or not.
+    pub fn variables(&self) -> impl Iterator<Item = u32> + '_ {
+        self.literals.values().map(|literal| literal.index)
+    }
+
+    /// Returns the indexes of the unassigned variables of the constraint in ascending order.
+    pub fn unassigned_variables(&self) -> impl Iterator<Item = u32> + '_ {
+        self.unassigned_literals
+            .values()
+            .map(|literal| literal.index)
+    }
+
+    /// Creates the OPB representation of the constraint with the names of the variables, e.g. to
+    /// inspect learned clauses. Negated literals are prefixed with `~`.
+    /// # Arguments
+    /// * `name_map` - The names of the variables by their index
+    /// # Returns
+    /// The constraint as `sum factor literal >= degree;` or `sum factor literal != degree;`
+    /// # Panics
+    /// Panics if a variable of the constraint has no name
+    pub fn to_equation(&self, name_map: &BiMap<String, u32>) -> String {
+        Equation {
+            lhs: self
+                .literals
+                .values()
+                .map(|literal| Summand {
+                    variable_index: literal.index,
+                    factor: literal.factor as i128,
+                    positive: literal.positive,
+                })
+                .collect(),
+            rhs: self.degree,
+            kind: match self.constraint_type {
+                GreaterEqual => EquationKind::Ge,
+                NotEqual => EquationKind::NotEq,
+            },
+        }
+        .to_string(name_map)
+    }
+
+    /// Reduces every factor of a constraint with >= that exceeds the degree to the degree. A literal
+    /// with such a factor satisfies the constraint on its own either way, so the models do not
+    /// change, but the sums of the constraint get smaller and `max_literal` implies its literal
+    /// earlier. The sums and the maximal literal are recomputed afterwards.
+    pub fn saturate(&mut self) {
+        if self.constraint_type == GreaterEqual && self.degree > 0 {
+            let degree = self.degree as u128;
+            for literal in self
+                .literals
+                .values_mut()
+                .chain(self.unassigned_literals.values_mut())
+            {
+                literal.factor = literal.factor.min(degree);
+            }
+        }
+        self.factor_sum = self.literals.values().map(|literal| literal.factor).sum();
+        self.sum_unassigned = self
+            .unassigned_literals
+            .values()
+            .map(|literal| literal.factor)
+            .sum();
+        self.sum_true = self
+            .assignments
+            .iter()
+            .filter(|(index, (sign, _, _))| self.literals[index].positive == *sign)
+            .map(|(index, _)| self.literals[index].factor)
+            .sum();
+        self.max_literal = self.get_max_literal();
+        self.hash_value_old = true;
+    }
+
+    /// Assigns a literal of the constraint and checks the consequences for the other literals.
+    /// Every result that implies literals or is a conflict counts as a propagation of the constraint.
+    pub(crate) fn propagate(
+        &mut self,
+        literal: Literal,
+        assignment_kind: AssignmentKind,
+        decision_level: u32,
+    ) -> PropagationResult {
+        let result = self.assign(literal, assignment_kind, decision_level);
+        if matches!(
+            result,
+            Conflict(_) | ImpliedLiteral(_) | ImpliedLiteralList(_)
+        ) {
+            self.propagations += 1;
+        }
+        result
+    }
+
+    fn assign(
         &mut self,
         literal: Literal,
         assignment_kind: AssignmentKind,
@@ -199,16 +677,11 @@ impl Constraint {
             if *a == literal.positive {
                 return NothingToPropagated;
             } else {
-                println!("2");
-                return Unsatisfied;
+                return Conflict(self.index);
             }
         }
 
-        let already_satisfied = if self.constraint_type == GreaterEqual {
-            self.sum_true >= self.degree as u128
-        } else {
-            self.sum_unassigned == 0 && self.sum_true != self.degree as u128
-        };
+        let already_satisfied = self.is_satisfied();
 
         if already_satisfied {
             return AlreadySatisfied;
@@ -241,16 +714,18 @@ impl Constraint {
                 self.hash_value_old = true;
 
                 if self.constraint_type == NotEqual {
-                    if self.sum_unassigned == 0 && self.sum_true != self.degree as u128 {
+                    if self.is_satisfied() {
                         // fulfilled
                         return if already_satisfied {
                             AlreadySatisfied
                         } else {
                             Satisfied
                         };
-                    } else if self.sum_unassigned == 0 && self.sum_true == self.degree as u128 {
+                    } else if self.is_violated() {
                         // violated
-                        return Unsatisfied;
+                        return Conflict(self.index);
+                    } else if let Some(implied_literal) = self.get_not_equal_implied_literal() {
+                        return ImpliedLiteral(implied_literal);
                     } else {
                         return NothingToPropagated;
                     }
@@ -258,16 +733,16 @@ impl Constraint {
 
                 self.max_literal = self.get_max_literal();
 
-                if self.sum_true >= self.degree as u128 {
+                if self.is_satisfied() {
                     // fulfilled
                     return if already_satisfied {
                         AlreadySatisfied
                     } else {
                         Satisfied
                     };
-                } else if self.sum_true + self.sum_unassigned < self.degree as u128 {
+                } else if self.is_violated() {
                     // violated
-                    return Unsatisfied;
+                    return Conflict(self.index);
                 } else if self.sum_true + self.sum_unassigned == self.degree as u128 {
                     let mut implied_literals = Vec::new();
                     for (index, unassigned_literal) in &self.unassigned_literals {
@@ -298,11 +773,7 @@ impl Constraint {
                 if literal.factor > self.max_literal.factor {
                     self.max_literal = literal.clone();
                 }
-                let satisfied_before_undo = if self.constraint_type == GreaterEqual {
-                    self.sum_true >= self.degree as u128
-                } else {
-                    self.sum_unassigned == 0 && self.sum_true != self.degree as u128
-                };
+                let satisfied_before_undo = self.is_satisfied();
                 self.unassigned_literals
                     .insert(literal.index as usize, literal.clone());
                 self.assignments.remove(&(variable_index as usize));
@@ -310,11 +781,7 @@ impl Constraint {
                 if literal.positive == variable_sign {
                     self.sum_true -= literal.factor;
                 }
-                let satisfied_after_undo = if self.constraint_type == GreaterEqual {
-                    self.sum_true >= self.degree as u128
-                } else {
-                    self.sum_unassigned == 0 && self.sum_true != self.degree as u128
-                };
+                let satisfied_after_undo = self.is_satisfied();
                 self.hash_value_old = true;
                 if satisfied_before_undo && !satisfied_after_undo {
                     return true;
@@ -326,23 +793,25 @@ impl Constraint {
 
     pub fn simplify(&mut self) -> PropagationResult {
         if self.constraint_type == NotEqual {
-            if self.sum_unassigned == 0 && self.sum_true != self.degree as u128 {
+            if self.is_satisfied() {
                 // fulfilled
                 return Satisfied;
-            } else if self.sum_unassigned == 0 && self.sum_true == self.degree as u128 {
+            } else if self.is_violated() {
                 // violated
-                return Unsatisfied;
+                return Conflict(self.index);
+            } else if let Some(implied_literal) = self.get_not_equal_implied_literal() {
+                return ImpliedLiteral(implied_literal);
             } else {
                 return NothingToPropagated;
             }
         }
 
-        if self.sum_true >= self.degree as u128 {
+        if self.is_satisfied() {
             // fulfilled
             return Satisfied;
-        } else if self.sum_true + self.sum_unassigned < self.degree as u128 {
+        } else if self.is_violated() {
             // violated
-            return Unsatisfied;
+            return Conflict(self.index);
         } else if self.sum_true + self.sum_unassigned == self.degree as u128 {
             let mut implied_literals = Vec::new();
             for (index, unassigned_literal) in &self.unassigned_literals {
@@ -364,15 +833,31 @@ impl Constraint {
         NothingToPropagated
     }
 
-    pub fn is_unsatisfied(&self) -> bool {
+    /// Checks if the constraint is satisfied by the current assignment, whatever the values of the
+    /// unassigned literals. A constraint with >= is satisfied as soon as the true literals reach the
+    /// degree. A constraint with != is only decided once all of its literals are assigned, even if
+    /// the sum of the true literals already exceeds the degree. A constraint that is neither
+    /// satisfied nor violated is still open and takes part in the search.
+    pub fn is_satisfied(&self) -> bool {
+        if self.constraint_type == GreaterEqual {
+            self.sum_true >= self.degree as u128
+        } else {
+            self.sum_unassigned == 0 && self.sum_true != self.degree as u128
+        }
+    }
+
+    /// Checks if the constraint can no longer be satisfied by any value of the unassigned literals.
+    /// A constraint with >= is violated if even all unassigned literals can not reach the degree,
+    /// one with != if all of its literals are assigned and their sum equals the degree.
+    pub fn is_violated(&self) -> bool {
         if self.constraint_type == GreaterEqual {
-            self.sum_true < self.degree as u128
+            self.sum_true + self.sum_unassigned < self.degree as u128
         } else {
-            self.sum_unassigned != 0 || self.sum_true == self.degree as u128
+            self.sum_unassigned == 0 && self.sum_true == self.degree as u128
         }
     }
 
-    pub fn calculate_reason(
+    pub(crate) fn calculate_reason(
         &self,
         propagated_variable_index: usize,
     ) -> BTreeMap<usize, (AssignmentKind, bool, u32)> {
@@ -385,6 +870,31 @@ impl Constraint {
         result
     }
 
+    /// Returns the literal implied by a constraint with != if exactly one literal is unassigned.
+    /// If the sum of the true literals already equals the degree, the last literal has to be
+    /// true. If the sum only equals the degree when the last literal is true, it has to be false.
+    /// In all other cases both values of the last literal satisfy the constraint and nothing is
+    /// implied. With two or more unassigned literals nothing is implied either, even if only
+    /// one value of a literal could lead to the degree.
+    fn get_not_equal_implied_literal(&self) -> Option<Literal> {
+        if self.unassigned_literals.len() != 1 {
+            return None;
+        }
+        let (index, last_literal) = self.unassigned_literals.iter().next()?;
+        let positive = if self.sum_true == self.degree as u128 {
+            last_literal.positive
+        } else if self.sum_true + last_literal.factor == self.degree as u128 {
+            !last_literal.positive
+        } else {
+            return None;
+        };
+        Some(Literal {
+            index: *index as u32,
+            factor: last_literal.factor,
+            positive,
+        })
+    }
+
     pub fn get_max_literal(&self) -> Literal {
         let mut max_literal_factor = 0;
         let mut max_literal_index = 0;
@@ -422,85 +932,85 @@ fn replace_equal_equations(equation: &Equation) -> Vec<Equation> {
     }
 }
 
-fn replace_le_equations(equation: &Equation) -> Equation {
+fn replace_le_equations(equation: &Equation) -> Result<Equation, String> {
     if equation.kind == Le {
-        let mut e = Equation {
-            lhs: equation.lhs.clone(),
-            rhs: -1 * equation.rhs,
-            kind: EquationKind::Ge,
-        };
-        e.lhs = e
-            .lhs
-            .iter()
-            .map(|s| Summand {
-                variable_index: s.variable_index,
-                factor: -1 * s.factor,
-                positive: s.positive,
-            })
-            .collect();
-        e
+        negate_equation(equation, EquationKind::Ge)
     } else {
-        equation.clone()
+        Ok(equation.clone())
     }
 }
 
-fn replace_l_equations(equation: &Equation) -> Equation {
-    if equation.kind == L {
-        let mut e = Equation {
-            lhs: equation.lhs.clone(),
-            rhs: -1 * equation.rhs,
-            kind: EquationKind::G,
-        };
-        e.lhs = e
-            .lhs
-            .iter()
-            .map(|s| Summand {
-                variable_index: s.variable_index,
-                factor: -1 * s.factor,
-                positive: s.positive,
-            })
-            .collect();
-        e
-    } else {
-        equation.clone()
+/// Multiplies both sides of an equation by -1 and changes the kind of the equation accordingly.
+fn negate_equation(equation: &Equation, kind: EquationKind) -> Result<Equation, String> {
+    let mut lhs = Vec::with_capacity(equation.lhs.len());
+    for s in &equation.lhs {
+        lhs.push(Summand {
+            variable_index: s.variable_index,
+            factor: s.factor.checked_neg().ok_or_else(coefficient_overflow)?,
+            positive: s.positive,
+        });
     }
+    Ok(Equation {
+        lhs,
+        rhs: equation
+            .rhs
+            .checked_neg()
+            .ok_or_else(coefficient_overflow)?,
+        kind,
+    })
 }
 
-fn replace_g_equations(equation: &Equation) -> Equation {
-    if equation.kind == G {
-        let e = Equation {
-            lhs: equation.lhs.clone(),
-            rhs: equation.rhs + 1,
-            kind: EquationKind::Ge,
-        };
-        e
-    } else {
-        equation.clone()
-    }
+/// Replaces > and < by >= and <=. The left-hand side only takes integer values, so `> rhs` is
+/// the same as `>= rhs + 1` and `< rhs` the same as `<= rhs - 1`. This is the only step of the
+/// normalization that shifts the right-hand side, the following steps keep the bound.
+fn replace_strict_equations(equation: &Equation) -> Result<Equation, String> {
+    let (rhs, kind) = match equation.kind {
+        G => (equation.rhs.checked_add(1), EquationKind::Ge),
+        L => (equation.rhs.checked_sub(1), EquationKind::Le),
+        _ => return Ok(equation.clone()),
+    };
+    Ok(Equation {
+        lhs: equation.lhs.clone(),
+        rhs: rhs.ok_or_else(coefficient_overflow)?,
+        kind,
+    })
 }
 
-fn replace_negative_factors(equation: &Equation) -> Equation {
+fn replace_negative_factors(equation: &Equation) -> Result<Equation, String> {
     let mut new_equation = Equation {
         lhs: Vec::new(),
-        rhs: equation.rhs.clone(),
+        rhs: equation.rhs,
         kind: equation.kind.clone(),
     };
     for s in &equation.lhs {
         if s.factor < 0 {
             new_equation.lhs.push(Summand {
-                factor: -1 * s.factor,
+                factor: s.factor.checked_neg().ok_or_else(coefficient_overflow)?,
                 variable_index: s.variable_index,
                 positive: !s.positive,
             });
-            new_equation.rhs -= s.factor;
+            new_equation.rhs = new_equation
+                .rhs
+                .checked_sub(s.factor)
+                .ok_or_else(coefficient_overflow)?;
         } else {
             new_equation.lhs.push(s.clone());
         }
     }
-    new_equation
+    Ok(new_equation)
 }
 
-fn add_up_same_variables(equation: &Equation) -> Equation {
+fn coefficient_overflow() -> String {
+    format!(
+        "the coefficients of a constraint must stay between {} and {} during normalization",
+        i128::MIN,
+        i128::MAX
+    )
+}
+
+/// Merges the summands of the same variable into one summand with the sign of the first summand.
+/// A summand with the opposite sign is rewritten first, as `a ~x = a - a x`.
+fn add_up_same_variables(equation: &Equation) -> Result<Equation, String> {
     let mut new_equation = Equation {
         lhs: Vec::new(),
         rhs: equation.rhs.clone(),
@@ -523,32 +1033,58 @@ fn add_up_same_variables(equation: &Equation) -> Equation {
         };
 
         for j in i + 1..equation.lhs.len() {
-            if summand.variable_index == equation.lhs.get(j).unwrap().variable_index {
-                summand.factor += equation.lhs.get(j).unwrap().factor;
+            let other_summand = equation.lhs.get(j).unwrap();
+            if summand.variable_index != other_summand.variable_index {
+                continue;
+            }
+            if summand.positive == other_summand.positive {
+                summand.factor = summand
+                    .factor
+                    .checked_add(other_summand.factor)
+                    .ok_or_else(coefficient_overflow)?;
+            } else {
+                summand.factor = summand
+                    .factor
+                    .checked_sub(other_summand.factor)
+                    .ok_or_else(coefficient_overflow)?;
+                new_equation.rhs = new_equation
+                    .rhs
+                    .checked_sub(other_summand.factor)
+                    .ok_or_else(coefficient_overflow)?;
             }
         }
         new_equation.lhs.push(summand)
     }
 
-    new_equation
+    Ok(new_equation)
 }
 
 impl PseudoBooleanFormula {
     fn hash<H: Hasher>(&mut self, state: &mut H, constraints_in_scope: &BTreeSet<usize>) {
         for ci in constraints_in_scope {
             let constraint = self.constraints.get_mut(*ci).unwrap();
-            if constraint.is_unsatisfied() {
+            if !constraint.is_satisfied() {
                 constraint.calculate_hash().hash(state);
             }
         }
     }
 }
 
+/// Calculates the cache key of the current subformula.
+/// The assignment itself is not needed, the scope and `sum_true` already determine the subformula:
+/// assigned variables are removed from the scope and all unassigned variables of a constraint in
+/// scope are in scope as well (a component never separates a constraint from its variables).
+/// The unassigned literals of a constraint are therefore its literals in scope. The degree and type
+/// are fixed for a constraint index, so the remaining degree only depends on `sum_true`.
+/// Two assignments with the same key only differ in the assigned variables and leave the same
+/// subformula, so they have the same model count.
+/// # Arguments
+/// * `variables_in_scope` - The unassigned variables of the current subformula
+/// * `pseudo_boolean_formula` - The formula with the current assignment
+/// * `constraint_indexes_in_scope` - The unsatisfied constraints of the current subformula
 pub fn calculate_hash(
     variables_in_scope: &BTreeSet<usize>,
-    assigments: &Vec<Option<(u32, bool)>>,
-    t: &mut PseudoBooleanFormula,
-    n: u32,
+    pseudo_boolean_formula: &PseudoBooleanFormula,
     constraint_indexes_in_scope: &BTreeSet<usize>,
 ) -> u64 {
     let mut s = DefaultHasher::new();
@@ -556,7 +1092,15 @@ pub fn calculate_hash(
     variables_in_scope.hash(&mut s);
     '|'.hash(&mut s);
     for ci in constraint_indexes_in_scope {
-        (ci, t.constraints.get(*ci).unwrap().sum_true).hash(&mut s);
+        (
+            ci,
+            pseudo_boolean_formula
+                .constraints
+                .get(*ci)
+                .unwrap()
+                .sum_true,
+        )
+            .hash(&mut s);
     }
 
     s.finish()
@@ -1,7 +1,10 @@
-use crate::partitioning::disconnected_component_datastructure::ComponentBasedFormula;
+use crate::partitioning::disconnected_component_datastructure::{Component, ComponentBasedFormula};
 use crate::partitioning::hypergraph::Hypergraph;
 use crate::solving::ddnnf::DDNNFNode::{AndNode, FalseLeave, LiteralLeave, TrueLeave};
-use crate::solving::ddnnf::{DDNNFLiteral, DDNNFNode, DDNNF};
+#[cfg(all(feature = "serde", feature = "cache"))]
+use crate::solving::ddnnf::FlatDDNNF;
+use crate::solving::ddnnf::{DDNNFLiteral, DDNNFNode, NodeTable, DDNNF};
+use crate::solving::lru_cache::LruCache;
 use crate::solving::pseudo_boolean_datastructure::ConstraintIndex::{
     LearnedClauseIndex, NormalConstraintIndex,
 };
@@ -12,92 +15,660 @@ use crate::solving::pseudo_boolean_datastructure::{
 };
 use crate::solving::solver::AssignmentKind::{FirstDecision, Propagated, SecondDecision};
 use crate::solving::solver::AssignmentStackEntry::{Assignment, ComponentBranch};
+use bimap::BiMap;
 use num_bigint::BigUint;
 use num_traits::{One, Zero};
+use p2d_opb::Equation;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::cmp::PartialEq;
-use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::collections::hash_map::DefaultHasher;
+#[cfg(all(feature = "serde", feature = "cache"))]
+use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 pub struct Solver {
     pub(crate) pseudo_boolean_formula: PseudoBooleanFormula,
     assignment_stack: Vec<AssignmentStackEntry>,
     pub(crate) assignments: Vec<Option<(u32, bool)>>,
+    /// The assignments at decision level 0 of the last search, see `current_assignment`
+    root_assignment: Vec<Option<bool>>,
     decision_level: u32,
     learned_clauses: Vec<Constraint>,
     learned_clauses_by_variables: Vec<Vec<usize>>,
+    learned_clause_activities: Vec<u32>,
+    active_learned_clauses: usize,
+    max_learned_clauses: Option<usize>,
     result_stack: Vec<BigUint>,
+    weighted_result_stack: Vec<f64>,
+    weights: Option<Vec<(f64, f64)>>,
     ddnnf_stack: Vec<Rc<DDNNFNode>>,
+    node_table: NodeTable,
+    /// The splits into disconnected components of the current search
+    component_splits: Vec<ComponentStats>,
+    /// Whether the splits are recorded in `component_splits`, see `set_record_splits`
+    record_splits: bool,
+    /// Whether the d-DNNF is not built, only the result stacks are used then
+    count_only: bool,
+    /// Only the statistics are collected, see `set_stats_only`
+    stats_only: bool,
     pub(crate) number_unsat_constraints: usize,
     pub(crate) number_unassigned_variables: u32,
-    cache: HashMap<u64, (BigUint, Rc<DDNNFNode>)>,
+    cache: LruCache<(BigUint, f64, Option<Rc<DDNNFNode>>)>,
     pub statistics: Statistics,
     pub(crate) variable_in_scope: BTreeSet<usize>,
     pub(crate) constraint_indexes_in_scope: BTreeSet<usize>,
-    progress: HashMap<u32, f32>,
+    /// The share of the search space of the current subproblem, see `complete_progress`
+    progress_weight: f64,
+    /// The share of the search space that is solved
+    completed_progress: f64,
     last_progress: f32,
     pub(crate) next_variables: Vec<u32>,
-    progress_split: u128,
     vsids_scores: Vec<f64>,
+    /// The factor all VSIDS scores are multiplied with after a conflict, see `set_vsids_decay`
+    vsids_decay: f64,
+    /// The factor of the increase of the VSIDS scores while learning a clause
+    vsids_bump: f64,
+    /// The absolute coefficients of each variable in the objective function, empty without one
+    objective_scores: Vec<f64>,
     dlcs_scores: Vec<f64>,
     unique_id: u32,
+    projected_variables: Option<Vec<bool>>,
+    /// The projection of the search that filled the cache, the cache key does not contain it
+    cached_projection: Option<Vec<bool>>,
+    /// The variables that are not counted on their own, see `set_auxiliary`
+    auxiliary_variables: BTreeSet<u32>,
+    /// The literals forced by a single constraint, they are assigned before the search, see
+    /// `PseudoBooleanFormula::unit_literals`
+    unit_literals: Vec<(u32, bool, ConstraintIndex)>,
+    /// The decision order set by the user without repeated or unknown variables, empty if no order
+    /// is set
+    variable_order: Vec<u32>,
+    /// The equations of the constraints of all conflicts so far, None if no unsatisfiable core is
+    /// recorded
+    unsat_core: Option<BTreeSet<usize>>,
+    assumptions: Vec<(u32, bool)>,
+    cancel_flag: Option<Arc<AtomicBool>>,
+    model_threshold: Option<BigUint>,
+    found_models: BigUint,
+    branching_heuristic: BranchingHeuristic,
+    rng: StdRng,
+    phase_selection: PhaseSelection,
+    saved_phases: Vec<Option<bool>>,
+    progress_callback: Option<Box<dyn FnMut(f32)>>,
+    trace_writer: Option<Box<dyn Write>>,
+    max_cut_ratio: f64,
+    min_constraints_for_decomposition: usize,
+    partition_seed: i32,
+    hypergraph_cache: LruCache<HypergraphCacheEntry>,
+    #[cfg(feature = "parallel")]
+    parallel_components: bool,
 }
 
+/// The content of a file written by `Solver::save_cache`. Every entry consists of the key, the
+/// model count, the weighted model count (if weights were set) and the position of its circuit
+/// (if the search built one).
+#[cfg(all(feature = "serde", feature = "cache"))]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CacheFile {
+    circuit: FlatDDNNF,
+    entries: Vec<(u64, BigUint, Option<f64>, Option<usize>)>,
+}
+
+/// Everything a solver passes on to the sub-solver of one of its components: the current
+/// assignments, the learned clauses and the settings of the search. Unlike a solver it can be
+/// sent to another thread, see `Solver::component_solver_setup`.
+struct ComponentSolverSetup {
+    pseudo_boolean_formula: PseudoBooleanFormula,
+    component: Component,
+    assignments: Vec<Option<(u32, bool)>>,
+    learned_clauses: Vec<Constraint>,
+    learned_clauses_by_variables: Vec<Vec<usize>>,
+    learned_clause_activities: Vec<u32>,
+    active_learned_clauses: usize,
+    max_learned_clauses: Option<usize>,
+    vsids_decay: f64,
+    vsids_bump: f64,
+    weights: Option<Vec<(f64, f64)>>,
+    decision_level: u32,
+    projected_variables: Option<Vec<bool>>,
+    cancel_flag: Option<Arc<AtomicBool>>,
+    cache_capacity: Option<usize>,
+    branching_heuristic: BranchingHeuristic,
+    phase_selection: PhaseSelection,
+    variable_order: Vec<u32>,
+    hash_consing: bool,
+    max_cut_ratio: f64,
+    min_constraints_for_decomposition: usize,
+    partition_seed: i32,
+    count_only: bool,
+    stats_only: bool,
+    unsat_core: Option<BTreeSet<usize>>,
+    record_splits: bool,
+    seed: u64,
+}
+
+impl ComponentSolverSetup {
+    /// Creates the sub-solver, its scope is the component. It does not split the component into
+    /// threads again and reports no progress.
+    fn into_solver(self) -> Solver {
+        let mut solver = Solver::new(self.pseudo_boolean_formula);
+        solver.assignments = self.assignments;
+        solver.learned_clauses = self.learned_clauses;
+        solver.learned_clauses_by_variables = self.learned_clauses_by_variables;
+        solver.learned_clause_activities = self.learned_clause_activities;
+        solver.active_learned_clauses = self.active_learned_clauses;
+        solver.max_learned_clauses = self.max_learned_clauses;
+        solver.vsids_decay = self.vsids_decay;
+        solver.vsids_bump = self.vsids_bump;
+        solver.weights = self.weights;
+        solver.decision_level = self.decision_level;
+        solver.variable_in_scope = self.component.variables;
+        solver.constraint_indexes_in_scope = self.component.constraint_indexes_in_scope;
+        solver.number_unsat_constraints = self.component.number_unsat_constraints as usize;
+        solver.number_unassigned_variables = self.component.number_unassigned_variables;
+        solver.projected_variables = self.projected_variables;
+        solver.cancel_flag = self.cancel_flag;
+        if let Some(capacity) = self.cache_capacity {
+            solver.set_cache_capacity(capacity);
+        }
+        solver.branching_heuristic = self.branching_heuristic;
+        solver.phase_selection = self.phase_selection;
+        solver.variable_order = self.variable_order;
+        solver.set_hash_consing(self.hash_consing);
+        solver.max_cut_ratio = self.max_cut_ratio;
+        solver.min_constraints_for_decomposition = self.min_constraints_for_decomposition;
+        solver.partition_seed = self.partition_seed;
+        solver.rng = StdRng::seed_from_u64(self.seed);
+        #[cfg(feature = "parallel")]
+        {
+            solver.parallel_components = false;
+        }
+        solver.count_only = self.count_only;
+        solver.stats_only = self.stats_only;
+        solver.unsat_core = self.unsat_core;
+        solver.record_splits = self.record_splits;
+        // only the solver that started the sub-solvers reports the progress
+        solver.set_progress_callback(Box::new(|_| {}));
+        solver
+    }
+}
+
+/// A hypergraph together with the scope it was built for, the scope is stored to detect hash collisions.
+type HypergraphCacheEntry = (BTreeSet<usize>, BTreeSet<usize>, Rc<Hypergraph>);
+
 impl Solver {
     pub fn new(pseudo_boolean_formula: PseudoBooleanFormula) -> Solver {
         let number_unsat_constraints = pseudo_boolean_formula.constraints.len();
         let number_variables = pseudo_boolean_formula.number_variables;
+        let unit_literals = pseudo_boolean_formula.unit_literals();
         let mut solver = Solver {
             pseudo_boolean_formula,
             assignment_stack: Vec::new(),
             decision_level: 0,
             learned_clauses_by_variables: Vec::new(),
             learned_clauses: Vec::new(),
+            learned_clause_activities: Vec::new(),
+            active_learned_clauses: 0,
+            max_learned_clauses: None,
             result_stack: Vec::new(),
+            weighted_result_stack: Vec::new(),
+            weights: None,
             ddnnf_stack: Vec::new(),
+            node_table: NodeTable::new(),
+            component_splits: Vec::new(),
+            record_splits: false,
+            count_only: false,
+            stats_only: false,
             number_unsat_constraints,
             number_unassigned_variables: number_variables,
-            cache: HashMap::with_capacity(100),
+            cache: LruCache::new(),
             statistics: Statistics {
+                decisions: 0,
+                conflicts: 0,
+                propagations: 0,
+                backtracks: 0,
                 cache_hits: 0,
                 time_to_compute: 0,
                 cache_entries: 0,
+                cache_evictions: 0,
+                peak_cache_entries: 0,
+                learned_clause_literals: 0,
+                ddnnf_node_count: 0,
                 learned_clauses: 0,
                 propagations_from_learned_clauses: 0,
+                learned_clause_implications: 0,
             },
             assignments: Vec::new(),
+            root_assignment: vec![None; number_variables as usize],
             variable_in_scope: BTreeSet::new(),
-            progress: HashMap::new(),
+            progress_weight: 1.0,
+            completed_progress: 0.0,
             last_progress: -1.0,
             constraint_indexes_in_scope: BTreeSet::new(),
             next_variables: Vec::new(),
-            progress_split: 1,
             vsids_scores: Vec::new(),
+            vsids_decay: 1.0,
+            vsids_bump: 1.0,
+            objective_scores: Vec::new(),
             dlcs_scores: Vec::new(),
             unique_id: 0,
+            projected_variables: None,
+            cached_projection: None,
+            auxiliary_variables: BTreeSet::new(),
+            unit_literals,
+            variable_order: Vec::new(),
+            unsat_core: None,
+            assumptions: Vec::new(),
+            cancel_flag: None,
+            model_threshold: None,
+            found_models: BigUint::zero(),
+            branching_heuristic: BranchingHeuristic::Vsids,
+            rng: StdRng::from_entropy(),
+            phase_selection: PhaseSelection::AlwaysTrue,
+            saved_phases: Vec::new(),
+            progress_callback: None,
+            trace_writer: None,
+            max_cut_ratio: f64::INFINITY,
+            min_constraints_for_decomposition: 2,
+            partition_seed: 1,
+            hypergraph_cache: LruCache::new(),
+            #[cfg(feature = "parallel")]
+            parallel_components: true,
         };
+        solver.hypergraph_cache.set_capacity(0);
         for i in 0..number_variables {
             solver.assignments.push(None);
             solver.variable_in_scope.insert(i as usize);
             solver.learned_clauses_by_variables.push(Vec::new());
             solver.vsids_scores.push(1.0);
             solver.dlcs_scores.push(0.0);
+            solver.saved_phases.push(None);
         }
         for c in &solver.pseudo_boolean_formula.constraints {
             if let NormalConstraintIndex(i) = c.index {
                 solver.constraint_indexes_in_scope.insert(i);
             }
             for (i, l) in &c.literals {
-                solver.dlcs_scores[*i] = l.factor as f64 / c.degree as f64;
+                solver.dlcs_scores[*i] = dlcs_score(l.factor, c.degree);
+            }
+        }
+        if let Some(objective) = &solver.pseudo_boolean_formula.objective {
+            solver.objective_scores = vec![0.0; number_variables as usize];
+            for summand in objective {
+                solver.objective_scores[summand.variable_index as usize] +=
+                    summand.factor.unsigned_abs() as f64;
             }
         }
         solver
     }
 
+    /// Sets the heuristic that is used to choose the next decision variable.
+    /// The heuristic only changes the search order, not the model count.
+    pub fn set_branching_heuristic(&mut self, branching_heuristic: BranchingHeuristic) {
+        self.branching_heuristic = branching_heuristic;
+    }
+
+    /// Sets a function that receives the progress in percent whenever it changes instead of printing it.
+    /// The progress is only tracked if the feature `show_progress` is enabled.
+    pub fn set_progress_callback(&mut self, progress_callback: Box<dyn FnMut(f32)>) {
+        self.progress_callback = Some(progress_callback);
+    }
+
+    /// Sets a writer that receives a trace of how the model count is computed, one line per step:
+    /// `SPLIT k=..` when the formula falls apart into k components, `CACHE_HIT hash=.. count=..`
+    /// when a result is taken from the cache, `LEAF count=..` for every satisfied or conflicting
+    /// assignment and `COMBINE .. => ..` whenever the results of the two branches of a decision
+    /// (`+`) or of all components (`*`) are combined. The last `COMBINE` of a search is the model
+    /// count of the whole formula. Errors while writing are ignored, they must not stop the search.
+    /// Without a writer the lines are never formatted.
+    pub fn set_trace_writer(&mut self, trace_writer: Box<dyn Write>) {
+        self.trace_writer = Some(trace_writer);
+    }
+
+    /// Writes a line to the trace writer if there is one.
+    /// # Arguments
+    /// * `line` - Creates the line, it is only called if a trace writer is set
+    fn trace(&mut self, line: impl FnOnce(&Solver) -> String) {
+        if let Some(mut trace_writer) = self.trace_writer.take() {
+            let _ = writeln!(trace_writer, "{}", line(self));
+            self.trace_writer = Some(trace_writer);
+        }
+    }
+
+    /// Sets the maximum size of a cut relative to the number of constraints in the current component.
+    /// The variables of a cut are only preferred during branching if the cut is at most this large,
+    /// a ratio of 0 disables branching on cut variables. By default every cut is used.
+    pub fn set_max_cut_ratio(&mut self, max_cut_ratio: f64) {
+        self.max_cut_ratio = max_cut_ratio;
+    }
+
+    /// Sets the minimum number of unsatisfied constraints in the current component for which the
+    /// solver looks for disconnected components. Below this size no hypergraph is built, which
+    /// saves its construction on instances with many tiny components. The model count does not
+    /// change. On financialservices01.opb a minimum of 4 to 8 is about 10% faster, while 64 or more
+    /// is several times slower, as large components are no longer split. By default every
+    /// component with at least 2 unsatisfied constraints is decomposed.
+    pub fn set_min_constraints_for_decomposition(&mut self, min_constraints: usize) {
+        self.min_constraints_for_decomposition = min_constraints;
+    }
+
+    /// Sets the seed of the hypergraph partitioner that looks for good cuts. Different seeds can
+    /// lead to different cuts and thus to a different search, but never to a different model
+    /// count. The seed is 1 by default.
+    pub fn set_partition_seed(&mut self, seed: i32) {
+        self.partition_seed = seed;
+    }
+
+    /// Sets how many hypergraphs are kept to be reused when the same scope occurs again.
+    /// Reusing is disabled by default, on automotive2_4.opb only about 7% of the hypergraphs are
+    /// reused and storing them costs more time than it saves.
+    pub fn set_hypergraph_cache_capacity(&mut self, capacity: usize) {
+        self.hypergraph_cache.set_capacity(capacity);
+    }
+
+    /// Limits the number of cached subformula results. When the cache is full, the least recently
+    /// used entry is evicted.
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.statistics.cache_evictions += self.cache.set_capacity(capacity);
+        self.statistics.cache_entries = self.cache.len();
+    }
+
+    /// Writes the cached subformula results (model counts and circuits) to a file, so they can be
+    /// loaded by a later run with `load_cache`. The results of a count-only search are written
+    /// without a circuit.
+    /// The keys of the cache are hashes of the subformulas that depend on the indexes of the
+    /// variables and constraints. A saved cache is therefore only valid for the same formula and
+    /// a binary built with the same Rust version. The weighted counts are only valid for the same
+    /// weights.
+    /// # Arguments
+    /// * `path` - The file to write the cache to
+    #[cfg(all(feature = "serde", feature = "cache"))]
+    pub fn save_cache(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let mut circuit = FlatDDNNF::new();
+        let mut ids = HashMap::new();
+        let entries = self
+            .cache
+            .iter()
+            .map(|(key, (mc, weighted_mc, ddnnf_ref))| {
+                (
+                    key,
+                    mc.clone(),
+                    self.weights.as_ref().map(|_| *weighted_mc),
+                    ddnnf_ref
+                        .as_ref()
+                        .map(|ddnnf_ref| circuit.add_node(ddnnf_ref, &mut ids)),
+                )
+            })
+            .collect();
+        let cache_file = CacheFile { circuit, entries };
+        let content = serde_json::to_string(&cache_file)
+            .map_err(|e| format!("cannot serialize cache: {e}"))?;
+        fs::write(path, content).map_err(|e| format!("cannot write cache: {e}"))
+    }
+
+    /// Adds the subformula results saved by `save_cache` to the cache. The cache has to be saved
+    /// for the same formula, otherwise the model count is wrong. Entries that can not be used by
+    /// the next search are skipped: entries without a circuit unless the search is count-only, and
+    /// entries without a weighted count if weights are set.
+    /// # Arguments
+    /// * `path` - The file to read the cache from
+    #[cfg(all(feature = "serde", feature = "cache"))]
+    pub fn load_cache(&mut self, path: impl AsRef<Path>) -> Result<(), String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("cannot read cache: {e}"))?;
+        let cache_file: CacheFile =
+            serde_json::from_str(&content).map_err(|e| format!("cannot deserialize cache: {e}"))?;
+        let nodes = cache_file.circuit.into_nodes(|| self.get_unique_id());
+        for (key, mc, weighted_mc, node) in cache_file.entries {
+            let ddnnf_ref = node
+                .map(|node| {
+                    nodes
+                        .get(node)
+                        .map(Rc::clone)
+                        .ok_or(format!("cannot deserialize cache: unknown node {node}"))
+                })
+                .transpose()?;
+            if ddnnf_ref.is_none() && !self.count_only
+                || weighted_mc.is_none() && self.weights.is_some()
+            {
+                continue;
+            }
+            // the weighted count is not used without weights
+            let weighted_mc = weighted_mc.unwrap_or(f64::NAN);
+            let ddnnf_ref = ddnnf_ref.filter(|_| !self.count_only);
+            self.statistics.cache_evictions += self.cache.insert(key, (mc, weighted_mc, ddnnf_ref));
+        }
+        self.statistics.cache_entries = self.cache.len();
+        self.statistics.peak_cache_entries = self
+            .statistics
+            .peak_cache_entries
+            .max(self.statistics.cache_entries);
+        Ok(())
+    }
+
+    /// Limits the number of learned clauses. Whenever a new clause exceeds the limit, the least active
+    /// half of the learned clauses is deleted. A clause is active if it implies a literal, causes a
+    /// conflict or is used while learning a new clause.
+    pub fn set_max_learned_clauses(&mut self, max_learned_clauses: usize) {
+        self.max_learned_clauses = Some(max_learned_clauses);
+    }
+
+    /// Sets the factor all VSIDS scores are multiplied with after every conflict, before the
+    /// variables of the learned clause are bumped. A factor below 1.0 lets recent conflicts count
+    /// more than old ones, 1.0 (the default) disables the decay. Without clause learning the scores
+    /// never change. The decay only changes the search order, not the model count.
+    /// # Panics
+    /// Panics if the decay is not in (0, 1]
+    pub fn set_vsids_decay(&mut self, decay: f64) {
+        assert!(
+            decay > 0.0 && decay <= 1.0,
+            "the VSIDS decay has to be in (0, 1], got {decay}"
+        );
+        self.vsids_decay = decay;
+    }
+
+    /// Sets the factor of the increase of the VSIDS scores of the variables of a learned clause.
+    /// Each score increases by the bump times the factor of the literal divided by the missing
+    /// degree of the clause. The default is 1.0. The bump only changes the search order, not the
+    /// model count.
+    /// # Panics
+    /// Panics if the bump is negative or not finite
+    pub fn set_vsids_bump(&mut self, bump: f64) {
+        assert!(
+            bump >= 0.0 && bump.is_finite(),
+            "the VSIDS bump has to be a non-negative number, got {bump}"
+        );
+        self.vsids_bump = bump;
+    }
+
+    /// Sets the weights of the literals for weighted model counting. The weighted model count is the
+    /// sum over all models of the product of the weights of their literals.
+    /// The cache is cleared, as the cached weighted counts depend on the weights.
+    /// # Arguments
+    /// * `weights` - The weight of the positive and the negative literal of each variable
+    pub fn set_weights(&mut self, weights: Vec<(f64, f64)>) {
+        self.weights = Some(weights);
+        let capacity = self.cache.capacity();
+        self.cache = LruCache::new();
+        if let Some(capacity) = capacity {
+            self.cache.set_capacity(capacity);
+        }
+        self.statistics.cache_entries = 0;
+    }
+
+    /// Fixes the order of the decisions. The next decision variable is the earliest variable of the
+    /// order that is unassigned and occurs in an unsatisfied constraint of the current component,
+    /// even if a cut suggests other variables. Only if no variable of the order is eligible, the
+    /// cut and then the branching heuristic choose the variable. The order only changes the search
+    /// order, not the model count. An empty order removes the fixed order.
+    /// # Arguments
+    /// * `order` - The indexes of the variables (starting at 0) in the order of the decisions
+    pub fn set_variable_order(&mut self, order: Vec<u32>) {
+        let mut ordered = vec![false; self.pseudo_boolean_formula.number_variables as usize];
+        // a repeated variable keeps its first position
+        self.variable_order = order
+            .into_iter()
+            .filter(|variable_index| {
+                ordered
+                    .get_mut(*variable_index as usize)
+                    .is_some_and(|seen| !std::mem::replace(seen, true))
+            })
+            .collect();
+    }
+
+    /// Marks variables as auxiliary, e.g. the helper variables of a Tseitin encoding. Models that
+    /// only differ in auxiliary variables are counted once, i.e. the count is projected onto the
+    /// other variables. This also applies to `solve_projected`, whose projection then excludes the
+    /// auxiliary variables. As with every projection, no weighted count is computed, and the cache
+    /// is cleared by the next search if the projection changes. An empty set counts all variables
+    /// again.
+    /// # Arguments
+    /// * `auxiliary` - The indexes of the auxiliary variables
+    pub fn set_auxiliary(&mut self, auxiliary: &BTreeSet<u32>) {
+        self.auxiliary_variables = auxiliary.clone();
+    }
+
+    /// Reads hints for the search from a file, e.g. an order and phases that worked well for a
+    /// similar instance. Every line contains the name of a variable and its phase (1 or 0), empty
+    /// lines and lines starting with `*` are skipped. The variables are decided in the order of the
+    /// file (see `set_variable_order`) and first get the phase of the file, which also sets the
+    /// phase selection to `PhaseSelection::Saved`. Like the order and the phases themselves, the
+    /// hints only change the search order, not the model count.
+    /// # Arguments
+    /// * `path` - The file to read the hints from
+    /// # Returns
+    /// One warning for every line whose variable does not exist in the formula, as such lines are
+    /// ignored, or an error if the file cannot be read or a line is malformed. The solver is
+    /// unchanged in case of an error.
+    pub fn load_hints(&mut self, path: impl AsRef<Path>) -> Result<Vec<String>, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("cannot read hints: {e}"))?;
+        let mut warnings = Vec::new();
+        let mut hints = Vec::new();
+        for (line_number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('*') {
+                continue;
+            }
+            let (name, phase) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [name, "1"] => (name, true),
+                [name, "0"] => (name, false),
+                _ => {
+                    return Err(format!(
+                        "invalid hint in line {}: {line}, expected a variable and 1 or 0",
+                        line_number + 1
+                    ))
+                }
+            };
+            match self.pseudo_boolean_formula.index_of(name) {
+                Some(variable_index) => hints.push((variable_index, phase)),
+                None => warnings.push(format!(
+                    "unknown variable {name} in line {} is ignored",
+                    line_number + 1
+                )),
+            }
+        }
+        for (variable_index, phase) in &hints {
+            self.saved_phases[*variable_index as usize] = Some(*phase);
+        }
+        self.set_variable_order(hints.iter().map(|(index, _)| *index).collect());
+        self.phase_selection = PhaseSelection::Saved;
+        Ok(warnings)
+    }
+
+    /// Enables or disables recording every split of the formula into components, which is
+    /// reported in `SolverResult::component_splits`. A search can split the formula millions of
+    /// times, so the splits are only recorded on request. Disabled by default.
+    pub fn set_record_splits(&mut self, record_splits: bool) {
+        self.record_splits = record_splits;
+    }
+
+    /// Enables or disables recording an unsatisfiable core, which is reported in
+    /// `SolverResult::unsat_core` if the formula has no models. Every conflict adds the equation of
+    /// the violated constraint and, recursively, the equations of the constraints that implied its
+    /// literals. An equation that no assignment satisfies is a core on its own. The core is
+    /// therefore not minimal, but the recorded equations alone (together with the assumptions)
+    /// have no model. Enabling it clears the cache and the learned clauses, as the conflicts they
+    /// came from were not recorded. Disabled by default.
+    pub fn set_unsat_core(&mut self, unsat_core: bool) {
+        if !unsat_core {
+            self.unsat_core = None;
+            return;
+        }
+        if self.unsat_core.is_some() {
+            return;
+        }
+        self.restore_initial_state();
+        self.clear_cache();
+        self.learned_clauses.clear();
+        self.learned_clause_activities.clear();
+        self.active_learned_clauses = 0;
+        for clause_indexes in &mut self.learned_clauses_by_variables {
+            clause_indexes.clear();
+        }
+        self.unsat_core = Some(BTreeSet::new());
+    }
+
+    /// Enables or disables reusing a node of the d-DNNF when a structurally identical node is
+    /// created, e.g. the literal leaves of the same literal. The circuit then has fewer nodes, but
+    /// the same models. Enabled by default.
+    pub fn set_hash_consing(&mut self, hash_consing: bool) {
+        self.node_table.set_enabled(hash_consing);
+    }
+
+    /// Sets which phase is assigned first to a decision variable.
+    /// The phase only changes the search order, not the model count.
+    pub fn set_phase_selection(&mut self, phase_selection: PhaseSelection) {
+        self.phase_selection = phase_selection;
+    }
+
+    /// Enables or disables building the d-DNNF. Without the circuit only the (weighted) model
+    /// count is computed, which saves the memory of its nodes (about 14 million on
+    /// automotive01.opb). The run time hardly changes, it is dominated by the search. The result then
+    /// contains no d-DNNF and no witness. Changing the mode clears the cache, as cached results
+    /// of a count-only search have no circuit. Disabled by default.
+    pub fn set_count_only(&mut self, count_only: bool) {
+        if self.count_only != count_only {
+            self.count_only = count_only;
+            self.clear_cache();
+        }
+    }
+
+    /// Enables or disables exploring the search space only for the statistics. The solver makes the
+    /// same decisions, propagations, cache lookups and splits as when counting, but keeps no model
+    /// counts and builds no d-DNNF, so no big integer arithmetic is needed. The result of a search
+    /// in this mode has the model count 0. Enabling it also enables `set_count_only`. The cached
+    /// results of this mode have no count, so changing the mode clears the cache. Disabled by
+    /// default.
+    pub fn set_stats_only(&mut self, stats_only: bool) {
+        if stats_only {
+            self.set_count_only(true);
+        }
+        if self.stats_only != stats_only {
+            self.stats_only = stats_only;
+            self.clear_cache();
+        }
+    }
+
+    /// Enables or disables solving the components of the formula on separate threads. Only the
+    /// components that exist directly after the initial simplification are solved in parallel,
+    /// components found later in the search are solved sequentially. Enabled by default.
+    #[cfg(feature = "parallel")]
+    pub fn set_parallel_components(&mut self, parallel_components: bool) {
+        self.parallel_components = parallel_components;
+    }
+
     fn update_dlcs_scores(&mut self) {
         for c in &self.pseudo_boolean_formula.constraints {
             for (i, l) in &c.literals {
-                if c.is_unsatisfied() {
-                    self.dlcs_scores[*i] = l.factor as f64 / (c.degree - c.sum_true as i128) as f64;
+                if !c.is_satisfied() {
+                    self.dlcs_scores[*i] = dlcs_score(l.factor, c.degree - c.sum_true as i128);
                 }
             }
         }
@@ -108,193 +679,837 @@ impl Solver {
         self.unique_id - 1
     }
 
+    /// Creates a solver that can be interrupted from another thread.
+    /// The flag is checked before every decision and every backtracking step, so setting it to true
+    /// stops the search after a bounded amount of work and lets `try_solve` return `Err(Cancelled)`.
+    /// # Arguments
+    /// * `pseudo_boolean_formula` - The formula to solve
+    /// * `cancel_flag` - The flag that signals the solver to stop
+    pub fn with_cancel(
+        pseudo_boolean_formula: PseudoBooleanFormula,
+        cancel_flag: Arc<AtomicBool>,
+    ) -> Solver {
+        let mut solver = Solver::new(pseudo_boolean_formula);
+        solver.cancel_flag = Some(cancel_flag);
+        solver
+    }
+
+    /// Creates a solver that uses the random branching heuristic with a fixed seed.
+    /// Two solvers created with the same formula and seed make identical decisions.
+    /// # Arguments
+    /// * `pseudo_boolean_formula` - The formula to solve
+    /// * `seed` - The seed of the random number generator
+    pub fn with_seed(pseudo_boolean_formula: PseudoBooleanFormula, seed: u64) -> Solver {
+        let mut solver = Solver::new(pseudo_boolean_formula);
+        solver.rng = StdRng::seed_from_u64(seed);
+        solver.branching_heuristic = BranchingHeuristic::Random;
+        solver
+    }
+
+    /// Solves the formula.
+    /// # Panics
+    /// Panics if the solver was created with a cancellation flag and got cancelled, use `try_solve` in that case.
     pub fn solve(&mut self) -> SolverResult {
+        self.try_solve().expect("solver was cancelled")
+    }
+
+    /// Counts the models of the formula without building the d-DNNF, see `set_count_only`.
+    /// Afterwards, the solver is in its previous mode again.
+    /// # Panics
+    /// Panics if the solver was created with a cancellation flag and got cancelled.
+    pub fn model_count(&mut self) -> BigUint {
+        let count_only = self.count_only;
+        self.set_count_only(true);
+        let model_count = self.solve().model_count;
+        self.set_count_only(count_only);
+        model_count
+    }
+
+    /// Solves the formula unless the cancellation flag is set before the search is finished.
+    /// After a cancellation the solver is left in an intermediate state and should not be used anymore.
+    /// # Returns
+    /// The result of the solver or `Cancelled` if the search was interrupted
+    pub fn try_solve(&mut self) -> Result<SolverResult, Cancelled> {
         use std::time::Instant;
         let now = Instant::now();
+        let projected_variables = self.projected_variables.clone();
+        if !self.auxiliary_variables.is_empty() {
+            let mut projection = self.projected_variables.take().unwrap_or_else(|| {
+                vec![true; self.pseudo_boolean_formula.number_variables as usize]
+            });
+            for variable_index in &self.auxiliary_variables {
+                if let Some(projected) = projection.get_mut(*variable_index as usize) {
+                    *projected = false;
+                }
+            }
+            self.projected_variables = Some(projection);
+        }
         let result = self.count();
+        self.projected_variables = projected_variables;
         #[cfg(feature = "show_progress")]
-        self.print_progress(0);
+        if result.is_ok() {
+            // the shares of the subproblems do not add up to exactly 1 due to rounding
+            self.completed_progress = 1.0;
+            self.print_progress();
+        }
         let elapsed = now.elapsed();
         self.statistics.time_to_compute = elapsed.as_millis();
-        self.statistics.learned_clauses = self.learned_clauses.len();
+        self.statistics.learned_clauses = self.active_learned_clauses;
+        self.statistics.learned_clause_literals =
+            self.learned_clauses.iter().map(|c| c.literals.len()).sum();
+        if let Ok(solver_result) = &result {
+            self.statistics.ddnnf_node_count = solver_result
+                .ddnnf
+                .as_ref()
+                .map_or(0, |ddnnf| ddnnf.node_count());
+            if let Some(trace_writer) = &mut self.trace_writer {
+                let _ = trace_writer.flush();
+            }
+            // the scopes of very large circuits do not fit into memory
+            #[cfg(test)]
+            if self.statistics.ddnnf_node_count <= 1_000_000 {
+                if let Some(Err(error)) = solver_result.ddnnf.as_ref().map(DDNNF::validate) {
+                    panic!("the solver created an invalid d-DNNF: {error}");
+                }
+            }
+        }
         result
     }
 
-    fn count(&mut self) -> SolverResult {
-        if !self.simplify() {
-            //after simplifying formula violated constraint detected
-            return SolverResult {
-                model_count: BigUint::zero(),
-                ddnnf: DDNNF {
-                    root_node: Rc::new(FalseLeave),
-                    number_variables: self.pseudo_boolean_formula.number_variables,
-                },
-            };
+    /// Checks if the formula has at least the given number of models. The search stops as soon as
+    /// the models found so far reach the threshold, which is usually much faster than counting all
+    /// models. The formula is not split into components during this search: another component
+    /// could still be unsatisfiable, so the models of a component would only be known to be models
+    /// of the formula once the whole branch is solved. The solver is reset before and after the
+    /// query.
+    /// # Arguments
+    /// * `threshold` - The number of models the formula must at least have
+    /// # Returns
+    /// true if the formula has at least `threshold` models
+    /// # Panics
+    /// Panics if the solver was created with a cancellation flag and got cancelled
+    pub fn solve_at_least(&mut self, threshold: BigUint) -> bool {
+        self.restore_initial_state();
+        self.found_models = BigUint::zero();
+        self.model_threshold = Some(threshold.clone());
+        let result = self.count();
+        self.model_threshold = None;
+        self.restore_initial_state();
+        match result {
+            Ok(result) => result.model_count >= threshold,
+            Err(Cancelled) if self.found_models >= threshold => true,
+            Err(Cancelled) => panic!("solver was cancelled"),
         }
+    }
 
-        loop {
-            if self.number_unsat_constraints <= 0 {
-                //current assignment satisfies all constraints
-                self.result_stack
-                    .push(BigUint::from(2 as u32).pow(self.number_unassigned_variables));
-                self.ddnnf_stack.push(Rc::new(TrueLeave));
-                self.next_variables.clear();
-                if !self.backtrack() {
-                    //nothing to backtrack to, we searched the whole space
-                    return SolverResult {
-                        model_count: self.result_stack.pop().unwrap(),
-                        ddnnf: DDNNF {
-                            root_node: self.ddnnf_stack.pop().unwrap(),
-                            number_variables: self.pseudo_boolean_formula.number_variables,
-                        },
-                    };
-                }
-                continue;
-            }
-
-            #[cfg(feature = "cache")]
-            {
-                let cached_result = self.get_cached_result();
-                if let Some((mc, ddnnf_ref)) = cached_result {
-                    self.ddnnf_stack.push(Rc::clone(&ddnnf_ref));
-                    self.result_stack.push(mc);
-                    self.next_variables.clear();
-                    self.statistics.cache_hits += 1;
-                    if !self.backtrack() {
-                        //nothing to backtrack to, we searched the whole space
-                        return SolverResult {
-                            model_count: self.result_stack.pop().unwrap(),
-                            ddnnf: DDNNF {
-                                root_node: self.ddnnf_stack.pop().unwrap(),
-                                number_variables: self.pseudo_boolean_formula.number_variables,
-                            },
-                        };
-                    }
-                    continue;
-                }
-            }
-
-            #[cfg(feature = "disconnected_components")]
-            {
-                if self.branch_components() {
-                    continue;
-                }
-            }
+    /// Adds the result on top of the result stack to the models found by `solve_at_least`. The
+    /// leaves and cache hits of different branches never share a model.
+    fn add_found_models(&mut self) {
+        if self.model_threshold.is_some() && !self.stats_only {
+            self.found_models += self.result_stack.last().unwrap();
+        }
+    }
 
-            let decided_literal = self.decide();
-            match decided_literal {
-                None => {
-                    //there are no free variables to assign a value to
-                    self.result_stack.push(BigUint::zero());
-                    self.ddnnf_stack.push(Rc::new(FalseLeave));
-                    self.next_variables.clear();
-                    if !self.backtrack() {
-                        //nothing to backtrack to, we searched the whole space
-                        return SolverResult {
-                            model_count: self.result_stack.pop().unwrap(),
-                            ddnnf: DDNNF {
-                                root_node: self.ddnnf_stack.pop().unwrap(),
-                                number_variables: self.pseudo_boolean_formula.number_variables,
-                            },
-                        };
-                    }
-                }
-                Some((var_index, var_sign)) => {
-                    //set and propagate the new decided variable
-                    if let Some(constraint_index) =
-                        self.propagate(var_index, var_sign, FirstDecision)
-                    {
-                        //at least one constraint violated
-                        #[cfg(feature = "clause_learning")]
-                        self.safe_conflict_clause(constraint_index);
+    /// Checks if `solve_at_least` found enough models to stop the search.
+    fn reached_model_threshold(&self) -> bool {
+        match &self.model_threshold {
+            None => false,
+            Some(threshold) => self.found_models >= *threshold,
+        }
+    }
 
-                        self.result_stack.push(BigUint::zero());
-                        self.ddnnf_stack.push(Rc::new(FalseLeave));
+    fn is_cancelled(&self) -> bool {
+        match &self.cancel_flag {
+            None => false,
+            Some(cancel_flag) => cancel_flag.load(Ordering::Relaxed),
+        }
+    }
 
-                        self.next_variables.clear();
-                        if !self.backtrack() {
-                            //nothing to backtrack to, we searched the whole space
-                            return SolverResult {
-                                model_count: self.result_stack.pop().unwrap(),
-                                ddnnf: DDNNF {
-                                    root_node: self.ddnnf_stack.pop().unwrap(),
-                                    number_variables: self.pseudo_boolean_formula.number_variables,
-                                },
-                            };
-                        }
-                    }
-                }
-            }
+    /// Counts the models of the formula that satisfy the given assumptions. The solver is reset
+    /// before and after the query, so it can be used for further queries. Learned clauses are
+    /// kept, they contain the assumed literals they depend on. Cached results are kept as well:
+    /// the assumptions are assigned before the search, so the key of a cached subformula already
+    /// describes what is left of it under the assumptions.
+    /// # Arguments
+    /// * `assumptions` - The variables (index starting at 0) and the values they are fixed to
+    /// # Returns
+    /// The result of the solver, the model count is zero if the assumptions are contradictory
+    /// # Panics
+    /// Panics if an assumption refers to a variable that does not exist
+    pub fn solve_under_assumptions(&mut self, assumptions: &[(u32, bool)]) -> SolverResult {
+        for (variable_index, _) in assumptions {
+            assert!(
+                *variable_index < self.pseudo_boolean_formula.number_variables,
+                "unknown variable {variable_index} in assumptions"
+            );
         }
+        self.restore_initial_state();
+        self.assumptions = assumptions.to_vec();
+        let result = self.solve();
+        self.assumptions.clear();
+        self.restore_initial_state();
+        result
     }
 
-    /// Checks if there are any implications and if so propagates them until there are no more implications
+    /// Adds a constraint to the formula without creating a new solver. The solver is reset, but
+    /// learned clauses and cached results are kept: a learned clause still follows from the
+    /// extended formula, and a cached subformula either contains the new constraint, which changes
+    /// its key, or is not affected by it.
+    /// Variables that do not occur in the formula yet are added, their weights are 1 for both
+    /// literals and they are not part of a projection.
+    /// # Arguments
+    /// * `equation` - The equation to add, its variable indexes must use the name map of the formula
     /// # Returns
-    /// true: all implications were assigned without any conflicts
-    /// false: a conflict occurred and the formula is therefore unsatisfiable
-    fn simplify(&mut self) -> bool {
-        let mut propagation_set = Vec::new();
-        for constraint in &mut self.pseudo_boolean_formula.constraints {
-            match constraint.simplify() {
-                Satisfied => {
-                    self.number_unsat_constraints -= 1;
-                    if let ConstraintIndex::NormalConstraintIndex(index) = constraint.index {
-                        self.constraint_indexes_in_scope.remove(&index);
-                    }
-                }
-                Unsatisfied => {
-                    return false;
-                }
-                ImpliedLiteral(l) => {
-                    propagation_set.push((l.index, l.positive, constraint.index));
-                }
-                ImpliedLiteralList(list) => {
-                    for l in list {
-                        propagation_set.push((l.index, l.positive, constraint.index));
-                    }
-                }
-                _ => {}
+    /// An error if the coefficients of the equation overflow, the formula is unchanged in this case
+    pub fn add_constraint(&mut self, equation: &Equation) -> Result<(), String> {
+        self.restore_initial_state();
+        let first_new_constraint = self.pseudo_boolean_formula.constraints.len();
+        self.pseudo_boolean_formula.add_constraint(equation)?;
+        for _ in self.assignments.len()..self.pseudo_boolean_formula.number_variables as usize {
+            self.assignments.push(None);
+            self.root_assignment.push(None);
+            self.learned_clauses_by_variables.push(Vec::new());
+            self.vsids_scores.push(1.0);
+            self.dlcs_scores.push(0.0);
+            self.saved_phases.push(None);
+            if let Some(weights) = self.weights.as_mut() {
+                weights.push((1.0, 1.0));
+            }
+            if let Some(projected_variables) = self.projected_variables.as_mut() {
+                projected_variables.push(false);
             }
         }
-        for (index, sign, constraint_index) in propagation_set {
-            if !self
-                .propagate(index, sign, Propagated(constraint_index))
-                .is_none()
-            {
-                return false;
+        for c in &self.pseudo_boolean_formula.constraints[first_new_constraint..] {
+            for (i, l) in &c.literals {
+                self.dlcs_scores[*i] = dlcs_score(l.factor, c.degree);
             }
         }
-        true
+        self.unit_literals = self.pseudo_boolean_formula.unit_literals();
+        self.restore_initial_state();
+        Ok(())
     }
 
-    fn decide(&mut self) -> Option<(u32, bool)> {
-        if self.number_unassigned_variables == 0 {
-            return None;
+    /// Runs the simplification that precedes every search and returns the literals it assigned,
+    /// i.e. the literals that are implied by the formula without any decision, e.g. the mandatory
+    /// features of a configuration. The assumptions are not part of it. The solver is reset before
+    /// and after the simplification.
+    /// # Returns
+    /// false if the simplification found a conflict, so the formula has no models, and the implied
+    /// literals as (variable_index, value) in the order of their assignment. After a conflict the
+    /// list contains the literals that were assigned before it.
+    pub fn implied_literals(&mut self) -> (bool, Vec<(u32, bool)>) {
+        self.restore_initial_state();
+        let result = self.simplify();
+        self.restore_initial_state();
+        result
+    }
+
+    /// Counts the models of every component of the formula at the top level on its own, e.g. for
+    /// a sensitivity analysis. After the simplification (see `implied_literals`), two unassigned
+    /// variables are in the same component if they are connected by unsatisfied constraints.
+    /// Every component is counted by its own sub-solver like a component of the parallel search,
+    /// with the settings of this solver, e.g. the branching heuristic, the cache capacity and the
+    /// cancellation flag. The model count of the formula is the product of the component counts
+    /// times 2 to the power of the free variables, the unassigned variables that occur in no
+    /// unsatisfied constraint. A projection, auxiliary variables and weights are ignored, and as
+    /// the query takes no assumptions, the components are the ones of the whole formula. The
+    /// solver is reset before and after.
+    /// # Returns
+    /// The variables of each component in ascending order together with its model count, the
+    /// components ordered by their smallest variable. If the simplification finds a conflict, all
+    /// variables form one component without models.
+    /// # Panics
+    /// Panics if the solver was created with a cancellation flag and got cancelled
+    pub fn solve_all_counts(&mut self) -> Vec<(Vec<u32>, BigUint)> {
+        self.restore_initial_state();
+        if !self.simplify().0 {
+            let variables = (0..self.pseudo_boolean_formula.number_variables).collect();
+            return vec![(variables, BigUint::zero())];
         }
-        let variable_index = self.get_next_variable();
-        match variable_index {
-            None => None,
-            Some(variable_index) => {
-                self.decision_level += 1;
-                Some((variable_index, true))
-            }
+
+        // a formula that does not fall apart is a single component
+        let hypergraph = Hypergraph::new(self);
+        let partvec = hypergraph
+            .find_disconnected_components(self)
+            .unwrap_or_else(|| vec![0; hypergraph.current_constraint_index as usize]);
+        let mut components: Vec<Component> = hypergraph
+            .create_partition(self, partvec)
+            .components
+            .into_iter()
+            // the free variables form a component without constraints
+            .filter(|component| component.number_unsat_constraints > 0)
+            .collect();
+        components.sort_unstable_by_key(|component| component.variables.first().copied());
+
+        let mut counts = Vec::with_capacity(components.len());
+        for component in components {
+            let variables = component.variables.iter().map(|v| *v as u32).collect();
+            let mut solver = self.component_solver_setup(component).into_solver();
+            solver.projected_variables = None;
+            solver.weights = None;
+            solver.count_only = true;
+            let model_count = solver.search().expect("solver was cancelled").model_count;
+            counts.push((variables, model_count));
         }
+        self.restore_initial_state();
+        counts
     }
 
-    /// This function is used to set a variable to true or false in all constraints.
-    /// It also detects implied variables and also sets them until no more implications are left.
-    /// It adapts all constraints, the assignment_stack and the number of unsatisfied constraints
-    /// accordingly. This means all relevant datastructures for setting a variable assignment
-    /// are handled by this function.
+    /// Returns the value of every variable at decision level 0 of the last search, e.g. to inspect
+    /// the state of the solver after solving: the literals that are implied by the formula (and
+    /// the assumptions) before any decision. The search undoes all assignments, so the decisions
+    /// and the literals implied by them are not part of it.
+    /// # Returns
+    /// The value of each variable by its index (starting at 0), None if it was not assigned. All
+    /// variables are None before the first search and if the formula has no models.
+    pub fn current_assignment(&self) -> Vec<Option<bool>> {
+        self.root_assignment.clone()
+    }
+
+    /// Returns how often each constraint implied literals or was violated during the searches of
+    /// this solver, in the order of `pseudo_boolean_formula.constraints`. The counts are not
+    /// reset by `reset`.
+    pub fn constraint_activities(&self) -> Vec<u32> {
+        self.pseudo_boolean_formula
+            .constraints
+            .iter()
+            .map(|constraint| constraint.propagations)
+            .collect()
+    }
+
+    /// Returns the constraints that implied literals or were violated most often.
     /// # Arguments
-    /// * `variable_index` - The index of the variable to be set to true or false
-    /// * `variable_sign` - true or false depending on what the variable is set to
-    /// * `assignment_kind` - depending on how the decision for this assigment was made
+    /// * `n` - The maximum number of constraints to return
     /// # Returns
-    /// true: the variable assignment and all implications are set and no constraints were violated
-    /// false: the assignment resulted in conflicting implications
-    pub fn propagate(
-        &mut self,
+    /// The indexes of the constraints and their activities, sorted by decreasing activity
+    pub fn most_active_constraints(&self, n: usize) -> Vec<(usize, u32)> {
+        let mut activities: Vec<(usize, u32)> = self
+            .constraint_activities()
+            .into_iter()
+            .enumerate()
+            .collect();
+        activities.sort_by(|(i1, a1), (i2, a2)| a2.cmp(a1).then(i1.cmp(i2)));
+        activities.truncate(n);
+        activities
+    }
+
+    /// Resets the solver after a search, so it can be used for another query without running the
+    /// initialization of `Solver::new` again. All assignments are undone and the constraints, the
+    /// scope and the search state are the same as after the creation of the solver.
+    /// Learned clauses are kept, as they follow from the formula. Cached results are kept as well,
+    /// they also depend on the projection, the weights and the count-only and stats-only modes.
+    /// The solver clears the cache itself if one of these changes before the next query, use
+    /// `clear_cache` to drop the cached results anyway.
+    pub fn reset(&mut self) {
+        self.assumptions.clear();
+        self.restore_initial_state();
+    }
+
+    /// Removes all cached subformula results, the capacity of the cache is kept.
+    pub fn clear_cache(&mut self) {
+        let capacity = self.cache.capacity();
+        self.cache = LruCache::new();
+        if let Some(capacity) = capacity {
+            self.cache.set_capacity(capacity);
+        }
+        self.statistics.cache_entries = 0;
+    }
+
+    /// Undoes all assignments and resets the scope and the search state to the state after the
+    /// creation of the solver. The constraints simplified away at the start are in scope again.
+    fn restore_initial_state(&mut self) {
+        while let Some(entry) = self.assignment_stack.last() {
+            match entry {
+                Assignment(_) => self.undo_last_assignment(),
+                ComponentBranch(_) => {
+                    self.assignment_stack.pop();
+                }
+            }
+        }
+        self.decision_level = 0;
+        self.result_stack.clear();
+        self.weighted_result_stack.clear();
+        self.ddnnf_stack.clear();
+        self.component_splits.clear();
+        self.next_variables.clear();
+        self.progress_weight = 1.0;
+        self.completed_progress = 0.0;
+        self.last_progress = -1.0;
+        self.number_unsat_constraints = self.pseudo_boolean_formula.constraints.len();
+        self.number_unassigned_variables = self.pseudo_boolean_formula.number_variables;
+        self.variable_in_scope =
+            (0..self.pseudo_boolean_formula.number_variables as usize).collect();
+        self.constraint_indexes_in_scope = self
+            .pseudo_boolean_formula
+            .constraints
+            .iter()
+            .filter_map(|c| match c.index {
+                NormalConstraintIndex(i) => Some(i),
+                LearnedClauseIndex(_) => None,
+            })
+            .collect();
+    }
+
+    /// Counts the models of the formula projected onto the given variables, i.e. models that
+    /// only differ in variables outside the projection are counted once.
+    /// Projected variables are always decided before the other variables. As soon as no projected
+    /// variable is left in an unsatisfied constraint, the remaining subproblem only has to be
+    /// checked for satisfiability, which is why both branches of such a decision are combined
+    /// by taking the maximum instead of the sum.
+    /// The returned d-DNNF still represents all models of the formula.
+    /// The cached results depend on the projection, so the cache is cleared if the previous search
+    /// used another projection or none.
+    /// # Arguments
+    /// * `projection` - The indexes of the variables to project onto
+    pub fn solve_projected(&mut self, projection: &BTreeSet<u32>) -> SolverResult {
+        let mut projected_variables =
+            vec![false; self.pseudo_boolean_formula.number_variables as usize];
+        for variable_index in projection {
+            if let Some(projected) = projected_variables.get_mut(*variable_index as usize) {
+                *projected = true;
+            }
+        }
+        self.projected_variables = Some(projected_variables);
+        let result = self.solve();
+        self.projected_variables = None;
+        result
+    }
+
+    /// Returns the number of models of the current subproblem if all constraints are satisfied.
+    /// Without a projection every unassigned variable in scope doubles the count, with a projection
+    /// only the unassigned projected variables do.
+    fn satisfied_count(&self) -> BigUint {
+        let free_variables = match &self.projected_variables {
+            None => self.number_unassigned_variables,
+            Some(projected_variables) => self
+                .variable_in_scope
+                .iter()
+                .filter(|v| projected_variables[**v])
+                .count() as u32,
+        };
+        power_of_two(free_variables)
+    }
+
+    /// Returns the weight of a literal, 1.0 if no weights are set.
+    fn literal_weight(&self, variable_index: u32, variable_sign: bool) -> f64 {
+        match &self.weights {
+            None => 1.0,
+            Some(weights) => {
+                let (positive_weight, negative_weight) = weights[variable_index as usize];
+                if variable_sign {
+                    positive_weight
+                } else {
+                    negative_weight
+                }
+            }
+        }
+    }
+
+    /// Returns the weighted model count of the current subproblem if all constraints are satisfied.
+    /// Every unassigned variable in scope can take both values and contributes the sum of its weights.
+    fn satisfied_weight(&self) -> f64 {
+        match &self.weights {
+            None => 2.0_f64.powi(self.number_unassigned_variables as i32),
+            Some(weights) => self
+                .variable_in_scope
+                .iter()
+                .map(|v| weights[*v].0 + weights[*v].1)
+                .product(),
+        }
+    }
+
+    /// Creates the result from the remaining entries of the result stacks after the search.
+    fn pop_result(&mut self) -> SolverResult {
+        let (model_count, weighted_count) = self.pop_result_of_subproblem();
+        SolverResult {
+            model_count,
+            weighted_count: match (&self.weights, &self.projected_variables) {
+                (Some(_), None) => Some(weighted_count),
+                _ => None,
+            },
+            ddnnf: self.ddnnf_stack.pop().map(|root_node| DDNNF {
+                root_node,
+                number_variables: self.pseudo_boolean_formula.number_variables,
+            }),
+            name_map: self.pseudo_boolean_formula.name_map.clone(),
+            component_splits: std::mem::take(&mut self.component_splits),
+            unsat_core: None,
+        }
+    }
+
+    /// Adds the constraints of a conflict to the unsatisfiable core, if one is recorded: the violated
+    /// constraint and, recursively, the constraints that implied one of its literals. Constraints
+    /// already in the core are followed again, as their literals may have other reasons now.
+    /// Learned clauses are followed, but not added, the constraints they were learned from were
+    /// added by an earlier conflict. Decisions end the search, as both of their values fail.
+    /// # Arguments
+    /// * `conflict` - The constraint that was violated
+    fn record_conflict(&mut self, conflict: ConstraintIndex) {
+        let Some(unsat_core) = &mut self.unsat_core else {
+            return;
+        };
+        let mut visited_constraints = BTreeSet::new();
+        let mut visited_learned_clauses = BTreeSet::new();
+        let mut open_constraints = vec![conflict];
+        while let Some(constraint_index) = open_constraints.pop() {
+            let constraint = match constraint_index {
+                NormalConstraintIndex(i) => {
+                    if !visited_constraints.insert(i) {
+                        continue;
+                    }
+                    let constraint = &self.pseudo_boolean_formula.constraints[i];
+                    unsat_core.extend(constraint.equation_index);
+                    constraint
+                }
+                LearnedClauseIndex(i) => {
+                    if !visited_learned_clauses.insert(i) {
+                        continue;
+                    }
+                    &self.learned_clauses[i]
+                }
+            };
+            for (_, kind, _) in constraint.assignments.values() {
+                if let Propagated(reason) = kind {
+                    open_constraints.push(*reason);
+                }
+            }
+        }
+    }
+
+    /// Checks if a variable is projected away, meaning it must not be counted on its own.
+    fn is_projected_away(&self, variable_index: u32) -> bool {
+        match &self.projected_variables {
+            None => false,
+            Some(projected_variables) => !projected_variables[variable_index as usize],
+        }
+    }
+
+    fn count(&mut self) -> Result<SolverResult, Cancelled> {
+        // the cached counts of another projection are wrong for this search
+        if self.cached_projection != self.projected_variables {
+            self.clear_cache();
+            self.cached_projection = self.projected_variables.clone();
+        }
+        let mut result = if !self.simplify().0 || !self.assume() {
+            //after simplifying formula violated constraint detected
+            self.trace(|_| "LEAF count=0".to_string());
+            #[cfg(feature = "show_progress")]
+            self.complete_progress();
+            self.push_result(BigUint::zero(), 0.0);
+            self.push_ddnnf_node(FalseLeave);
+            self.root_assignment.fill(None);
+            self.pop_result()
+        } else {
+            self.root_assignment = self
+                .assignments
+                .iter()
+                .map(|assignment| assignment.map(|(_, value)| value))
+                .collect();
+            self.search()?
+        };
+        if result.model_count.is_zero() {
+            result.unsat_core = self
+                .unsat_core
+                .as_ref()
+                .filter(|unsat_core| !unsat_core.is_empty())
+                .map(|unsat_core| unsat_core.iter().copied().collect());
+        }
+        Ok(result)
+    }
+
+    /// Assigns the assumptions at decision level 0, so they are part of every model.
+    /// # Returns
+    /// true: all assumptions were assigned without any conflicts
+    /// false: the assumptions contradict each other or the formula
+    fn assume(&mut self) -> bool {
+        for (variable_index, variable_sign) in self.assumptions.clone() {
+            match self.assignments[variable_index as usize] {
+                Some((_, sign)) if sign == variable_sign => {}
+                Some(_) => {
+                    // the assumption contradicts an earlier assumption or an implied literal
+                    let reason = self.assignment_stack.iter().find_map(|entry| match entry {
+                        Assignment(VariableAssignment {
+                            variable_index: index,
+                            assignment_kind: Propagated(reason),
+                            ..
+                        }) if *index == variable_index => Some(*reason),
+                        _ => None,
+                    });
+                    if let Some(reason) = reason {
+                        self.record_conflict(reason);
+                    }
+                    return false;
+                }
+                None => {
+                    if let Some(constraint_index) =
+                        self.propagate(variable_index, variable_sign, FirstDecision)
+                    {
+                        self.record_conflict(constraint_index);
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Runs the search on the current scope until the whole search space is visited.
+    fn search(&mut self) -> Result<SolverResult, Cancelled> {
+        loop {
+            if self.is_cancelled() || self.reached_model_threshold() {
+                return Err(Cancelled);
+            }
+
+            if self.number_unsat_constraints == 0 {
+                //current assignment satisfies all constraints
+                self.trace(|solver| format!("LEAF count={}", solver.satisfied_count()));
+                #[cfg(feature = "show_progress")]
+                self.complete_progress();
+                if !self.stats_only {
+                    self.push_result(self.satisfied_count(), self.satisfied_weight());
+                }
+                self.push_ddnnf_node(TrueLeave);
+                self.add_found_models();
+                self.next_variables.clear();
+                if !self.backtrack() {
+                    //nothing to backtrack to, we searched the whole space
+                    return Ok(self.pop_result());
+                }
+                continue;
+            }
+
+            #[cfg(feature = "cache")]
+            {
+                let cached_result = self.get_cached_result();
+                if let Some((mc, weighted_mc, ddnnf_ref)) = cached_result {
+                    self.trace(|solver| {
+                        let hash = calculate_hash(
+                            &solver.variable_in_scope,
+                            &solver.pseudo_boolean_formula,
+                            &solver.constraint_indexes_in_scope,
+                        );
+                        format!("CACHE_HIT hash={hash} count={mc}")
+                    });
+                    #[cfg(feature = "show_progress")]
+                    self.complete_progress();
+                    if let Some(ddnnf_ref) = ddnnf_ref {
+                        self.ddnnf_stack.push(ddnnf_ref);
+                    }
+                    self.push_result(mc, weighted_mc);
+                    self.add_found_models();
+                    self.next_variables.clear();
+                    self.statistics.cache_hits += 1;
+                    if !self.backtrack() {
+                        //nothing to backtrack to, we searched the whole space
+                        return Ok(self.pop_result());
+                    }
+                    continue;
+                }
+            }
+
+            // the models of a component only count once all components of the branch are solved
+            #[cfg(feature = "disconnected_components")]
+            if let Some(component_based_formula) = self
+                .model_threshold
+                .is_none()
+                .then(|| self.to_disconnected_components())
+                .flatten()
+            {
+                // deeper splits are too small to pay for the threads
+                #[cfg(feature = "parallel")]
+                if self.parallel_components && self.decision_level == 0 {
+                    self.count_components_in_parallel(component_based_formula)?;
+                    #[cfg(feature = "show_progress")]
+                    self.complete_progress();
+                    self.next_variables.clear();
+                    if !self.backtrack() {
+                        //nothing to backtrack to, we searched the whole space
+                        return Ok(self.pop_result());
+                    }
+                    continue;
+                }
+                self.branch_components(component_based_formula);
+                continue;
+            }
+
+            let decided_literal = self.decide();
+            match decided_literal {
+                None => {
+                    //there are no free variables to assign a value to
+                    self.trace(|_| "LEAF count=0".to_string());
+                    #[cfg(feature = "show_progress")]
+                    self.complete_progress();
+                    self.push_result(BigUint::zero(), 0.0);
+                    self.push_ddnnf_node(FalseLeave);
+                    self.next_variables.clear();
+                    if !self.backtrack() {
+                        //nothing to backtrack to, we searched the whole space
+                        return Ok(self.pop_result());
+                    }
+                }
+                Some((var_index, var_sign)) => {
+                    //set and propagate the new decided variable
+                    #[cfg(feature = "show_progress")]
+                    self.split_progress(2);
+                    if let Some(constraint_index) =
+                        self.propagate(var_index, var_sign, FirstDecision)
+                    {
+                        //at least one constraint violated
+                        self.statistics.conflicts += 1;
+                        self.record_conflict(constraint_index);
+                        #[cfg(feature = "clause_learning")]
+                        self.safe_conflict_clause(constraint_index);
+
+                        self.trace(|_| "LEAF count=0".to_string());
+                        #[cfg(feature = "show_progress")]
+                        self.complete_progress();
+                        self.push_result(BigUint::zero(), 0.0);
+                        self.push_ddnnf_node(FalseLeave);
+
+                        self.next_variables.clear();
+                        if !self.backtrack() {
+                            //nothing to backtrack to, we searched the whole space
+                            return Ok(self.pop_result());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks if there are any implications and if so propagates them until there are no more implications.
+    /// Satisfied constraints are removed from the scope and the implied literals are assigned at
+    /// decision level 0. If the formula turns out to be unsatisfiable, all of these changes are
+    /// undone, so the solver is in the same state as after `restore_initial_state` and can be
+    /// reset or changed and solved again.
+    /// # Returns
+    /// A flag and the assigned literals in the order of their assignment, after a conflict the
+    /// literals that were assigned before it
+    /// true: all implications were assigned without any conflicts
+    /// false: a conflict occurred and the formula is therefore unsatisfiable
+    fn simplify(&mut self) -> (bool, Vec<(u32, bool)>) {
+        if self.pseudo_boolean_formula.is_trivially_unsat() {
+            if let Some(unsat_core) = &mut self.unsat_core {
+                unsat_core.extend(
+                    self.pseudo_boolean_formula
+                        .constraints
+                        .iter()
+                        .filter(|c| {
+                            c.constraint_type == GreaterEqual && c.factor_sum < c.degree as u128
+                        })
+                        .filter_map(|c| c.equation_index),
+                );
+                unsat_core.extend(&self.pseudo_boolean_formula.contradictory_equations);
+            }
+            return (false, Vec::new());
+        }
+        let simplified = self.simplify_constraints();
+        let implied_literals = self
+            .assignment_stack
+            .iter()
+            .filter_map(|entry| match entry {
+                Assignment(assignment) => {
+                    Some((assignment.variable_index, assignment.variable_sign))
+                }
+                ComponentBranch(_) => None,
+            })
+            .collect();
+        if !simplified {
+            self.restore_initial_state();
+        }
+        (simplified, implied_literals)
+    }
+
+    /// Removes the satisfied constraints from the scope and propagates the implied literals, see
+    /// `simplify`. The unit literals are assigned first, so the constraints they satisfy are
+    /// already out of scope and the other constraints are simplified on the smaller formula.
+    /// The changes are kept if a conflict occurs.
+    fn simplify_constraints(&mut self) -> bool {
+        for (index, sign, constraint_index) in self.unit_literals.clone() {
+            if let Some(conflict) = self.propagate(index, sign, Propagated(constraint_index)) {
+                self.record_conflict(conflict);
+                return false;
+            }
+        }
+        let mut propagation_set = Vec::new();
+        for constraint in &mut self.pseudo_boolean_formula.constraints {
+            if let NormalConstraintIndex(index) = constraint.index {
+                if !self.constraint_indexes_in_scope.contains(&index) {
+                    continue;
+                }
+            }
+            match constraint.simplify() {
+                Satisfied => {
+                    count_satisfied_constraint(&mut self.number_unsat_constraints);
+                    if let ConstraintIndex::NormalConstraintIndex(index) = constraint.index {
+                        self.constraint_indexes_in_scope.remove(&index);
+                    }
+                }
+                Conflict(_) => {
+                    if let Some(unsat_core) = &mut self.unsat_core {
+                        unsat_core.extend(constraint.equation_index);
+                    }
+                    return false;
+                }
+                ImpliedLiteral(l) => {
+                    propagation_set.push((l.index, l.positive, constraint.index));
+                }
+                ImpliedLiteralList(list) => {
+                    for l in list {
+                        propagation_set.push((l.index, l.positive, constraint.index));
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (index, sign, constraint_index) in propagation_set {
+            if let Some(conflict) = self.propagate(index, sign, Propagated(constraint_index)) {
+                self.record_conflict(conflict);
+                return false;
+            }
+        }
+        true
+    }
+
+    fn decide(&mut self) -> Option<(u32, bool)> {
+        if self.number_unassigned_variables == 0 {
+            return None;
+        }
+        let variable_index = self.get_next_variable();
+        match variable_index {
+            None => None,
+            Some(variable_index) => {
+                self.decision_level += 1;
+                self.statistics.decisions += 1;
+                Some((variable_index, self.get_phase(variable_index)))
+            }
+        }
+    }
+
+    /// Returns the phase that is assigned first to the given decision variable.
+    fn get_phase(&mut self, variable_index: u32) -> bool {
+        match self.phase_selection {
+            PhaseSelection::AlwaysTrue => true,
+            PhaseSelection::AlwaysFalse => false,
+            PhaseSelection::Saved => self.saved_phases[variable_index as usize].unwrap_or(true),
+            PhaseSelection::Random => self.rng.gen_bool(0.5),
+        }
+    }
+
+    /// This function is used to set a variable to true or false in all constraints.
+    /// It also detects implied variables and also sets them until no more implications are left.
+    /// It adapts all constraints, the assignment_stack and the number of unsatisfied constraints
+    /// accordingly. This means all relevant datastructures for setting a variable assignment
+    /// are handled by this function.
+    /// # Arguments
+    /// * `variable_index` - The index of the variable to be set to true or false
+    /// * `variable_sign` - true or false depending on what the variable is set to
+    /// * `assignment_kind` - depending on how the decision for this assigment was made
+    /// # Returns
+    /// true: the variable assignment and all implications are set and no constraints were violated
+    /// false: the assignment resulted in conflicting implications
+    pub(crate) fn propagate(
+        &mut self,
         variable_index: u32,
         variable_sign: bool,
         assignment_kind: AssignmentKind,
@@ -303,66 +1518,6 @@ impl Solver {
         propagation_queue.push_back((variable_index, variable_sign, assignment_kind, false));
 
         //TODO check if the assignments should be made somewhere in the assignment stack (e.g. on max decisionlevel of the assigned literals of the constraint that implies)
-        /*
-               for clause in &mut self.learned_clauses {
-                   let mut flag = false;
-                   for constraint_index in self.learned_clauses_by_variables.get(variable_index as usize).unwrap() {
-                       if let LearnedClauseIndex(i) = clause.index {
-                           if *constraint_index == i {
-                               flag = true;
-                               break;
-                           }
-                       }
-                   }
-                   if flag {
-                       //break;
-                   }
-
-                   if clause.literals.contains_key(&(variable_index as usize)) {
-                       continue;
-                   }
-                   let result = clause.simplify();
-                   let constraint_index = &clause.index;
-                   match result {
-                       Satisfied => {
-                           //self.number_unsat_constraints -= 1;
-                           //all results here
-
-                       },
-                       Unsatisfied => {
-
-                           //self.statistics.propagations_from_learned_clauses += 1;
-                           propagation_queue.clear();
-                           return Some(*constraint_index);
-                       },
-                       ImpliedLiteral(l) => {
-                           self.statistics.tmp_count += 1;
-                           propagation_queue.push_back((l.index, l.positive, Propagated(*constraint_index), true));
-                       },
-                       NothingToPropagated => {
-
-                       },
-                       AlreadySatisfied => {
-
-                       },
-                       ImpliedLiteralList(list) => {
-                           self.statistics.tmp_count += 1;
-                           /*
-                           println!("decision_level: {}, variable_index: {}", self.decision_level, variable_index);
-                           for (i, (s,kind,dl)) in &clause.assignments {
-                               println!("i: {}, s: {}, k: {:?}, dl: {}", i, s, kind, dl);
-                           }
-
-                            */
-                           for l in list {
-                               propagation_queue.push_back((l.index, l.positive, Propagated(*constraint_index), true));
-                           }
-                       }
-                   }
-               }
-
-
-        */
 
         while !propagation_queue.is_empty() {
             let (index, sign, kind, from_learned_clause) = propagation_queue.pop_front().unwrap();
@@ -375,15 +1530,24 @@ impl Solver {
                     //already done exactly this assignment -> skip
                     continue;
                 } else {
-                    // this is a conflicting assignment
-                    panic!(
-                        "Assigning a two different values to a single variable should never happen"
-                    )
+                    // this is a conflicting assignment, the constraint that implied it is violated
+                    debug_assert!(
+                        matches!(kind, Propagated(_)),
+                        "a decision must not assign an already assigned variable"
+                    );
+                    if let Propagated(constraint_index) = kind {
+                        propagation_queue.clear();
+                        return Some(constraint_index);
+                    }
+                    continue;
                 }
             }
             if from_learned_clause {
                 self.statistics.propagations_from_learned_clauses += 1;
             }
+            if let Propagated(_) = kind {
+                self.statistics.propagations += 1;
+            }
             self.number_unassigned_variables -= 1;
             self.variable_in_scope.remove(&(index as usize));
             self.assignment_stack.push(Assignment(VariableAssignment {
@@ -393,6 +1557,7 @@ impl Solver {
                 variable_sign: sign,
             }));
             self.assignments[index as usize] = Some((index, sign));
+            self.saved_phases[index as usize] = Some(sign);
             //propagate from constraints
             for constraint_index in self
                 .pseudo_boolean_formula
@@ -416,12 +1581,12 @@ impl Solver {
                     );
                 match result {
                     Satisfied => {
-                        self.number_unsat_constraints -= 1;
+                        count_satisfied_constraint(&mut self.number_unsat_constraints);
                         self.constraint_indexes_in_scope.remove(&constraint_index);
                     }
-                    Unsatisfied => {
+                    Conflict(conflict_index) => {
                         propagation_queue.clear();
-                        return Some(NormalConstraintIndex(*constraint_index));
+                        return Some(conflict_index);
                     }
                     ImpliedLiteral(l) => {
                         propagation_queue.push_back((
@@ -467,12 +1632,15 @@ impl Solver {
                     );
                 match result {
                     Satisfied => {}
-                    Unsatisfied => {
+                    Conflict(conflict_index) => {
                         //self.statistics.propagations_from_learned_clauses += 1;
+                        self.learned_clause_activities[*constraint_index] += 1;
                         propagation_queue.clear();
-                        return Some(LearnedClauseIndex(*constraint_index));
+                        return Some(conflict_index);
                     }
                     ImpliedLiteral(l) => {
+                        self.learned_clause_activities[*constraint_index] += 1;
+                        self.statistics.learned_clause_implications += 1;
                         propagation_queue.push_back((
                             l.index,
                             l.positive,
@@ -483,6 +1651,8 @@ impl Solver {
                     NothingToPropagated => {}
                     AlreadySatisfied => {}
                     ImpliedLiteralList(list) => {
+                        self.learned_clause_activities[*constraint_index] += 1;
+                        self.statistics.learned_clause_implications += 1;
                         for l in list {
                             propagation_queue.push_back((
                                 l.index,
@@ -512,212 +1682,308 @@ impl Solver {
             if let Some(top_element) = self.assignment_stack.last() {
                 match top_element {
                     Assignment(last_assignment) => {
+                        let weight = self.literal_weight(
+                            last_assignment.variable_index,
+                            last_assignment.variable_sign,
+                        );
                         if last_assignment.decision_level == 0 {
-                            let ddnnf_node = self.ddnnf_stack.pop().unwrap();
-                            if matches!(*ddnnf_node, FalseLeave) {
-                                self.ddnnf_stack.push(Rc::new(FalseLeave));
-                                return false;
-                            }
-                            if let AndNode(child_list, _) = (*ddnnf_node).clone() {
-                                let mut new_child_list = Vec::new();
-                                let mut contains_false = false;
-                                for node in child_list {
-                                    new_child_list.push(node.clone());
-                                    if matches!(*node, FalseLeave) {
-                                        contains_false = true;
-                                        break;
-                                    }
+                            if !self.count_only {
+                                let ddnnf_node = self.ddnnf_stack.pop().unwrap();
+                                if matches!(*ddnnf_node, FalseLeave) {
+                                    self.ddnnf_stack.push(self.node_table.node(FalseLeave));
+                                    return false;
                                 }
-                                if contains_false {
-                                    self.ddnnf_stack.push(Rc::from(FalseLeave));
+                                if let AndNode(child_list, _) = (*ddnnf_node).clone() {
+                                    let mut new_child_list = Vec::new();
+                                    let mut contains_false = false;
+                                    for node in child_list {
+                                        new_child_list.push(node.clone());
+                                        if matches!(*node, FalseLeave) {
+                                            contains_false = true;
+                                            break;
+                                        }
+                                    }
+                                    if contains_false {
+                                        self.ddnnf_stack.push(self.node_table.node(FalseLeave));
+                                    } else {
+                                        new_child_list.push(self.node_table.node(LiteralLeave(
+                                            Rc::new(DDNNFLiteral {
+                                                index: last_assignment.variable_index,
+                                                positive: last_assignment.variable_sign,
+                                            }),
+                                        )));
+                                        let node_id = self.get_unique_id();
+                                        self.ddnnf_stack.push(
+                                            self.node_table.node(AndNode(new_child_list, node_id)),
+                                        );
+                                    }
                                 } else {
-                                    new_child_list.push(Rc::new(LiteralLeave(Rc::new(
+                                    let mut child_list = Vec::new();
+                                    child_list.push(ddnnf_node);
+                                    child_list.push(self.node_table.node(LiteralLeave(Rc::new(
                                         DDNNFLiteral {
                                             index: last_assignment.variable_index,
                                             positive: last_assignment.variable_sign,
                                         },
                                     ))));
-                                    let node_id = self.get_unique_id();
-                                    self.ddnnf_stack
-                                        .push(Rc::new(AndNode(new_child_list, node_id)));
+                                    let and_node = AndNode(child_list, self.get_unique_id());
+                                    self.ddnnf_stack.push(self.node_table.node(and_node));
                                 }
-                            } else {
-                                let mut child_list = Vec::new();
-                                child_list.push(ddnnf_node);
-                                child_list.push(Rc::new(LiteralLeave(Rc::new(DDNNFLiteral {
-                                    index: last_assignment.variable_index,
-                                    positive: last_assignment.variable_sign,
-                                }))));
-                                let and_node = AndNode(child_list, self.get_unique_id());
-                                self.ddnnf_stack.push(Rc::new(and_node));
+                            }
+                            if !self.stats_only {
+                                *self.weighted_result_stack.last_mut().unwrap() *= weight;
                             }
                             self.undo_last_assignment();
                         } else if let Propagated(_) = last_assignment.assignment_kind {
-                            let ddnnf_node = self.ddnnf_stack.pop().unwrap();
-                            if let AndNode(child_list, _) = (*ddnnf_node).clone() {
-                                let mut new_child_list = Vec::new();
-                                for node in child_list {
-                                    new_child_list.push(node.clone());
-                                }
-                                new_child_list.push(Rc::new(LiteralLeave(Rc::new(DDNNFLiteral {
-                                    index: last_assignment.variable_index,
-                                    positive: last_assignment.variable_sign,
-                                }))));
-                                let node_id = self.get_unique_id();
-                                self.ddnnf_stack
-                                    .push(Rc::new(AndNode(new_child_list, node_id)));
-                            } else if let FalseLeave = (*ddnnf_node).clone() {
-                                self.ddnnf_stack.push(Rc::new(FalseLeave));
-                            } else {
-                                let mut child_list = Vec::new();
-                                if !matches!(*ddnnf_node, TrueLeave) {
-                                    child_list.push(ddnnf_node);
+                            if !self.count_only {
+                                let ddnnf_node = self.ddnnf_stack.pop().unwrap();
+                                if let AndNode(child_list, _) = (*ddnnf_node).clone() {
+                                    let mut new_child_list = Vec::new();
+                                    for node in child_list {
+                                        new_child_list.push(node.clone());
+                                    }
+                                    new_child_list.push(self.node_table.node(LiteralLeave(
+                                        Rc::new(DDNNFLiteral {
+                                            index: last_assignment.variable_index,
+                                            positive: last_assignment.variable_sign,
+                                        }),
+                                    )));
+                                    let node_id = self.get_unique_id();
+                                    self.ddnnf_stack.push(
+                                        self.node_table.node(AndNode(new_child_list, node_id)),
+                                    );
+                                } else if let FalseLeave = (*ddnnf_node).clone() {
+                                    self.ddnnf_stack.push(self.node_table.node(FalseLeave));
+                                } else {
+                                    let mut child_list = Vec::new();
+                                    if !matches!(*ddnnf_node, TrueLeave) {
+                                        child_list.push(ddnnf_node);
+                                    }
+                                    child_list.push(self.node_table.node(LiteralLeave(Rc::new(
+                                        DDNNFLiteral {
+                                            index: last_assignment.variable_index,
+                                            positive: last_assignment.variable_sign,
+                                        },
+                                    ))));
+                                    let and_node = AndNode(child_list, self.get_unique_id());
+                                    self.ddnnf_stack.push(self.node_table.node(and_node));
                                 }
-                                child_list.push(Rc::new(LiteralLeave(Rc::new(DDNNFLiteral {
-                                    index: last_assignment.variable_index,
-                                    positive: last_assignment.variable_sign,
-                                }))));
-                                let and_node = AndNode(child_list, self.get_unique_id());
-                                self.ddnnf_stack.push(Rc::new(and_node));
+                            }
+                            if !self.stats_only {
+                                *self.weighted_result_stack.last_mut().unwrap() *= weight;
                             }
                             self.undo_last_assignment();
                         } else if last_assignment.assignment_kind == FirstDecision {
                             let index = last_assignment.variable_index;
                             let sign = last_assignment.variable_sign;
 
-                            #[cfg(feature = "show_progress")]
-                            self.print_progress(last_assignment.decision_level);
-
                             self.undo_last_assignment();
                             let new_sign = !sign;
+                            self.statistics.backtracks += 1;
 
                             if let Some(constraint_index) =
                                 self.propagate(index, new_sign, SecondDecision)
                             {
+                                self.statistics.conflicts += 1;
+                                self.record_conflict(constraint_index);
                                 #[cfg(feature = "clause_learning")]
                                 self.safe_conflict_clause(constraint_index);
-                                self.result_stack.push(BigUint::zero());
-                                self.ddnnf_stack.push(Rc::new(FalseLeave));
+                                self.trace(|_| "LEAF count=0".to_string());
+                                #[cfg(feature = "show_progress")]
+                                self.complete_progress();
+                                self.push_result(BigUint::zero(), 0.0);
+                                self.push_ddnnf_node(FalseLeave);
                             } else {
                                 return true;
                             }
                         } else if last_assignment.assignment_kind == SecondDecision {
-                            let r1 = self.result_stack.pop().unwrap();
-                            let r2 = self.result_stack.pop().unwrap();
-                            let res = r1 + r2;
-                            self.result_stack.push(res.clone());
-
-                            let mut d1 = self.ddnnf_stack.pop().unwrap();
-                            if let TrueLeave = *d1 {
-                                d1 = Rc::new(LiteralLeave(Rc::new(DDNNFLiteral {
-                                    index: last_assignment.variable_index,
-                                    positive: last_assignment.variable_sign,
-                                })));
-                            } else if !matches!(*d1, FalseLeave) {
-                                if let AndNode(child_list, _) = (*d1).clone() {
-                                    let mut new_child_list = Vec::new();
-                                    for child in child_list {
-                                        new_child_list.push(child);
-                                    }
-                                    new_child_list.push(Rc::new(LiteralLeave(Rc::new(
-                                        DDNNFLiteral {
+                            let (r1, r2, w1, w2) = if self.stats_only {
+                                (BigUint::zero(), BigUint::zero(), 0.0, 0.0)
+                            } else {
+                                (
+                                    self.result_stack.pop().unwrap(),
+                                    self.result_stack.pop().unwrap(),
+                                    self.weighted_result_stack.pop().unwrap(),
+                                    self.weighted_result_stack.pop().unwrap(),
+                                )
+                            };
+                            let projected_away =
+                                self.is_projected_away(last_assignment.variable_index);
+                            let res = if projected_away {
+                                (&r1).max(&r2).clone()
+                            } else {
+                                &r1 + &r2
+                            };
+                            let variable = last_assignment.variable_index + 1;
+                            let weighted_res = w1
+                                * self.literal_weight(
+                                    last_assignment.variable_index,
+                                    last_assignment.variable_sign,
+                                )
+                                + w2 * self.literal_weight(
+                                    last_assignment.variable_index,
+                                    !last_assignment.variable_sign,
+                                );
+                            if !self.stats_only {
+                                self.result_stack.push(res.clone());
+                                self.weighted_result_stack.push(weighted_res);
+                            }
+
+                            let ddnnf_ref = if self.count_only {
+                                None
+                            } else {
+                                let mut d1 = self.ddnnf_stack.pop().unwrap();
+                                if let TrueLeave = *d1 {
+                                    d1 =
+                                        self.node_table.node(LiteralLeave(Rc::new(DDNNFLiteral {
                                             index: last_assignment.variable_index,
                                             positive: last_assignment.variable_sign,
-                                        },
-                                    ))));
-                                    d1 = Rc::new(AndNode(new_child_list, node_id));
-                                } else {
-                                    let mut child_list = Vec::new();
-                                    child_list.push(Rc::new(LiteralLeave(Rc::new(DDNNFLiteral {
-                                        index: last_assignment.variable_index,
-                                        positive: last_assignment.variable_sign,
-                                    }))));
-                                    child_list.push(d1);
-                                    d1 = Rc::new(AndNode(child_list, node_id));
+                                        })));
+                                } else if !matches!(*d1, FalseLeave) {
+                                    if let AndNode(child_list, _) = (*d1).clone() {
+                                        let mut new_child_list = Vec::new();
+                                        for child in child_list {
+                                            new_child_list.push(child);
+                                        }
+                                        new_child_list.push(self.node_table.node(LiteralLeave(
+                                            Rc::new(DDNNFLiteral {
+                                                index: last_assignment.variable_index,
+                                                positive: last_assignment.variable_sign,
+                                            }),
+                                        )));
+                                        d1 = self.node_table.node(AndNode(new_child_list, node_id));
+                                    } else {
+                                        let mut child_list = Vec::new();
+                                        child_list.push(self.node_table.node(LiteralLeave(
+                                            Rc::new(DDNNFLiteral {
+                                                index: last_assignment.variable_index,
+                                                positive: last_assignment.variable_sign,
+                                            }),
+                                        )));
+                                        child_list.push(d1);
+                                        d1 = self.node_table.node(AndNode(child_list, node_id));
+                                    }
                                 }
-                            }
 
-                            let mut d2 = self.ddnnf_stack.pop().unwrap();
-                            if let TrueLeave = *d2 {
-                                d2 = Rc::new(LiteralLeave(Rc::new(DDNNFLiteral {
-                                    index: last_assignment.variable_index,
-                                    positive: !last_assignment.variable_sign,
-                                })));
-                            } else if !matches!(*d2, FalseLeave) {
-                                if let AndNode(child_list, _) = (*d2).clone() {
-                                    let mut new_child_list = Vec::new();
-                                    for child in child_list {
-                                        new_child_list.push(child);
-                                    }
-                                    new_child_list.push(Rc::new(LiteralLeave(Rc::new(
-                                        DDNNFLiteral {
+                                let mut d2 = self.ddnnf_stack.pop().unwrap();
+                                if let TrueLeave = *d2 {
+                                    d2 =
+                                        self.node_table.node(LiteralLeave(Rc::new(DDNNFLiteral {
                                             index: last_assignment.variable_index,
                                             positive: !last_assignment.variable_sign,
-                                        },
-                                    ))));
-                                    d2 = Rc::new(AndNode(new_child_list, self.get_unique_id()));
-                                } else {
-                                    let mut child_list = Vec::new();
-                                    child_list.push(Rc::new(LiteralLeave(Rc::new(DDNNFLiteral {
-                                        index: last_assignment.variable_index,
-                                        positive: !last_assignment.variable_sign,
-                                    }))));
-                                    child_list.push(d2);
-                                    d2 = Rc::new(AndNode(child_list, self.get_unique_id()));
+                                        })));
+                                } else if !matches!(*d2, FalseLeave) {
+                                    if let AndNode(child_list, _) = (*d2).clone() {
+                                        let mut new_child_list = Vec::new();
+                                        for child in child_list {
+                                            new_child_list.push(child);
+                                        }
+                                        new_child_list.push(self.node_table.node(LiteralLeave(
+                                            Rc::new(DDNNFLiteral {
+                                                index: last_assignment.variable_index,
+                                                positive: !last_assignment.variable_sign,
+                                            }),
+                                        )));
+                                        let node_id = self.get_unique_id();
+                                        d2 = self.node_table.node(AndNode(new_child_list, node_id));
+                                    } else {
+                                        let mut child_list = Vec::new();
+                                        child_list.push(self.node_table.node(LiteralLeave(
+                                            Rc::new(DDNNFLiteral {
+                                                index: last_assignment.variable_index,
+                                                positive: !last_assignment.variable_sign,
+                                            }),
+                                        )));
+                                        child_list.push(d2);
+                                        let node_id = self.get_unique_id();
+                                        d2 = self.node_table.node(AndNode(child_list, node_id));
+                                    }
                                 }
-                            }
 
-                            let d_res;
-                            if matches!(*d1, FalseLeave) && matches!(*d2, FalseLeave) {
-                                d_res = Rc::new(FalseLeave);
-                            } else if matches!(*d2, FalseLeave) {
-                                d_res = d1;
-                            } else if matches!(*d1, FalseLeave) {
-                                d_res = d2;
-                            } else {
-                                d_res =
-                                    Rc::new(DDNNFNode::OrNode(vec![d1, d2], self.get_unique_id()));
-                            }
-                            let ddnnf_ref = d_res.clone();
-                            self.ddnnf_stack.push(d_res);
+                                let d_res;
+                                if matches!(*d1, FalseLeave) && matches!(*d2, FalseLeave) {
+                                    d_res = self.node_table.node(FalseLeave);
+                                } else if matches!(*d2, FalseLeave) {
+                                    d_res = d1;
+                                } else if matches!(*d1, FalseLeave) {
+                                    d_res = d2;
+                                } else {
+                                    let node_id = self.get_unique_id();
+                                    d_res = self
+                                        .node_table
+                                        .node(DDNNFNode::OrNode(vec![d1, d2], node_id));
+                                }
+                                let ddnnf_ref = d_res.clone();
+                                self.ddnnf_stack.push(d_res);
+                                Some(ddnnf_ref)
+                            };
+                            self.trace(|_| {
+                                if projected_away {
+                                    format!("COMBINE var={variable} max({r1}, {r2}) => {res}")
+                                } else {
+                                    format!("COMBINE var={variable} {r1} + {r2} => {res}")
+                                }
+                            });
 
                             self.next_variables.clear();
                             self.decision_level -= 1;
+                            #[cfg(feature = "show_progress")]
+                            self.merge_progress(2);
 
                             self.undo_last_assignment();
 
                             #[cfg(feature = "cache")]
-                            self.cache(res, ddnnf_ref);
+                            self.cache(res, weighted_res, ddnnf_ref);
                         }
                     }
                     ComponentBranch(last_branch) => {
                         //undo branch
                         if last_branch.current_component == last_branch.components.len() - 1 {
                             // we processed all components
-                            #[cfg(feature = "show_progress")]
-                            if self.decision_level < 5 {
-                                self.progress_split /= last_branch.components.len() as u128;
-                            }
+                            let number_components = last_branch.components.len();
 
                             let mut branch_result = BigUint::one();
+                            let mut weighted_branch_result = 1.0;
                             let mut zero_flag = false;
                             let mut child_nodes = Vec::new();
-                            for _ in 0..last_branch.components.len() {
-                                branch_result = branch_result * self.result_stack.pop().unwrap();
-                                let child_node = self.ddnnf_stack.pop().unwrap();
-                                if let FalseLeave = *child_node {
-                                    zero_flag = true;
+                            // the results of the components are only kept for the trace
+                            let mut component_results = Vec::new();
+                            for _ in 0..number_components {
+                                let (component_result, weighted_component_result) =
+                                    if self.stats_only {
+                                        (BigUint::zero(), 0.0)
+                                    } else {
+                                        (
+                                            self.result_stack.pop().unwrap(),
+                                            self.weighted_result_stack.pop().unwrap(),
+                                        )
+                                    };
+                                if self.trace_writer.is_some() {
+                                    component_results.push(component_result.clone());
+                                }
+                                branch_result *= component_result;
+                                weighted_branch_result *= weighted_component_result;
+                                if !self.count_only {
+                                    let child_node = self.ddnnf_stack.pop().unwrap();
+                                    if let FalseLeave = *child_node {
+                                        zero_flag = true;
+                                    }
+                                    child_nodes.push(child_node);
                                 }
-                                child_nodes.push(child_node);
                             }
                             let ddnnf_node = if zero_flag {
                                 FalseLeave
                             } else {
                                 AndNode(child_nodes, node_id)
                             };
-                            self.ddnnf_stack.push(Rc::new(ddnnf_node));
+                            if !self.count_only {
+                                self.ddnnf_stack.push(self.node_table.node(ddnnf_node));
+                            }
 
-                            self.result_stack.push(branch_result);
+                            if !self.stats_only {
+                                self.result_stack.push(branch_result);
+                                self.weighted_result_stack.push(weighted_branch_result);
+                            }
                             self.next_variables.clear();
 
                             self.number_unassigned_variables =
@@ -729,6 +1995,10 @@ impl Solver {
                             self.constraint_indexes_in_scope =
                                 last_branch.previous_constraint_indexes_in_scope.clone();
                             self.assignment_stack.pop();
+                            #[cfg(feature = "show_progress")]
+                            self.merge_progress(number_components);
+                            component_results.reverse();
+                            self.trace(|_| combine_components_line(&component_results));
                         } else {
                             // process next component
                             if let ComponentBranch(mut last_branch) =
@@ -789,18 +2059,16 @@ impl Solver {
                     .constraints
                     .get_mut(*constraint_index)
                     .unwrap();
-                if constraint
-                    .assignments
-                    .get(&(last_assignment.variable_index as usize))
-                    .is_some()
-                {
-                    //self.dlcs_scores[last_assignment.variable_index as usize] = self.dlcs_scores[last_assignment.variable_index as usize] + constraint.literals.get(&(last_assignment.variable_index as usize)).unwrap().factor as f64 / constraint.degree as f64;
-                }
                 if constraint.undo(
                     last_assignment.variable_index,
                     last_assignment.variable_sign,
                 ) {
                     self.number_unsat_constraints += 1;
+                    debug_assert!(
+                        self.number_unsat_constraints
+                            <= self.pseudo_boolean_formula.constraints.len(),
+                        "a constraint was counted as unsatisfied twice"
+                    );
                     self.constraint_indexes_in_scope.insert(*constraint_index);
                 }
             }
@@ -823,138 +2091,227 @@ impl Solver {
         input.iter_mut().for_each(|x| *x *= factor);
     }
 
+    /// Calculates the score of a variable according to the chosen branching heuristic.
+    /// The variable with the highest score is decided next.
+    fn branching_score(&self, variable_index: u32) -> f64 {
+        let vsids = self.vsids_scores[variable_index as usize];
+        let dlcs = self.dlcs_scores[variable_index as usize];
+        match self.branching_heuristic {
+            BranchingHeuristic::Vsids | BranchingHeuristic::Random => vsids,
+            BranchingHeuristic::Objective => {
+                vsids
+                    + self
+                        .objective_scores
+                        .get(variable_index as usize)
+                        .unwrap_or(&0.0)
+            }
+            BranchingHeuristic::Dlcs => dlcs,
+            BranchingHeuristic::VsidsDlcsBlend { vsids_weight } => {
+                (1.0 - vsids_weight) * dlcs + vsids_weight * vsids
+            }
+        }
+    }
+
+    /// Chooses a variable uniformly at random among the unassigned variables in scope that occur in unsatisfied constraints.
+    fn get_random_variable(&mut self, only_projected: bool) -> Option<u32> {
+        let mut candidates = BTreeSet::new();
+        for constraint in &self.pseudo_boolean_formula.constraints {
+            if !constraint.is_satisfied() {
+                for variable_index in constraint.unassigned_variables() {
+                    if self.variable_in_scope.contains(&(variable_index as usize))
+                        && (!only_projected || !self.is_projected_away(variable_index))
+                    {
+                        candidates.insert(variable_index);
+                    }
+                }
+            }
+        }
+        if candidates.is_empty() {
+            return None;
+        }
+        let chosen = self.rng.gen_range(0..candidates.len());
+        candidates.into_iter().nth(chosen)
+    }
+
     fn get_next_variable(&mut self) -> Option<u32> {
-        //TODO only necessary if the scores are used, otherwise just decreases the performance
-        //Self::scale_vector(&mut self.vsids_scores, 0.8);
-        //self.update_dlcs_scores();
+        if matches!(
+            self.branching_heuristic,
+            BranchingHeuristic::Dlcs | BranchingHeuristic::VsidsDlcsBlend { .. }
+        ) {
+            self.update_dlcs_scores();
+        }
+
+        // with a projection, the projected variables have to be decided first
+        let only_projected = self.projected_variables.is_some()
+            && self.pseudo_boolean_formula.constraints.iter().any(|c| {
+                !c.is_satisfied()
+                    && c.unassigned_variables().any(|i| {
+                        self.variable_in_scope.contains(&(i as usize)) && !self.is_projected_away(i)
+                    })
+            });
+        let is_candidate = |k: u32| match &self.projected_variables {
+            Some(projected_variables) if only_projected => projected_variables[k as usize],
+            _ => true,
+        };
 
-        if self.next_variables.len() == 1 {
+        // the order set by the user comes before the cut and the heuristic
+        // assigned variables are not in scope, so the scan stops at the first open variable
+        let formula = &self.pseudo_boolean_formula;
+        let first = self.variable_order.iter().copied().find(|variable_index| {
+            self.variable_in_scope.contains(&(*variable_index as usize))
+                && is_candidate(*variable_index)
+                && formula.constraints_by_variable[*variable_index as usize]
+                    .iter()
+                    .any(|constraint_index| !formula.constraints[*constraint_index].is_satisfied())
+        });
+        if first.is_some() {
+            return first;
+        }
+
+        if self.branching_heuristic == BranchingHeuristic::Random {
+            self.next_variables.clear();
+            return self.get_random_variable(only_projected);
+        }
+
+        if self.next_variables.len() == 1 && is_candidate(self.next_variables[0]) {
             return self.next_variables.pop();
         }
 
         if self.next_variables.len() > 0 {
-            let mut max_index: Option<u32> = None;
-            let mut max_value: Option<f64> = None;
+            let mut best: Option<(f64, u32)> = None;
             for k in &self.next_variables {
-                if *self.dlcs_scores.get(*k as usize).unwrap() < 0.0 {
-                    panic!("test")
-                }
-                let v = *self.vsids_scores.get(*k as usize).unwrap(); //0.2 * *self.dlcs_scores.get(*k as usize).unwrap() + 0.8 * *self.vsids_scores.get(*k as usize).unwrap();
-                if max_value.is_none() {
-                    max_value = Some(v);
-                    max_index = Some(*k);
-                } else if let Some(value) = max_value {
-                    if v > value {
-                        max_value = Some(v);
-                        max_index = Some(*k);
-                    }
+                if !is_candidate(*k) {
+                    continue;
                 }
+                best = better_candidate(best, (self.branching_score(*k), *k));
             }
-            if let Some(_) = max_index {
-                return max_index;
+            if let Some((_, max_index)) = best {
+                return Some(max_index);
             } else {
                 self.next_variables.clear();
             }
         }
 
-        let mut max_index: Option<u32> = None;
-        let mut max_value: Option<f64> = None;
+        let mut best: Option<(f64, u32)> = None;
 
         for constraint in &self.pseudo_boolean_formula.constraints {
-            if constraint.is_unsatisfied() {
+            if !constraint.is_satisfied() {
                 for (_, literal) in &constraint.unassigned_literals {
-                    if self.variable_in_scope.contains(&(literal.index as usize)) {
+                    if self.variable_in_scope.contains(&(literal.index as usize))
+                        && is_candidate(literal.index)
+                    {
                         let k = literal.index;
-                        let v = *self.vsids_scores.get(k as usize).unwrap(); //0.2 *self.dlcs_scores.get(k as usize).unwrap()+ 0.8 * *self.vsids_scores.get(k as usize).unwrap();
-                        if max_value.is_none() {
-                            max_value = Some(v);
-                            max_index = Some(k);
-                        } else if let Some(value) = max_value {
-                            if v > value {
-                                max_value = Some(v);
-                                max_index = Some(k);
-                            }
-                        }
+                        best = better_candidate(best, (self.branching_score(k), k));
                     }
                 }
             }
         }
-        if let Some(_) = max_index {
-            return max_index;
-        } else {
-            return None;
+        best.map(|(_, max_index)| max_index)
+    }
+
+    /// Pushes the (weighted) model count of a subproblem unless only the statistics are collected.
+    fn push_result(&mut self, model_count: BigUint, weighted_model_count: f64) {
+        if !self.stats_only {
+            self.result_stack.push(model_count);
+            self.weighted_result_stack.push(weighted_model_count);
+        }
+    }
+
+    /// Pops the (weighted) model count of a subproblem, 0 if only the statistics are collected.
+    fn pop_result_of_subproblem(&mut self) -> (BigUint, f64) {
+        if self.stats_only {
+            return (BigUint::zero(), 0.0);
+        }
+        (
+            self.result_stack.pop().unwrap(),
+            self.weighted_result_stack.pop().unwrap(),
+        )
+    }
+
+    /// Pushes a node of the circuit unless only the model count is computed.
+    fn push_ddnnf_node(&mut self, node: DDNNFNode) {
+        if !self.count_only {
+            self.ddnnf_stack.push(self.node_table.node(node));
         }
     }
 
     #[cfg(feature = "cache")]
-    fn cache(&mut self, mc: BigUint, ddnnf_ref: Rc<DDNNFNode>) {
+    fn cache(&mut self, mc: BigUint, weighted_mc: f64, ddnnf_ref: Option<Rc<DDNNFNode>>) {
         if self.number_unsat_constraints > 0 {
-            self.cache.insert(
+            self.statistics.cache_evictions += self.cache.insert(
                 calculate_hash(
                     &self.variable_in_scope,
-                    &self.assignments,
-                    &mut self.pseudo_boolean_formula,
-                    self.number_unassigned_variables,
+                    &self.pseudo_boolean_formula,
                     &self.constraint_indexes_in_scope,
                 ),
-                (mc, ddnnf_ref),
+                (mc, weighted_mc, ddnnf_ref),
             );
-            self.statistics.cache_entries += 1;
+            self.statistics.cache_entries = self.cache.len();
+            self.statistics.peak_cache_entries = self
+                .statistics
+                .peak_cache_entries
+                .max(self.statistics.cache_entries);
         }
     }
 
     #[cfg(feature = "cache")]
-    fn get_cached_result(&mut self) -> Option<(BigUint, Rc<DDNNFNode>)> {
-        match self.cache.get(&calculate_hash(
-            &self.variable_in_scope,
-            &self.assignments,
-            &mut self.pseudo_boolean_formula,
-            self.number_unassigned_variables,
-            &self.constraint_indexes_in_scope,
-        )) {
-            None => None,
-            Some((mc, ddnnf_ref)) => Some((mc.clone(), Rc::clone(ddnnf_ref))),
-        }
+    fn get_cached_result(&mut self) -> Option<(BigUint, f64, Option<Rc<DDNNFNode>>)> {
+        self.cache
+            .get(calculate_hash(
+                &self.variable_in_scope,
+                &self.pseudo_boolean_formula,
+                &self.constraint_indexes_in_scope,
+            ))
+            .cloned()
     }
 
     #[cfg(feature = "disconnected_components")]
-    fn branch_components(&mut self) -> bool {
-        let result = self.to_disconnected_components();
-        match result {
-            Some(component_based_formula) => {
-                #[cfg(feature = "show_progress")]
-                if self.decision_level < 5 {
-                    self.progress_split *= component_based_formula.components.len() as u128;
-                }
-                self.number_unsat_constraints = component_based_formula
-                    .components
-                    .get(0)
-                    .unwrap()
-                    .number_unsat_constraints
-                    as usize;
-                self.number_unassigned_variables = component_based_formula
-                    .components
-                    .get(0)
-                    .unwrap()
-                    .number_unassigned_variables;
-                self.variable_in_scope = component_based_formula
-                    .components
-                    .get(0)
-                    .unwrap()
-                    .variables
-                    .clone();
-                self.constraint_indexes_in_scope = component_based_formula
-                    .components
-                    .get(0)
-                    .unwrap()
-                    .constraint_indexes_in_scope
-                    .clone();
-                self.assignment_stack
-                    .push(ComponentBranch(component_based_formula));
-                true
-            }
-            None => false,
+    fn branch_components(&mut self, component_based_formula: ComponentBasedFormula) {
+        self.trace(|_| format!("SPLIT k={}", component_based_formula.components.len()));
+        self.record_split(&component_based_formula);
+        #[cfg(feature = "show_progress")]
+        self.split_progress(component_based_formula.components.len());
+        self.number_unsat_constraints = component_based_formula
+            .components
+            .get(0)
+            .unwrap()
+            .number_unsat_constraints as usize;
+        self.number_unassigned_variables = component_based_formula
+            .components
+            .get(0)
+            .unwrap()
+            .number_unassigned_variables;
+        self.variable_in_scope = component_based_formula
+            .components
+            .get(0)
+            .unwrap()
+            .variables
+            .clone();
+        self.constraint_indexes_in_scope = component_based_formula
+            .components
+            .get(0)
+            .unwrap()
+            .constraint_indexes_in_scope
+            .clone();
+        self.assignment_stack
+            .push(ComponentBranch(component_based_formula));
+    }
+
+    /// Records the sizes of the components of a split for `SolverResult::component_splits`.
+    #[cfg(feature = "disconnected_components")]
+    fn record_split(&mut self, component_based_formula: &ComponentBasedFormula) {
+        if !self.record_splits {
+            return;
         }
+        let components = &component_based_formula.components;
+        self.component_splits.push(ComponentStats {
+            decision_level: self.decision_level,
+            variable_counts: components.iter().map(|c| c.variables.len()).collect(),
+            constraint_counts: components
+                .iter()
+                .map(|c| c.constraint_indexes_in_scope.len())
+                .collect(),
+        });
     }
 
     #[cfg(feature = "disconnected_components")]
@@ -969,8 +2326,9 @@ impl Solver {
             .map(|x| *x)
             .collect();
 
-        if self.number_unsat_constraints > 1 {
-            let hypergraph = Hypergraph::new(&self);
+        // a single constraint can never be split into components
+        if self.number_unsat_constraints >= self.min_constraints_for_decomposition.max(2) {
+            let hypergraph = self.get_hypergraph();
             match hypergraph.find_disconnected_components(&self) {
                 Some(partvec) => {
                     // there is already a partition
@@ -979,8 +2337,13 @@ impl Solver {
                 None => {
                     // currently no partition => get variables for a good cut
                     if self.next_variables.is_empty() {
-                        let nv = hypergraph.get_variables_for_cut();
-                        self.next_variables.extend(nv);
+                        let (nv, cut) = hypergraph.get_variables_for_cut(2, self.partition_seed);
+                        // a bad cut is ignored and the usual branching heuristic is used instead
+                        if cut as f64
+                            <= self.max_cut_ratio * hypergraph.current_constraint_index as f64
+                        {
+                            self.next_variables.extend(nv);
+                        }
                     }
 
                     None
@@ -991,30 +2354,216 @@ impl Solver {
         }
     }
 
-    #[cfg(feature = "show_progress")]
-    fn print_progress(&mut self, decision_level: u32) {
-        if decision_level < 5 {
-            let res = self.progress.get(&decision_level);
-            let additional_progress: f32 = 1.0 / self.progress_split as f32;
-            match res {
-                None => {
-                    self.progress.insert(decision_level, additional_progress);
-                }
-                Some(v) => {
-                    self.progress
-                        .insert(decision_level, *v + additional_progress);
+    /// Collects what the sub-solver of a component needs to continue the search of this solver on
+    /// the component.
+    /// # Arguments
+    /// * `component` - The component the sub-solver is restricted to
+    fn component_solver_setup(&mut self, component: Component) -> ComponentSolverSetup {
+        ComponentSolverSetup {
+            pseudo_boolean_formula: self.pseudo_boolean_formula.clone(),
+            component,
+            assignments: self.assignments.clone(),
+            // the reasons of the current assignments refer to the learned clauses and decision levels
+            learned_clauses: self.learned_clauses.clone(),
+            learned_clauses_by_variables: self.learned_clauses_by_variables.clone(),
+            learned_clause_activities: self.learned_clause_activities.clone(),
+            active_learned_clauses: self.active_learned_clauses,
+            max_learned_clauses: self.max_learned_clauses,
+            vsids_decay: self.vsids_decay,
+            vsids_bump: self.vsids_bump,
+            weights: self.weights.clone(),
+            decision_level: self.decision_level,
+            projected_variables: self.projected_variables.clone(),
+            cancel_flag: self.cancel_flag.clone(),
+            cache_capacity: self.cache.capacity(),
+            branching_heuristic: self.branching_heuristic,
+            phase_selection: self.phase_selection,
+            variable_order: self.variable_order.clone(),
+            hash_consing: self.node_table.is_enabled(),
+            max_cut_ratio: self.max_cut_ratio,
+            min_constraints_for_decomposition: self.min_constraints_for_decomposition,
+            partition_seed: self.partition_seed,
+            count_only: self.count_only,
+            stats_only: self.stats_only,
+            unsat_core: self.unsat_core.as_ref().map(|_| BTreeSet::new()),
+            record_splits: self.record_splits,
+            // keeps seeded solvers deterministic
+            seed: self.rng.gen::<u64>(),
+        }
+    }
+
+    /// Solves every component on its own thread and combines the results like a sequential
+    /// component branch: the model counts are multiplied and the circuits become the children of
+    /// an and node.
+    /// Every thread uses its own sub-solver over the variables and constraints of its component.
+    /// The caches are not shared, as the components have disjoint scopes and a cache entry of one
+    /// component can never be used by another one. Sharing them would only add locking.
+    #[cfg(all(feature = "parallel", feature = "disconnected_components"))]
+    fn count_components_in_parallel(
+        &mut self,
+        component_based_formula: ComponentBasedFormula,
+    ) -> Result<(), Cancelled> {
+        self.trace(|_| format!("SPLIT k={}", component_based_formula.components.len()));
+        self.record_split(&component_based_formula);
+        let component_results: Vec<Result<_, Cancelled>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = component_based_formula
+                .components
+                .into_iter()
+                .map(|component| {
+                    let setup = self.component_solver_setup(component);
+                    scope.spawn(move || {
+                        let mut solver = setup.into_solver();
+                        let result = solver.search()?;
+                        let constraint_activities = solver.constraint_activities();
+                        Ok((
+                            result.model_count,
+                            // without weights the weighted count is never reported
+                            result.weighted_count.unwrap_or(f64::NAN),
+                            result.ddnnf.map(|ddnnf| ddnnf.to_flat()),
+                            solver.statistics,
+                            constraint_activities,
+                            result.component_splits,
+                            solver.unsat_core,
+                        ))
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("component solver panicked"))
+                .collect()
+        });
+
+        let mut model_count = BigUint::one();
+        let mut weighted_model_count = 1.0;
+        let mut zero_flag = false;
+        let mut child_nodes = Vec::new();
+        let mut component_counts = Vec::new();
+        let initial_activities = self.constraint_activities();
+        for component_result in component_results {
+            let (
+                component_count,
+                weighted_component_count,
+                flat_ddnnf,
+                statistics,
+                constraint_activities,
+                component_splits,
+                component_unsat_core,
+            ) = component_result?;
+            self.component_splits.extend(component_splits);
+            if let (Some(unsat_core), Some(component_unsat_core)) =
+                (&mut self.unsat_core, component_unsat_core)
+            {
+                unsat_core.extend(component_unsat_core);
+            }
+            model_count *= &component_count;
+            component_counts.push(component_count);
+            weighted_model_count *= weighted_component_count;
+            if let Some(flat_ddnnf) = flat_ddnnf {
+                let child_node = flat_ddnnf.into_node(|| self.get_unique_id());
+                if let FalseLeave = *child_node {
+                    zero_flag = true;
                 }
+                child_nodes.push(child_node);
             }
-            for i in decision_level + 1..9 {
-                self.progress.remove(&i);
+            self.statistics.decisions += statistics.decisions;
+            self.statistics.conflicts += statistics.conflicts;
+            self.statistics.propagations += statistics.propagations;
+            self.statistics.backtracks += statistics.backtracks;
+            self.statistics.cache_hits += statistics.cache_hits;
+            self.statistics.cache_evictions += statistics.cache_evictions;
+            self.statistics.propagations_from_learned_clauses +=
+                statistics.propagations_from_learned_clauses;
+            self.statistics.learned_clause_implications += statistics.learned_clause_implications;
+            // the sub-solvers started with the activities of this solver
+            for ((constraint, activity), initial_activity) in self
+                .pseudo_boolean_formula
+                .constraints
+                .iter_mut()
+                .zip(constraint_activities)
+                .zip(&initial_activities)
+            {
+                constraint.propagations += activity - initial_activity;
             }
-            let mut progress = 0.0;
-            for (k, v) in &self.progress {
-                progress += (100.0 / 2_i32.pow(*k) as f32) * (*v as f32);
+        }
+        self.trace(|_| combine_components_line(&component_counts));
+        let ddnnf_node = if zero_flag {
+            FalseLeave
+        } else {
+            AndNode(child_nodes, self.get_unique_id())
+        };
+        self.push_result(model_count, weighted_model_count);
+        self.push_ddnnf_node(ddnnf_node);
+        Ok(())
+    }
+
+    /// Returns the hypergraph of the current scope. The hypergraph only depends on the variables and
+    /// constraints in scope, as assigned variables and satisfied constraints are removed from the scope.
+    /// It is therefore reused if the same scope occurs again.
+    #[cfg(feature = "disconnected_components")]
+    fn get_hypergraph(&mut self) -> Rc<Hypergraph> {
+        if self.hypergraph_cache.capacity() == Some(0) {
+            return Rc::new(Hypergraph::new(self));
+        }
+        let mut hasher = DefaultHasher::new();
+        self.variable_in_scope.hash(&mut hasher);
+        self.constraint_indexes_in_scope.hash(&mut hasher);
+        let key = hasher.finish();
+        if let Some((variables, constraint_indexes, hypergraph)) = self.hypergraph_cache.get(key) {
+            if *variables == self.variable_in_scope
+                && *constraint_indexes == self.constraint_indexes_in_scope
+            {
+                return Rc::clone(hypergraph);
             }
-            if progress != self.last_progress {
-                self.last_progress = progress;
-                println!("{progress} %");
+        }
+        let hypergraph = Rc::new(Hypergraph::new(self));
+        self.hypergraph_cache.insert(
+            key,
+            (
+                self.variable_in_scope.clone(),
+                self.constraint_indexes_in_scope.clone(),
+                Rc::clone(&hypergraph),
+            ),
+        );
+        hypergraph
+    }
+
+    /// Splits the share of the current subproblem equally between its parts. The estimate assumes
+    /// that both branches of a decision and all components of a split take the same amount of work,
+    /// which is usually wrong, but the estimate still never decreases.
+    /// # Arguments
+    /// * `parts` - The number of subproblems, 2 for a decision or the number of components
+    #[cfg(feature = "show_progress")]
+    fn split_progress(&mut self, parts: usize) {
+        self.progress_weight /= parts as f64;
+    }
+
+    /// Reverts `split_progress` after all parts of the current subproblem are solved.
+    #[cfg(feature = "show_progress")]
+    fn merge_progress(&mut self, parts: usize) {
+        self.progress_weight *= parts as f64;
+    }
+
+    /// Marks the current subproblem as solved. This is called whenever a subproblem is finished
+    /// without branching: at satisfied leaves, conflicts (including the ones found by unit
+    /// propagation), cache hits and components counted in parallel. So the parts of the search
+    /// space that are pruned are counted as solved immediately.
+    #[cfg(feature = "show_progress")]
+    fn complete_progress(&mut self) {
+        self.completed_progress += self.progress_weight;
+        self.print_progress();
+    }
+
+    /// Reports the progress in percent with one decimal place if it increased since the last
+    /// report.
+    #[cfg(feature = "show_progress")]
+    fn print_progress(&mut self) {
+        let progress = ((self.completed_progress * 1000.0).floor() / 10.0).min(100.0) as f32;
+        if progress > self.last_progress {
+            self.last_progress = progress;
+            match &mut self.progress_callback {
+                None => println!("{progress} %"),
+                Some(progress_callback) => progress_callback(progress),
             }
         }
     }
@@ -1023,9 +2572,15 @@ impl Solver {
     fn safe_conflict_clause(&mut self, constraint_index: ConstraintIndex) {
         let constraint = match constraint_index {
             NormalConstraintIndex(i) => self.pseudo_boolean_formula.constraints.get(i).unwrap(),
-            LearnedClauseIndex(i) => self.learned_clauses.get(i).unwrap(),
+            LearnedClauseIndex(i) => {
+                self.learned_clause_activities[i] += 1;
+                self.learned_clauses.get(i).unwrap()
+            }
         };
 
+        if self.vsids_decay < 1.0 {
+            Self::scale_vector(&mut self.vsids_scores, self.vsids_decay);
+        }
         let mut variable_index = BTreeMap::new();
         for (index, (sign, kind, decision_level)) in &constraint.assignments {
             //if *decision_level == self.decision_level {
@@ -1041,8 +2596,54 @@ impl Solver {
                         .push(constraint_index);
                 }
                 self.learned_clauses.push(learned_constraint);
+                self.learned_clause_activities.push(0);
+                self.active_learned_clauses += 1;
+                if self.max_learned_clauses.is_some_and(|max_learned_clauses| {
+                    self.active_learned_clauses > max_learned_clauses
+                }) {
+                    self.reduce_learned_clauses();
+                }
+            }
+        }
+    }
+
+    /// Deletes the least active half of the learned clauses. Clauses that are the reason for a
+    /// current assignment are kept, as they are needed to learn further clauses. A deleted clause
+    /// keeps its index with all literals removed, so the indexes of the other clauses stay valid.
+    /// The activities are halved afterwards, so old activity counts less than recent activity.
+    #[cfg(feature = "clause_learning")]
+    fn reduce_learned_clauses(&mut self) {
+        let mut reasons = vec![false; self.learned_clauses.len()];
+        for entry in &self.assignment_stack {
+            if let Assignment(VariableAssignment {
+                assignment_kind: Propagated(LearnedClauseIndex(i)),
+                ..
+            }) = entry
+            {
+                reasons[*i] = true;
             }
         }
+        let mut candidates: Vec<usize> = (0..self.learned_clauses.len())
+            .filter(|i| !reasons[*i] && !self.learned_clauses[*i].literals.is_empty())
+            .collect();
+        candidates.sort_by_key(|i| self.learned_clause_activities[*i]);
+        candidates.truncate(self.active_learned_clauses / 2);
+
+        let mut deleted = vec![false; self.learned_clauses.len()];
+        for i in &candidates {
+            deleted[*i] = true;
+            let clause = &mut self.learned_clauses[*i];
+            clause.literals.clear();
+            clause.unassigned_literals.clear();
+            clause.assignments.clear();
+        }
+        for clause_indexes in &mut self.learned_clauses_by_variables {
+            clause_indexes.retain(|i| !deleted[*i]);
+        }
+        self.active_learned_clauses -= candidates.len();
+        for activity in &mut self.learned_clause_activities {
+            *activity /= 2;
+        }
     }
 
     #[cfg(feature = "clause_learning")]
@@ -1115,11 +2716,13 @@ impl Solver {
                                     .get(i)
                                     .unwrap()
                                     .calculate_reason(next_variable_index),
-                                LearnedClauseIndex(i) => self
-                                    .learned_clauses
-                                    .get(i)
-                                    .unwrap()
-                                    .calculate_reason(next_variable_index),
+                                LearnedClauseIndex(i) => {
+                                    self.learned_clause_activities[i] += 1;
+                                    self.learned_clauses
+                                        .get(i)
+                                        .unwrap()
+                                        .calculate_reason(next_variable_index)
+                                }
                             };
                             for (index, (kind, sign, decision_level)) in new_reasons {
                                 match kind {
@@ -1174,11 +2777,13 @@ impl Solver {
             hash_value: 0,
             hash_value_old: true,
             constraint_type: GreaterEqual,
+            equation_index: None,
             max_literal: Literal {
                 index: 0,
                 factor: 0,
                 positive: false,
             },
+            propagations: 0,
         };
 
         for (index, entry) in reason_set_propagated.iter().enumerate() {
@@ -1215,7 +2820,8 @@ impl Solver {
         }
         for (_, literal) in &constraint.literals {
             let mut tmp = *self.vsids_scores.get(literal.index as usize).unwrap();
-            tmp += literal.factor as f64 / (constraint.degree - constraint.sum_true as i128) as f64;
+            tmp += self.vsids_bump * literal.factor as f64
+                / (constraint.degree - constraint.sum_true as i128) as f64;
             self.vsids_scores[literal.index as usize] = tmp;
         }
         constraint.max_literal = constraint.get_max_literal();
@@ -1223,6 +2829,33 @@ impl Solver {
     }
 }
 
+/// The heuristic that decides which variable is branched on next.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BranchingHeuristic {
+    /// Variables that were involved in recent conflicts are preferred
+    Vsids,
+    /// Variables with a high factor relative to the remaining degree of their constraints are preferred
+    Dlcs,
+    /// Weighted sum of the VSIDS and DLCS scores, `vsids_weight` is the weight of the VSIDS score
+    VsidsDlcsBlend { vsids_weight: f64 },
+    /// A variable is chosen uniformly at random using the random number generator of the solver
+    Random,
+    /// Variables with large coefficients in the objective function are preferred: the absolute
+    /// coefficient is added to the VSIDS score. Without an objective, this is the same as VSIDS.
+    Objective,
+}
+
+/// The phase that is tried first when deciding a variable.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PhaseSelection {
+    AlwaysTrue,
+    AlwaysFalse,
+    /// The phase the variable was assigned most recently, true if it was never assigned
+    Saved,
+    /// A random phase chosen with the random number generator of the solver
+    Random,
+}
+
 #[derive(Clone)]
 enum AssignmentStackEntry {
     Assignment(VariableAssignment),
@@ -1236,12 +2869,132 @@ struct VariableAssignment {
     assignment_kind: AssignmentKind,
 }
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Statistics {
+    decisions: u32,
+    conflicts: u32,
+    propagations: u64,
+    backtracks: u32,
     cache_hits: u32,
     time_to_compute: u128,
     cache_entries: usize,
+    cache_evictions: usize,
+    peak_cache_entries: usize,
     learned_clauses: usize,
+    learned_clause_literals: usize,
     propagations_from_learned_clauses: u32,
+    learned_clause_implications: u32,
+    ddnnf_node_count: usize,
+}
+
+impl Statistics {
+    /// Number of decisions, a variable whose second value is tried after the first one counts once
+    pub fn decisions(&self) -> u32 {
+        self.decisions
+    }
+
+    /// Number of decisions (of either value) whose propagation violated a constraint
+    pub fn conflicts(&self) -> u32 {
+        self.conflicts
+    }
+
+    /// Number of literals that were assigned because a constraint or learned clause implied them
+    pub fn propagations(&self) -> u64 {
+        self.propagations
+    }
+
+    /// Number of times the search returned to a decision to try its second value
+    pub fn backtracks(&self) -> u32 {
+        self.backtracks
+    }
+
+    /// Number of subformulas whose result was taken from the cache
+    pub fn cache_hits(&self) -> u32 {
+        self.cache_hits
+    }
+
+    /// Time needed to solve the formula in milliseconds
+    pub fn time_to_compute(&self) -> u128 {
+        self.time_to_compute
+    }
+
+    /// Number of entries currently in the cache
+    pub fn cache_entries(&self) -> usize {
+        self.cache_entries
+    }
+
+    /// Number of entries that were evicted from the cache
+    pub fn cache_evictions(&self) -> usize {
+        self.cache_evictions
+    }
+
+    /// Highest number of entries the cache contained at once
+    pub fn peak_cache_entries(&self) -> usize {
+        self.peak_cache_entries
+    }
+
+    /// Number of learned clauses
+    pub fn learned_clauses(&self) -> usize {
+        self.learned_clauses
+    }
+
+    /// Total number of literals in all learned clauses
+    pub fn learned_clause_literals(&self) -> usize {
+        self.learned_clause_literals
+    }
+
+    /// Number of assignments implied by learned clauses
+    pub fn propagations_from_learned_clauses(&self) -> u32 {
+        self.propagations_from_learned_clauses
+    }
+
+    /// Number of times a learned clause implied at least one literal, an implied literal that is
+    /// already assigned is counted here but not in `propagations_from_learned_clauses`
+    pub fn learned_clause_implications(&self) -> u32 {
+        self.learned_clause_implications
+    }
+
+    /// Number of distinct nodes of the resulting d-DNNF
+    pub fn ddnnf_node_count(&self) -> usize {
+        self.ddnnf_node_count
+    }
+
+    /// Gives a rough estimate of the memory used by the cache at its peak, the learned clauses and
+    /// the resulting d-DNNF. Only the sizes of the stored elements are considered, the overhead of the
+    /// collections and the digits of large model counts are ignored.
+    pub fn estimated_bytes(&self) -> usize {
+        // key, value and usage time of the cache plus the entry in the usage order
+        let cache_entry_bytes = 3 * size_of::<u64>() + size_of::<(BigUint, f64, Rc<DDNNFNode>)>();
+        // every literal of a learned clause is stored in the literals and the unassigned literals
+        let learned_clause_literal_bytes = 2 * size_of::<(usize, Literal)>();
+        // the node itself, the reference counters and the reference of its parent
+        let ddnnf_node_bytes = size_of::<DDNNFNode>() + 3 * size_of::<usize>();
+        self.peak_cache_entries * cache_entry_bytes
+            + self.learned_clause_literals * learned_clause_literal_bytes
+            + self.ddnnf_node_count * ddnnf_node_bytes
+    }
+
+    /// Creates a JSON object containing every field with its name as key.
+    /// The time to compute is given in milliseconds.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"decisions\":{},\"conflicts\":{},\"propagations\":{},\"backtracks\":{},\"cache_hits\":{},\"time_to_compute\":{},\"cache_entries\":{},\"cache_evictions\":{},\"peak_cache_entries\":{},\"learned_clauses\":{},\"learned_clause_literals\":{},\"propagations_from_learned_clauses\":{},\"learned_clause_implications\":{},\"ddnnf_node_count\":{}}}",
+            self.decisions,
+            self.conflicts,
+            self.propagations,
+            self.backtracks,
+            self.cache_hits,
+            self.time_to_compute,
+            self.cache_entries,
+            self.cache_evictions,
+            self.peak_cache_entries,
+            self.learned_clauses,
+            self.learned_clause_literals,
+            self.propagations_from_learned_clauses,
+            self.learned_clause_implications,
+            self.ddnnf_node_count
+        )
+    }
 }
 
 #[derive(PartialEq, Clone, Debug, Eq, Copy)]
@@ -1251,248 +3004,2795 @@ pub(crate) enum AssignmentKind {
     SecondDecision,
 }
 
-pub struct SolverResult {
-    pub(crate) model_count: BigUint,
-    pub(crate) ddnnf: DDNNF,
-}
+/// Calculates the DLCS score of a literal, i.e. its factor relative to the part of the degree that
+/// is still missing. A constraint with != can already have reached its degree, the missing part is
+/// at least 1 so that the score stays positive and finite.
+fn dlcs_score(factor: u128, missing_degree: i128) -> f64 {
+    factor as f64 / missing_degree.max(1) as f64
+}
+
+/// Creates the trace line for the multiplication of the results of all components.
+fn combine_components_line(component_results: &[BigUint]) -> String {
+    let product: BigUint = component_results.iter().product();
+    let factors: Vec<String> = component_results.iter().map(|r| r.to_string()).collect();
+    format!(
+        "COMBINE k={} {} => {}",
+        component_results.len(),
+        factors.join(" * "),
+        product
+    )
+}
+
+/// Returns 2^exponent. A shift only writes the digits of the result, while a power also multiplies
+/// all intermediate results. On automotive01.opb the run time does not change measurably, the
+/// search and not the leaves dominate it.
+fn power_of_two(exponent: u32) -> BigUint {
+    BigUint::one() << exponent
+}
+
+/// Decreases the number of unsatisfied constraints when a constraint becomes satisfied. Every
+/// constraint is only counted once between becoming satisfied and its undo, so the number can not
+/// fall below 0. Wrapping around would make the search continue on a satisfied formula, so a
+/// violation of this invariant panics in release builds as well.
+/// # Panics
+/// Panics if there is no unsatisfied constraint left
+fn count_satisfied_constraint(number_unsat_constraints: &mut usize) {
+    *number_unsat_constraints = number_unsat_constraints
+        .checked_sub(1)
+        .expect("a constraint was counted as satisfied twice");
+}
+
+/// Compares a candidate for the next decision with the best candidate so far. The higher score
+/// wins and ties are broken by the smaller variable index, so the chosen variable only depends on
+/// the scores and not on the order in which the candidates are visited.
+/// # Arguments
+/// * `best` - The score and index of the best candidate so far
+/// * `candidate` - The score and index of the next candidate
+/// # Returns
+/// The score and index of the better candidate
+fn better_candidate(best: Option<(f64, u32)>, candidate: (f64, u32)) -> Option<(f64, u32)> {
+    match best {
+        Some((score, index))
+            if score > candidate.0 || (score == candidate.0 && index <= candidate.1) =>
+        {
+            best
+        }
+        _ => Some(candidate),
+    }
+}
+
+/// Signals that the solver was interrupted through its cancellation flag.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Cancelled;
+
+pub struct SolverResult {
+    pub model_count: BigUint,
+    /// The weighted model count if weights were set, projected counts are not weighted
+    pub weighted_count: Option<f64>,
+    /// The d-DNNF of the formula, None if the solver only counted the models
+    pub ddnnf: Option<DDNNF>,
+    /// The names of the variables by their index. The literals of the circuit use the same
+    /// indexes (starting at 0), the d4 format adds 1 to every index.
+    pub name_map: BiMap<String, u32>,
+    /// Every split of the formula into disconnected components in the order of the search, empty
+    /// unless recording them is enabled, see `Solver::set_record_splits`
+    pub component_splits: Vec<ComponentStats>,
+    /// The indexes of equations that have no model together, in the order of the input file
+    /// followed by the added constraints, if the formula has no models and recording the core is
+    /// enabled, see `Solver::set_unsat_core`. An equation that was dropped because every
+    /// assignment satisfies it is never part of the core.
+    pub unsat_core: Option<Vec<usize>>,
+}
+
+/// The sizes of the components of one split of a subformula into disconnected components.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComponentStats {
+    /// The decision level at which the subformula was split
+    pub decision_level: u32,
+    /// The number of variables in scope of every component
+    pub variable_counts: Vec<usize>,
+    /// The number of constraints in scope of every component
+    pub constraint_counts: Vec<usize>,
+}
+
+impl ComponentStats {
+    /// Number of components of the split
+    pub fn component_count(&self) -> usize {
+        self.variable_counts.len()
+    }
+}
+
+impl SolverResult {
+    /// Returns one model of the formula if the model count is not zero.
+    /// # Returns
+    /// The value of every variable (index starting at 0) or None if the formula is unsatisfiable
+    /// or no d-DNNF was built
+    pub fn witness(&self) -> Option<Vec<(u32, bool)>> {
+        self.ddnnf.as_ref()?.witness()
+    }
+
+    /// Returns the name of a variable of the circuit.
+    /// # Arguments
+    /// * `index` - The index of the variable in the circuit (starting at 0), i.e. the variable
+    ///   `index + 1` of the d4 format
+    /// # Returns
+    /// The name of the variable in the input file or None if there is no such variable
+    pub fn variable_name(&self, index: u32) -> Option<&str> {
+        self.name_map.get_by_right(&index).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solving::ddnnf::{DDNNFPrinter, DDNNFSize};
+    use crate::solving::pseudo_boolean_datastructure::ConstraintType::NotEqual;
+    use p2d_opb::{parse, OPBFile};
+    use serial_test::serial;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::str::FromStr;
+
+    #[test]
+    #[serial]
+    fn test_ex_1() {
+        let opb_file =
+            parse("#variable= 5 #constraint= 2\nx1 + x2 >= 0;\n3 x2 + x3 + x4 + x5 >= 3;")
+                .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from(18 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_2() {
+        let opb_file =
+            parse("#variable= 5 #constraint= 2\nx1 + x2 >= 1;\n3 x2 + x3 + x4 + x5 >= 3;")
+                .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from(17 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_3() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        //let mut printer = DDNNFPrinter{true_sink_id: None, false_sink_id: None, ddnnf: result.ddnnf.unwrap(), current_node_id: 0, id_map: HashMap::new(), edge_counter: 0, node_counter: 0};
+        //let ddnnf = printer.print();
+        //let ddnnf = result.ddnnf.unwrap().get_d4_string_representation();
+        //fs::write("berkely_p2d.d4", ddnnf);
+        let model_count = result.model_count;
+        println!("{:#?}", solver.statistics);
+        assert_eq!(model_count, BigUint::from_str(&"63552545718785").unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_4() {
+        let file_content =
+            fs::read_to_string("./test_models/financialservices01.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        println!("{:#?}", solver.statistics);
+        assert_eq!(model_count, BigUint::from_str("97451212554676").unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_5() {
+        let opb_file =
+            parse("#variable= 3 #constraint= 1\n2 x + y + z >= 2;\n").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        //let mut printer = DDNNFPrinter{true_sink_id: None, false_sink_id: None, ddnnf: result.ddnnf.unwrap(), current_node_id: 0, id_map: HashMap::new()};
+        //let ddnnf = printer.print();
+        //let ddnnf = result.ddnnf.unwrap().get_d4_string_representation();
+        //fs::write("test.d4", ddnnf);
+        let model_count = result.model_count;
+        println!("{:#?}", solver.statistics);
+        assert_eq!(model_count, BigUint::from(5 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_6() {
+        let file_content =
+            fs::read_to_string("./test_models/automotive2_4.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        //let mut printer = DDNNFPrinter{true_sink_id: None, false_sink_id: None, ddnnf: result.ddnnf.unwrap(), current_node_id: 0, id_map: HashMap::new()};
+        //let ddnnf = printer.print();
+        //let ddnnf = result.ddnnf.unwrap().get_d4_string_representation();
+        //fs::write("automotive2_p2d.d4", ddnnf);
+        let model_count = result.model_count;
+        println!("{:#?}", solver.statistics);
+        assert_eq!(model_count, BigUint::from_str("16505272636520770608049807336686263419262278171474896528902674080188226535986513386206222739154199990312304316432375708419908334951120777840761446056501033491673756322502123336090943486436039243372030766943458602037261070847529674534356018156008670682187009867114669183165589812678347677020009178324343716516097209109845184348679968274326123049227527790019157116786715333025963056661497445641173800199765222163167371496529076598275345593840432679060593082091562556148743367163011059914376453848874833624216454940443543476903147239713725910883379897186772787280371367887760273478656423910102759489682512679566900002943975655597096674268679680101882972677272515371297444691753104874195657464993976495326679318657622295700861777088118982149971100416087768578981508055766733740078413795875538473667538095783126142950285621270589214044781390019682483886583359849938540211221775670172765581722321182214883760887169041797021188330713322356432125673511102447057280896884295376155649470685335338495258057322025865111781429202794739966258303407257483764514048109066413495739887120721093956731137104071984616616093530304438776638066291197761951034921410607293591331155786344517409313802138987056145557947322022252231548896287559556403966183750725000574198535237943080891660398515976002019199247649442832823641555125736303883310186456855445612857146873733447167431344738817867253190162116602107467483579427839512688474377395370679756390400000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000").unwrap());
+    }
+
+    #[test]
+    #[serial]
+    // the seed is only used by PaToH
+    #[cfg(patoh_linked)]
+    fn test_partition_seeds() {
+        let file_content =
+            fs::read_to_string("./test_models/financialservices01.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        for seed in [1, 42] {
+            let mut solver = Solver::new(formula.clone());
+            solver.set_count_only(true);
+            solver.set_partition_seed(seed);
+            assert_eq!(
+                solver.solve().model_count,
+                BigUint::from(97451212554676_u64)
+            );
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_7() {
+        let file_content =
+            fs::read_to_string("./test_models/automotive01.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        //let mut printer = DDNNFPrinter{true_sink_id: None, false_sink_id: None, ddnnf: result.ddnnf.unwrap(), current_node_id: 0, id_map: HashMap::new()};
+        //let ddnnf = printer.print();
+        //let ddnnf = result.ddnnf.unwrap().get_d4_string_representation();
+        //fs::write("automotive2_p2d.d4", ddnnf);
+        let model_count = result.model_count;
+        println!("{:#?}", solver.statistics);
+        assert_eq!(model_count, BigUint::from_str("54337953889526644797436357304783500234473556203012469981705794070419609376066883019863858681556047971579366711252721976681982553481954710208375451836305175948768348959659511355551303323044387225600000000000000000000000").unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_8() {
+        let file_content =
+            fs::read_to_string("./test_models/busybox.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        //let mut printer = DDNNFPrinter{true_sink_id: None, false_sink_id: None, ddnnf: result.ddnnf.unwrap(), current_node_id: 0, id_map: HashMap::new()};
+        //let ddnnf = printer.print();
+        //let ddnnf = result.ddnnf.unwrap().get_d4_string_representation();
+        //fs::write("automotive2_p2d.d4", ddnnf);
+        let model_count = result.model_count;
+        println!("{:#?}", solver.statistics);
+        assert_eq!(model_count, BigUint::from_str("3599239755983329331332100508562451780508192148493160801718199944973008026807919208513108710328389951098075842967611059200000000000000000000000").unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_9() {
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 = 1;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from(2 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_10() {
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 < 2;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from(3 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_11() {
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 > 1;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from(1 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_12() {
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 != 1;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from(2 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_13() {
+        let opb_file = parse("#variable= 1 #constraint= 1\nx1 >= 0;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        let (ddnnf, _) = DDNNFPrinter::new(result.ddnnf.unwrap()).print();
+        assert_eq!(ddnnf, "t 1 0\n");
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_14() {
+        let opb_file = parse("#variable= 1 #constraint= 1\nx1 > 1;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        let (ddnnf, _) = DDNNFPrinter::new(result.ddnnf.unwrap()).print();
+        assert_eq!(ddnnf, "o 1 0\nf 2 0\n1 2 1 0\n");
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_15() {
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 >= 1;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        let (ddnnf, size) = DDNNFPrinter::new(result.ddnnf.unwrap()).print();
+        assert_eq!(ddnnf, "o 1 0\nt 2 0\n1 2 2 -1 0\n1 2 1 0\n");
+        // or(and(x2, -x1), x1)
+        assert_eq!(
+            size,
+            DDNNFSize {
+                and_nodes: 1,
+                or_nodes: 1,
+                leaves: 3,
+                edges: 4,
+            }
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_16() {
+        // the same as x1 + x2 < 2
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\n-x1 - x2 > -2;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from(3 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_17() {
+        // the same as x1 + x2 > 1
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\n-x1 - x2 < -1;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from(1 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_18() {
+        // all 4 models with x2 and only x1 = x3 = false without it
+        let opb_file = parse("#variable= 3 #constraint= 1\n2 x1 - 3 x2 + x3 < 1;")
+            .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from(5 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_19() {
+        // the complement of test_ex_18: no model with x2 and 3 without it
+        let opb_file = parse("#variable= 3 #constraint= 1\n2 x1 - 3 x2 + x3 > 0;")
+            .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from(3 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_20() {
+        // x1 - x2 - x3 = 0: all false, x1 and x2 or x1 and x3
+        let opb_file = parse("#variable= 3 #constraint= 1\n-1 < x1 - x2 - x3 < 1;")
+            .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from(3 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_21() {
+        // only ~x1 without x2
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\n~x1 - 2 x2 > 0;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from(1 as u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_ex_22() {
+        // the right-hand side of a strict comparison must be an integer
+        assert!(parse("#variable= 2 #constraint= 1\nx1 + x2 > 1.5;").is_err());
+        assert!(parse("#variable= 2 #constraint= 1\nx1 + x2 < 0.5;").is_err());
+        assert!(parse("#variable= 2 #constraint= 1\n-0.5 < x1 + x2 < 1;").is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_projected_count() {
+        let opb_file =
+            parse("#variable= 3 #constraint= 1\nx1 + x2 + x3 >= 1;").expect("error while parsing");
+        for (projection, expected) in [
+            (BTreeSet::from([0]), 2_u32),
+            (BTreeSet::from([0, 1]), 4),
+            (BTreeSet::from([0, 1, 2]), 7),
+            (BTreeSet::new(), 1),
+        ] {
+            let formula = PseudoBooleanFormula::new(&opb_file);
+            let mut solver = Solver::new(formula);
+            let model_count = solver.solve_projected(&projection).model_count;
+            assert_eq!(model_count, BigUint::from(expected));
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_projected_count_after_solve() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let projection: BTreeSet<u32> = (0..10).collect();
+        assert_eq!(
+            solver.solve().model_count,
+            BigUint::from(63552545718785_u64)
+        );
+        solver.reset();
+        let projected_count = solver.solve_projected(&projection).model_count;
+        solver.reset();
+        assert_eq!(
+            solver.solve().model_count,
+            BigUint::from(63552545718785_u64)
+        );
+
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut fresh_solver = Solver::new(formula);
+        assert_eq!(
+            fresh_solver.solve_projected(&projection).model_count,
+            projected_count
+        );
+        assert_eq!(projected_count, BigUint::from(4_u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_cancel() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+
+        let cancel_flag = Arc::new(AtomicBool::new(true));
+        let mut solver = Solver::with_cancel(formula.clone(), Arc::clone(&cancel_flag));
+        assert_eq!(
+            solver.try_solve().map(|result| result.model_count),
+            Err(Cancelled)
+        );
+
+        // the flag is set by the first progress report, i.e. in the middle of the search
+        #[cfg(feature = "show_progress")]
+        {
+            let cancel_flag = Arc::new(AtomicBool::new(false));
+            let mut solver = Solver::with_cancel(formula, Arc::clone(&cancel_flag));
+            let callback_flag = Arc::clone(&cancel_flag);
+            solver.set_progress_callback(Box::new(move |_| {
+                callback_flag.store(true, Ordering::Relaxed)
+            }));
+            assert_eq!(
+                solver.try_solve().map(|result| result.model_count),
+                Err(Cancelled)
+            );
+            assert!(cancel_flag.load(Ordering::Relaxed));
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_branching_heuristics() {
+        let heuristics = [
+            BranchingHeuristic::Vsids,
+            BranchingHeuristic::Dlcs,
+            BranchingHeuristic::VsidsDlcsBlend { vsids_weight: 0.8 },
+            BranchingHeuristic::Random,
+        ];
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        for heuristic in heuristics {
+            let opb_file =
+                parse("#variable= 5 #constraint= 2\nx1 + x2 >= 0;\n3 x2 + x3 + x4 + x5 >= 3;")
+                    .expect("error while parsing");
+            let formula = PseudoBooleanFormula::new(&opb_file);
+            let mut solver = Solver::new(formula);
+            solver.set_branching_heuristic(heuristic);
+            assert_eq!(solver.solve().model_count, BigUint::from(18_u32));
+
+            let opb_file = parse(file_content.as_str()).expect("error while parsing");
+            let formula = PseudoBooleanFormula::new(&opb_file);
+            let mut solver = Solver::new(formula);
+            solver.set_branching_heuristic(heuristic);
+            assert_eq!(
+                solver.solve().model_count,
+                BigUint::from_str("63552545718785").unwrap()
+            );
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_seeded_random_branching() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let mut outputs = Vec::new();
+        for _ in 0..2 {
+            let opb_file = parse(file_content.as_str()).expect("error while parsing");
+            let formula = PseudoBooleanFormula::new(&opb_file);
+            let mut solver = Solver::with_seed(formula, 42);
+            let result = solver.solve();
+            assert_eq!(
+                result.model_count,
+                BigUint::from_str("63552545718785").unwrap()
+            );
+            let mut node_ids = Vec::new();
+            let mut visited = std::collections::HashSet::new();
+            let mut stack = vec![Rc::clone(&result.ddnnf.unwrap().root_node)];
+            while let Some(node) = stack.pop() {
+                if !visited.insert(Rc::as_ptr(&node)) {
+                    continue;
+                }
+                match node.as_ref() {
+                    AndNode(children, id) | DDNNFNode::OrNode(children, id) => {
+                        node_ids.push(*id);
+                        stack.extend(children.iter().cloned());
+                    }
+                    LiteralLeave(literal) => node_ids.push(literal.index),
+                    _ => {}
+                }
+            }
+            outputs.push(node_ids);
+        }
+        assert_eq!(outputs[0], outputs[1]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_phase_selection() {
+        let phases = [
+            PhaseSelection::AlwaysTrue,
+            PhaseSelection::AlwaysFalse,
+            PhaseSelection::Saved,
+            PhaseSelection::Random,
+        ];
+        let mut instances = vec![
+            (
+                "#variable= 5 #constraint= 2\nx1 + x2 >= 0;\n3 x2 + x3 + x4 + x5 >= 3;".to_string(),
+                "18",
+            ),
+            (
+                "#variable= 5 #constraint= 2\nx1 + x2 >= 1;\n3 x2 + x3 + x4 + x5 >= 3;".to_string(),
+                "17",
+            ),
+            (
+                "#variable= 3 #constraint= 1\n2 x + y + z >= 2;\n".to_string(),
+                "5",
+            ),
+            ("#variable= 2 #constraint= 1\nx1 + x2 = 1;".to_string(), "2"),
+            ("#variable= 2 #constraint= 1\nx1 + x2 < 2;".to_string(), "3"),
+            ("#variable= 2 #constraint= 1\nx1 + x2 > 1;".to_string(), "1"),
+            (
+                "#variable= 2 #constraint= 1\nx1 + x2 != 1;".to_string(),
+                "2",
+            ),
+        ];
+        for (path, expected) in [
+            ("./test_models/berkeleydb.opb", "63552545718785"),
+            ("./test_models/financialservices01.opb", "97451212554676"),
+        ] {
+            instances.push((
+                fs::read_to_string(path).expect("cannot read file"),
+                expected,
+            ));
+        }
+        for phase in phases {
+            for (content, expected) in &instances {
+                let opb_file = parse(content.as_str()).expect("error while parsing");
+                let formula = PseudoBooleanFormula::new(&opb_file);
+                let mut solver = Solver::new(formula);
+                solver.set_phase_selection(phase);
+                assert_eq!(
+                    solver.solve().model_count,
+                    BigUint::from_str(expected).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_cache_capacity() {
+        let file_content =
+            fs::read_to_string("./test_models/financialservices01.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        solver.set_cache_capacity(8);
+        let model_count = solver.solve().model_count;
+        assert_eq!(model_count, BigUint::from_str("97451212554676").unwrap());
+        assert!(solver.statistics.cache_entries <= 8);
+        assert!(solver.statistics.cache_evictions > 0);
+    }
+
+    #[test]
+    #[serial]
+    fn test_memory_statistics() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        solver.solve();
+        let statistics = &solver.statistics;
+        assert!(statistics.peak_cache_entries > 0);
+        assert!(statistics.peak_cache_entries >= statistics.cache_entries);
+        assert!(statistics.ddnnf_node_count > 0);
+        assert!(statistics.estimated_bytes() > 0);
+
+        // no clauses are learned for berkeleydb
+        let file_content =
+            fs::read_to_string("./test_models/financialservices01.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        solver.solve();
+        assert!(solver.statistics.learned_clauses > 0);
+        assert!(solver.statistics.learned_clause_literals >= solver.statistics.learned_clauses);
+    }
+
+    #[test]
+    #[serial]
+    fn test_statistics_to_json() {
+        let opb_file =
+            parse("#variable= 5 #constraint= 2\nx1 + x2 >= 1;\n3 x2 + x3 + x4 + x5 >= 3;")
+                .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        solver.solve();
+        let json = solver.statistics.to_json();
+        assert!(json.starts_with('{') && json.ends_with('}'));
+        assert!(json.contains("\"cache_hits\":"));
+        assert!(json.contains("\"learned_clauses\":"));
+        assert!(json.contains(&format!(
+            "\"time_to_compute\":{}",
+            solver.statistics.time_to_compute
+        )));
+    }
+
+    #[test]
+    #[serial]
+    fn test_statistics_getters() {
+        let file_content =
+            fs::read_to_string("./test_models/financialservices01.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let start = std::time::Instant::now();
+        solver.solve();
+        let elapsed = start.elapsed().as_millis();
+        let statistics = &solver.statistics;
+        assert!(statistics.cache_hits() > 0);
+        assert_eq!(statistics.cache_entries(), solver.cache.len());
+        assert_eq!(statistics.cache_evictions(), 0);
+        assert!(statistics.peak_cache_entries() >= statistics.cache_entries());
+        assert_eq!(statistics.learned_clauses(), solver.learned_clauses.len());
+        assert!(statistics.learned_clause_literals() >= statistics.learned_clauses());
+        assert!(statistics.propagations_from_learned_clauses() > 0);
+        assert!(statistics.learned_clause_implications() > 0);
+        assert!(statistics.ddnnf_node_count() > 0);
+        assert!(statistics.time_to_compute() <= elapsed);
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(feature = "show_progress")]
+    fn test_progress_callback() {
+        let file_content =
+            fs::read_to_string("./test_models/financialservices01.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let progress = Rc::new(std::cell::RefCell::new(Vec::new()));
+        let callback_progress = Rc::clone(&progress);
+        solver.set_progress_callback(Box::new(move |p| callback_progress.borrow_mut().push(p)));
+        solver.solve();
+        let progress = progress.borrow();
+        assert!(!progress.is_empty());
+        for (previous, next) in progress.iter().zip(progress.iter().skip(1)) {
+            assert!(previous <= next);
+        }
+        assert!(progress.iter().all(|p| *p <= 100.0));
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(feature = "show_progress")]
+    fn test_progress_with_cache_hits_and_propagation() {
+        let unsat_formula = "#variable= 2 #constraint= 2\nx1 + x2 >= 2;\n~x1 >= 1;";
+        for file_content in [
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file"),
+            fs::read_to_string("./test_models/busybox.opb").expect("cannot read file"),
+            unsat_formula.to_string(),
+        ] {
+            let opb_file = parse(file_content.as_str()).expect("error while parsing");
+            let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+            let progress = Rc::new(std::cell::RefCell::new(Vec::new()));
+            let callback_progress = Rc::clone(&progress);
+            solver.set_progress_callback(Box::new(move |p| callback_progress.borrow_mut().push(p)));
+            solver.solve();
+            let progress = progress.borrow();
+            for (previous, next) in progress.iter().zip(progress.iter().skip(1)) {
+                assert!(previous < next);
+            }
+            assert_eq!(progress.last(), Some(&100.0));
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_conflicting_propagation() {
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 >= 1;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        assert_eq!(solver.propagate(0, true, FirstDecision), None);
+        // assigned variables leave the scope, so an inconsistent scope is simulated to reach the conflict
+        solver.variable_in_scope.insert(0);
+        assert_eq!(
+            solver.propagate(0, false, Propagated(NormalConstraintIndex(0))),
+            Some(NormalConstraintIndex(0))
+        );
+        assert_eq!(solver.assignments[0], Some((0, true)));
+    }
+
+    #[test]
+    #[serial]
+    fn test_cnf_input() {
+        let cnf_file = p2d_opb::parse_cnf("p cnf 4 3\n1 2 0\n-1 3 0\n-2 -3 4 0\n")
+            .expect("error while parsing");
+        let opb_file = parse(
+            "#variable= 4 #constraint= 3\nx1 + x2 >= 1;\n-1 x1 + x3 >= 0;\n-1 x2 -1 x3 + x4 >= -1;",
+        )
+        .expect("error while parsing");
+        let mut cnf_solver = Solver::new(PseudoBooleanFormula::new(&cnf_file));
+        let mut opb_solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let model_count = cnf_solver.solve().model_count;
+        assert_eq!(model_count, opb_solver.solve().model_count);
+        assert_eq!(model_count, BigUint::from(6_u32));
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(feature = "disconnected_components")]
+    fn test_max_cut_ratio() {
+        let content = "#variable= 5 #constraint= 4\nx1 + x2 >= 1;\nx2 + x3 >= 1;\nx3 + x4 >= 1;\nx4 + x5 >= 1;";
+        for (max_cut_ratio, uses_cut) in [(10.0, true), (0.0, false)] {
+            let opb_file = parse(content).expect("error while parsing");
+            let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+            solver.set_max_cut_ratio(max_cut_ratio);
+            assert!(solver.to_disconnected_components().is_none());
+            assert_eq!(!solver.next_variables.is_empty(), uses_cut);
+
+            let opb_file = parse(content).expect("error while parsing");
+            let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+            solver.set_max_cut_ratio(max_cut_ratio);
+            assert_eq!(solver.solve().model_count, BigUint::from(13_u32));
+        }
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(feature = "disconnected_components")]
+    fn test_hypergraph_cache() {
+        let opb_file = parse(
+            "#variable= 5 #constraint= 4\nx1 + x2 >= 1;\nx2 + x3 >= 1;\nx3 + x4 >= 1;\nx4 + x5 >= 1;",
+        )
+        .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_hypergraph_cache_capacity(16);
+        let undo = |solver: &mut Solver| {
+            while !solver.assignment_stack.is_empty() {
+                solver.undo_last_assignment();
+            }
+            solver.decision_level = 0;
+        };
+        let mut found_components = false;
+        for variable_index in [1, 2, 3] {
+            solver.decision_level += 1;
+            assert_eq!(solver.propagate(variable_index, true, FirstDecision), None);
+            let first = solver.get_hypergraph();
+            undo(&mut solver);
+            solver.get_hypergraph();
+            solver.decision_level += 1;
+            assert_eq!(solver.propagate(variable_index, true, FirstDecision), None);
+            let cached = solver.get_hypergraph();
+            assert!(Rc::ptr_eq(&first, &cached));
+            let recomputed = Hypergraph::new(&solver);
+            assert_eq!(
+                cached.find_disconnected_components(&solver),
+                recomputed.find_disconnected_components(&solver)
+            );
+            found_components |= cached.find_disconnected_components(&solver).is_some();
+            undo(&mut solver);
+        }
+        assert!(found_components);
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(all(feature = "parallel", feature = "disconnected_components"))]
+    fn test_parallel_components() {
+        use num_traits::ToPrimitive;
+        let file_content =
+            fs::read_to_string("./test_models/financialservices01.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let mut results = Vec::new();
+        for parallel_components in [false, true] {
+            let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+            solver.set_parallel_components(parallel_components);
+            let result = solver.solve();
+            assert_eq!(
+                result.ddnnf.unwrap().weighted_count(&HashMap::new()),
+                result.model_count.to_f64().unwrap()
+            );
+            results.push(result.model_count);
+        }
+        assert_eq!(results[0], results[1]);
+        assert_eq!(results[1], BigUint::from(97451212554676_u64));
+
+        // the formula only splits after deciding x1, so the components are solved sequentially
+        let opb_file = parse("#variable= 5 #constraint= 2\nx1 + x2 + x3 >= 1;\nx1 + x4 + x5 >= 1;")
+            .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert_eq!(solver.solve().model_count, BigUint::from(25_u32));
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(all(feature = "serde", feature = "cache"))]
+    fn test_save_and_load_cache() {
+        let content = "#variable= 4 #constraint= 2\nx1 + x2 + x3 >= 2;\nx3 + x4 >= 1;";
+        let path = crate::temp_file("cache.json");
+        let opb_file = parse(content).expect("error while parsing");
+        let mut cold_solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let cold_result = cold_solver.solve();
+        cold_solver.save_cache(&path).expect("cannot save cache");
+
+        let mut warm_solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        warm_solver.load_cache(&path).expect("cannot load cache");
+        assert_eq!(
+            warm_solver.statistics.cache_entries,
+            cold_solver.cache.len()
+        );
+        let warm_result = warm_solver.solve();
+        fs::remove_file(&path).expect("cannot remove cache");
+
+        assert_eq!(warm_result.model_count, cold_result.model_count);
+        assert_eq!(
+            warm_result.ddnnf.unwrap().weighted_count(&HashMap::new()),
+            cold_result.ddnnf.unwrap().weighted_count(&HashMap::new())
+        );
+        assert!(warm_solver.statistics.cache_hits > cold_solver.statistics.cache_hits);
+
+        // a count-only search saves its entries without circuits, which only a count-only search
+        // can use
+        let mut count_solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        count_solver.set_count_only(true);
+        let count_result = count_solver.solve();
+        count_solver.save_cache(&path).expect("cannot save cache");
+        let mut warm_solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        warm_solver.set_count_only(true);
+        warm_solver.load_cache(&path).expect("cannot load cache");
+        assert!(!count_solver.cache.is_empty());
+        assert_eq!(
+            warm_solver.statistics.cache_entries,
+            count_solver.cache.len()
+        );
+        assert_eq!(warm_solver.solve().model_count, count_result.model_count);
+        let mut circuit_solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        circuit_solver.load_cache(&path).expect("cannot load cache");
+        assert_eq!(circuit_solver.statistics.cache_entries, 0);
+
+        // the entries saved without weights have no weighted count
+        let mut weighted_solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        weighted_solver.set_weights(vec![(0.5, 2.0); 4]);
+        weighted_solver
+            .load_cache(&path)
+            .expect("cannot load cache");
+        assert_eq!(weighted_solver.statistics.cache_entries, 0);
+        let weighted_result = weighted_solver.solve();
+        fs::remove_file(&path).expect("cannot remove cache");
+        assert!(weighted_result.weighted_count.is_some_and(f64::is_finite));
+    }
+
+    #[test]
+    #[serial]
+    fn test_cache_key_depends_on_assignment() {
+        let opb_file = parse("#variable= 5 #constraint= 1\nx1 + x2 + x3 + x4 + x5 >= 2;")
+            .expect("error while parsing");
+        let cache_key = |assignment: &[(u32, bool)]| {
+            let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+            for (variable_index, sign) in assignment {
+                assert_eq!(
+                    solver.propagate(*variable_index, *sign, FirstDecision),
+                    None
+                );
+            }
+            calculate_hash(
+                &solver.variable_in_scope,
+                &solver.pseudo_boolean_formula,
+                &solver.constraint_indexes_in_scope,
+            )
+        };
+        // same scope, but x3 + x4 + x5 >= 1 remains in the first and x3 + x4 + x5 >= 2 in the second case
+        assert_ne!(
+            cache_key(&[(0, true), (1, false)]),
+            cache_key(&[(0, false), (1, false)])
+        );
+        // different assignments that leave the same subformula x3 + x4 + x5 >= 1
+        assert_eq!(
+            cache_key(&[(0, true), (1, false)]),
+            cache_key(&[(0, false), (1, true)])
+        );
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(feature = "clause_learning")]
+    fn test_max_learned_clauses() {
+        // busybox does not learn any clauses, financialservices01 exceeds the limit
+        for (path, expected_model_count, deletes_clauses) in [
+            ("./test_models/busybox.opb", "3599239755983329331332100508562451780508192148493160801718199944973008026807919208513108710328389951098075842967611059200000000000000000000000", false),
+            ("./test_models/financialservices01.opb", "97451212554676", true),
+        ] {
+            let file_content = fs::read_to_string(path).expect("cannot read file");
+            let opb_file = parse(file_content.as_str()).expect("error while parsing");
+            let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+            solver.set_max_learned_clauses(4);
+            let result = solver.solve();
+            assert_eq!(
+                result.model_count,
+                BigUint::from_str(expected_model_count).unwrap()
+            );
+            assert!(solver.statistics.learned_clauses <= 4);
+            assert_eq!(
+                solver.learned_clauses.len() > solver.statistics.learned_clauses,
+                deletes_clauses
+            );
+            for clause_indexes in &solver.learned_clauses_by_variables {
+                for i in clause_indexes {
+                    assert!(!solver.learned_clauses[*i].literals.is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(feature = "clause_learning")]
+    fn test_propagations_from_learned_clauses() {
+        // busybox does not learn any clauses, financialservices01 uses its learned clauses
+        for (path, uses_learned_clauses) in [
+            ("./test_models/busybox.opb", false),
+            ("./test_models/financialservices01.opb", true),
+        ] {
+            let file_content = fs::read_to_string(path).expect("cannot read file");
+            let opb_file = parse(file_content.as_str()).expect("error while parsing");
+            let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+            solver.solve();
+            let statistics = &solver.statistics;
+            assert_eq!(
+                statistics.propagations_from_learned_clauses > 0,
+                uses_learned_clauses
+            );
+            assert_eq!(
+                statistics.learned_clause_implications > 0,
+                uses_learned_clauses
+            );
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_weighted_count() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let number_variables = formula.number_variables;
+        let mut solver = Solver::new(formula);
+        solver.set_weights(vec![(0.5, 0.5); number_variables as usize]);
+        let result = solver.solve();
+        let expected = result.model_count.to_string().parse::<f64>().unwrap()
+            * 0.5_f64.powi(number_variables as i32);
+        let weighted_count = result.weighted_count.unwrap();
+        assert!((weighted_count - expected).abs() <= 1e-9 * expected);
+
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert_eq!(solver.solve().weighted_count, None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_weighted_count_matches_ddnnf() {
+        let file_content =
+            fs::read_to_string("./test_models/financialservices01.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let weights: Vec<(f64, f64)> = (0..formula.number_variables)
+            .map(|index| {
+                let positive_weight = (index % 7 + 1) as f64 / 8.0;
+                (positive_weight, 1.0 - positive_weight)
+            })
+            .collect();
+        let mut solver = Solver::new(formula);
+        solver.set_weights(weights.clone());
+        let result = solver.solve();
+        assert!(solver.statistics.cache_hits > 0);
+        let mut weight_map = HashMap::new();
+        for (index, (positive_weight, negative_weight)) in weights.iter().enumerate() {
+            weight_map.insert((index as u32, true), *positive_weight);
+            weight_map.insert((index as u32, false), *negative_weight);
+        }
+        let expected = result.ddnnf.unwrap().weighted_count(&weight_map);
+        let weighted_count = result.weighted_count.unwrap();
+        assert!((weighted_count - expected).abs() <= 1e-9 * expected);
+    }
+
+    #[test]
+    #[serial]
+    fn test_solve_under_assumptions() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let with_true = solver.solve_under_assumptions(&[(3, true)]).model_count;
+        let with_false = solver.solve_under_assumptions(&[(3, false)]).model_count;
+        assert!(!with_true.is_zero() && !with_false.is_zero());
+        assert_eq!(
+            with_true + with_false,
+            BigUint::from_str("63552545718785").unwrap()
+        );
+
+        let contradiction = solver.solve_under_assumptions(&[(3, true), (3, false)]);
+        assert!(contradiction.model_count.is_zero());
+        assert!(matches!(
+            *contradiction.ddnnf.unwrap().root_node,
+            FalseLeave
+        ));
+
+        let without_assumptions = solver.solve_under_assumptions(&[]);
+        assert_eq!(
+            without_assumptions.model_count,
+            BigUint::from_str("63552545718785").unwrap()
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_reset() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let first_result = solver.solve();
+        let (first_ddnnf, _) =
+            DDNNFPrinter::new(first_result.ddnnf.unwrap().canonicalize()).print();
+
+        solver.reset();
+        assert_eq!(
+            solver.number_unsat_constraints,
+            solver.pseudo_boolean_formula.constraints.len()
+        );
+        assert!(solver.assignments.iter().all(|a| a.is_none()));
+        let second_result = solver.solve();
+        assert_eq!(second_result.model_count, first_result.model_count);
+        let (second_ddnnf, _) =
+            DDNNFPrinter::new(second_result.ddnnf.unwrap().canonicalize()).print();
+        assert_eq!(second_ddnnf, first_ddnnf);
+
+        solver.reset();
+        solver.clear_cache();
+        assert_eq!(solver.cache.len(), 0);
+        assert_eq!(solver.solve().model_count, first_result.model_count);
+    }
+
+    #[test]
+    #[serial]
+    fn test_reset_query_kinds() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let fresh_solver = || Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let projection: BTreeSet<u32> = (0..10).collect();
+        let expected_projected = fresh_solver().solve_projected(&projection).model_count;
+        let expected_assumed = fresh_solver()
+            .solve_under_assumptions(&[(3, true)])
+            .model_count;
+        let mut solver = fresh_solver();
+        let model_count = solver.solve().model_count;
+
+        // every query runs on the cache filled by the queries before it
+        solver.reset();
+        assert_eq!(solver.model_count(), model_count);
+        solver.reset();
+        let result = solver.solve();
+        assert_eq!(result.model_count, model_count);
+        assert!(result.ddnnf.is_some());
+        solver.reset();
+        assert_eq!(
+            solver.solve_projected(&projection).model_count,
+            expected_projected
+        );
+        assert_eq!(
+            solver.solve_under_assumptions(&[(3, true)]).model_count,
+            expected_assumed
+        );
+        assert!(solver.solve_at_least(model_count.clone()));
+        assert!(!solver.solve_at_least(model_count.clone() + 1_u32));
+        solver.reset();
+        let number_variables = solver.pseudo_boolean_formula.number_variables as usize;
+        solver.set_weights(vec![(2.0, 1.0); number_variables]);
+        let weighted_count = solver.solve().weighted_count.unwrap();
+        let mut weighted_solver = fresh_solver();
+        weighted_solver.set_weights(vec![(2.0, 1.0); number_variables]);
+        assert_eq!(
+            weighted_solver.solve().weighted_count.unwrap(),
+            weighted_count
+        );
+        solver.reset();
+        assert_eq!(solver.solve().model_count, model_count);
+    }
+
+    #[test]
+    #[serial]
+    fn test_count_only() {
+        for model in ["berkeleydb", "busybox", "financialservices01"] {
+            let file_content =
+                fs::read_to_string(format!("./test_models/{model}.opb")).expect("cannot read file");
+            let opb_file = parse(file_content.as_str()).expect("error while parsing");
+            let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+            let full_result = solver.solve();
+            assert!(full_result.ddnnf.is_some());
+
+            let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+            solver.set_count_only(true);
+            let count_only_result = solver.solve();
+            assert_eq!(count_only_result.model_count, full_result.model_count);
+            assert!(count_only_result.ddnnf.is_none());
+            assert_eq!(count_only_result.witness(), None);
+            assert_eq!(solver.statistics.ddnnf_node_count(), 0);
+        }
+
+        let opb_file =
+            parse("#variable= 3 #constraint= 1\nx1 + x2 + x3 >= 2;").expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_weights(vec![(0.5, 0.5), (0.25, 0.75), (1.0, 1.0)]);
+        solver.set_count_only(true);
+        let result = solver.solve();
+        assert_eq!(result.model_count, BigUint::from(4u32));
+        solver.set_count_only(false);
+        solver.reset();
+        assert_eq!(result.weighted_count, solver.solve().weighted_count);
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(feature = "disconnected_components")]
+    fn test_component_splits() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let number_variables = formula.number_variables as usize;
+        let number_constraints = formula.constraints.len();
+        assert!(Solver::new(formula.clone())
+            .solve()
+            .component_splits
+            .is_empty());
+        let mut solver = Solver::new(formula);
+        solver.set_record_splits(true);
+        let result = solver.solve();
+        assert!(!result.component_splits.is_empty());
+        for split in &result.component_splits {
+            assert!(split.component_count() >= 2);
+            assert_eq!(split.constraint_counts.len(), split.component_count());
+            assert!(split.variable_counts.iter().all(|count| *count >= 1));
+            assert!(split.variable_counts.iter().sum::<usize>() <= number_variables);
+            assert!(split.constraint_counts.iter().sum::<usize>() <= number_constraints);
+            assert!(split.decision_level as usize <= number_variables);
+        }
+
+        // a formula without constraints in common is split at the start of the search
+        let opb_file = parse("#variable= 4 #constraint= 2\nx1 + x2 >= 1;\nx3 + x4 >= 1;")
+            .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_record_splits(true);
+        let result = solver.solve();
+        assert_eq!(
+            result.component_splits,
+            vec![ComponentStats {
+                decision_level: 0,
+                variable_counts: vec![2, 2],
+                constraint_counts: vec![1, 1],
+            }]
+        );
+        solver.reset();
+        assert_eq!(solver.solve().component_splits.len(), 1);
+    }
+
+    #[test]
+    #[serial]
+    fn test_min_constraints_for_decomposition() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula.clone());
+        solver.set_record_splits(true);
+        let decomposed = solver.solve();
+        assert!(!decomposed.component_splits.is_empty());
+
+        let mut solver = Solver::new(formula);
+        solver.set_record_splits(true);
+        solver.set_min_constraints_for_decomposition(usize::MAX);
+        let result = solver.solve();
+        assert!(result.component_splits.is_empty());
+        assert_eq!(result.model_count, decomposed.model_count);
+    }
+
+    #[test]
+    #[serial]
+    fn test_unsat_simplification_leaves_initial_state() {
+        // x2 >= 1 is satisfied and removed from the scope before the conflict of x1 is found
+        let opb_file =
+            parse("#variable= 3 #constraint= 4\nx2 >= 1;\nx1 >= 1;\n~x1 >= 1;\nx2 + x3 >= 1;")
+                .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert_eq!(solver.solve().model_count, BigUint::zero());
+        assert!(solver.assignment_stack.is_empty());
+        assert!(solver.assignments.iter().all(|a| a.is_none()));
+        assert_eq!(
+            solver.number_unsat_constraints,
+            solver.pseudo_boolean_formula.constraints.len()
+        );
+        assert_eq!(
+            solver.constraint_indexes_in_scope.len(),
+            solver.pseudo_boolean_formula.constraints.len()
+        );
+        assert_eq!(solver.solve().model_count, BigUint::zero());
+        solver.reset();
+        assert_eq!(solver.solve().model_count, BigUint::zero());
+
+        // a single impossible constraint makes the formula unsatisfiable before any search
+        let opb_file = parse("#variable= 3 #constraint= 2\nx1 + x2 >= 1;\nx2 + x3 >= 1;")
+            .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert_eq!(solver.solve().model_count, BigUint::from(5u32));
+        let impossible = parse("#variable= 3 #constraint= 1\nx1 + x2 + 2 x3 >= 5;")
+            .expect("error while parsing")
+            .equations
+            .remove(0);
+        solver
+            .add_constraint(&impossible)
+            .expect("cannot add constraint");
+        let result = solver.solve();
+        assert_eq!(result.model_count, BigUint::zero());
+        solver.reset();
+        assert_eq!(solver.solve().model_count, BigUint::zero());
+    }
+
+    #[test]
+    #[serial]
+    fn test_add_constraint() {
+        let file_content =
+            fs::read_to_string("./test_models/financialservices01.opb").expect("cannot read file");
+        let full_opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let expected = Solver::new(PseudoBooleanFormula::new(&full_opb_file))
+            .solve()
+            .model_count;
+
+        let mut opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let last_equation = opb_file.equations.pop().unwrap();
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let without_constraint = solver.solve().model_count;
+        solver
+            .add_constraint(&last_equation)
+            .expect("error while adding the constraint");
+        assert_eq!(solver.solve().model_count, expected);
+        assert!(without_constraint >= expected);
+
+        // the second clause introduces x3
+        let cnf_file =
+            p2d_opb::parse_cnf("p cnf 3 2\n1 2 0\n2 3 0\n").expect("error while parsing");
+        let mut opb_file = p2d_opb::parse_cnf("p cnf 2 1\n1 2 0\n").expect("error while parsing");
+        opb_file.name_map = cnf_file.name_map.clone();
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert_eq!(solver.solve().model_count, BigUint::from(3u32));
+        solver
+            .add_constraint(&cnf_file.equations[1])
+            .expect("error while adding the constraint");
+        assert_eq!(solver.solve().model_count, BigUint::from(5u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_empty_formula() {
+        let formula = PseudoBooleanFormula {
+            constraints: Vec::new(),
+            number_variables: 0,
+            constraints_by_variable: Vec::new(),
+            name_map: BiMap::new(),
+            contradictory: false,
+            contradictory_equations: Vec::new(),
+            number_equations: 0,
+            objective: None,
+        };
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        assert_eq!(result.model_count, BigUint::one());
+        assert!(matches!(
+            *result.ddnnf.as_ref().unwrap().root_node,
+            TrueLeave
+        ));
+        assert_eq!(result.witness(), Some(Vec::new()));
+        let (ddnnf, _) = DDNNFPrinter::new(result.ddnnf.as_ref().unwrap().canonicalize()).print();
+        assert!(!ddnnf.is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn test_trivial_constraints() {
+        let opb_file = parse(
+            "#variable= 3 #constraint= 4\nx1 + x2 >= 0;\n2 x2 + x3 >= -1;\nx1 + x3 != 3;\nx1 + x2 != -2;",
+        )
+        .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        assert!(formula.constraints.is_empty());
+        assert!(!formula.contradictory);
+        let mut solver = Solver::new(formula);
+        assert_eq!(solver.solve().model_count, BigUint::from(8u32));
+
+        let opb_file = parse("#variable= 3 #constraint= 2\nx1 + x2 >= 3;\nx1 + x2 + x3 >= 1;")
+            .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        assert_eq!(formula.constraints.len(), 1);
+        assert!(formula.contradictory);
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        assert!(result.model_count.is_zero());
+        assert!(matches!(*result.ddnnf.unwrap().root_node, FalseLeave));
+    }
+
+    #[test]
+    #[serial]
+    fn test_duplicate_constraints() {
+        let opb_file = parse("#variable= 3 #constraint= 2\nx1 + 2 x2 + x3 >= 2;\nx1 + x3 != 1;")
+            .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        assert_eq!(formula.constraints.len(), 2);
+        let mut solver = Solver::new(formula);
+        let expected_count = solver.solve().model_count;
+
+        let opb_file = parse(
+            "#variable= 3 #constraint= 4\nx1 + 2 x2 + x3 >= 2;\nx1 + x3 != 1;\n2 x2 + x3 + x1 >= 2;\nx1 + x3 != 1;",
+        )
+        .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        assert_eq!(formula.constraints.len(), 2);
+        for (position, constraint) in formula.constraints.iter().enumerate() {
+            assert_eq!(constraint.index, NormalConstraintIndex(position));
+        }
+        for (variable, constraints) in formula.constraints_by_variable.iter().enumerate() {
+            assert!(constraints
+                .iter()
+                .all(|index| formula.constraints[*index].literals.contains_key(&variable)));
+        }
+        let mut solver = Solver::new(formula);
+        assert_eq!(solver.solve().model_count, expected_count);
+
+        // the same literals with a different degree or type are kept
+        let opb_file =
+            parse("#variable= 2 #constraint= 3\nx1 + x2 >= 1;\nx1 + x2 >= 2;\nx1 + x2 != 1;")
+                .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        assert_eq!(formula.constraints.len(), 3);
+    }
+
+    #[test]
+    #[serial]
+    fn test_solve_at_least() {
+        let file_content =
+            fs::read_to_string("./test_models/automotive01.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        assert!(solver.solve_at_least(BigUint::from(2u32)));
+
+        // the threshold is only reached with all models
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let model_count = BigUint::from_str("63552545718785").unwrap();
+        assert!(solver.solve_at_least(model_count.clone()));
+        assert!(!solver.solve_at_least(model_count.clone() + 1u32));
+        assert!(solver.solve_at_least(BigUint::from(1000u32)));
+        assert_eq!(solver.solve().model_count, model_count);
+
+        let opb_file = parse("#variable= 2 #constraint= 2\nx1 >= 1;\n~x1 + x2 >= 2;")
+            .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        assert!(!solver.solve_at_least(BigUint::one()));
+        assert!(solver.solve_at_least(BigUint::zero()));
+    }
+
+    #[test]
+    #[serial]
+    fn test_constraint_activities() {
+        // the formula of test_ex_1
+        let opb_file =
+            parse("#variable= 5 #constraint= 2\nx1 + x2 >= 0;\n3 x2 + x3 + x4 + x5 >= 3;")
+                .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        assert_eq!(solver.solve().model_count, BigUint::from(18u32));
+        let activities = solver.constraint_activities();
+        assert_eq!(
+            activities.len(),
+            solver.pseudo_boolean_formula.constraints.len()
+        );
+        assert!(activities.iter().sum::<u32>() > 0);
+
+        let most_active_constraints = solver.most_active_constraints(1);
+        assert_eq!(most_active_constraints.len(), 1);
+        let (index, activity) = most_active_constraints[0];
+        assert_eq!(activity, *activities.iter().max().unwrap());
+        assert_eq!(activities[index], activity);
+        assert_eq!(solver.most_active_constraints(10).len(), activities.len());
+    }
+
+    /// Counts the models of a small OPB file by evaluating every assignment.
+    fn brute_force_count(opb_file: &OPBFile) -> BigUint {
+        let number_variables = opb_file.name_map.len();
+        let models = (0..1u32 << number_variables)
+            .filter(|bits| {
+                let assignment: Vec<bool> = (0..number_variables)
+                    .map(|i| bits & (1 << i) != 0)
+                    .collect();
+                opb_file.evaluate(&assignment)
+            })
+            .count();
+        BigUint::from(models)
+    }
+
+    #[test]
+    #[serial]
+    fn test_normalization_with_negations() {
+        let instances = [
+            // <= and < with negative factors and negated variables
+            "#variable= 3 #constraint= 2\n-2 x1 + 3 ~x2 - x3 <= 1;\n-x1 - 2 ~x3 < -1;",
+            // a variable and its negation in the same constraint
+            "#variable= 3 #constraint= 2\nx1 - 2 ~x1 + x2 < 1;\n3 x3 + 2 ~x3 - ~x2 <= 3;",
+            "#variable= 3 #constraint= 2\n-x1 - 2 ~x1 + x2 <= -1;\n~x2 + x3 - 2 x3 + ~x1 = 1;",
+            "#variable= 4 #constraint= 3\n2 ~x1 - 3 x1 + x4 < 2;\n-x2 + 2 ~x2 - x3 > -1;\nx1 + ~x4 + 2 x4 != 2;",
+        ];
+        for instance in instances {
+            let opb_file = parse(instance).expect("error while parsing");
+            let formula = PseudoBooleanFormula::new(&opb_file);
+            let mut solver = Solver::new(formula);
+            assert_eq!(
+                solver.solve().model_count,
+                brute_force_count(&opb_file),
+                "wrong count for {instance}"
+            );
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_constraint_to_equation() {
+        let opb_file = parse("#variable= 3 #constraint= 2\nx1 + 2 x2 - 3 x3 >= 1;\nx3 + x1 != 1;")
+            .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let equations: Vec<String> = formula
+            .constraints
+            .iter()
+            .map(|constraint| constraint.to_equation(&formula.name_map))
+            .collect();
+        assert_eq!(
+            equations,
+            vec!["+1 x1 +2 x2 +3 ~x3 >= 4;", "+1 x1 +1 x3 != 1;"]
+        );
+
+        // the printed constraints are parsed to the same constraints again
+        let opb_file = parse(&format!(
+            "#variable= 3 #constraint= 2\n{}",
+            equations.join("\n")
+        ))
+        .expect("error while parsing");
+        let round_tripped = PseudoBooleanFormula::new(&opb_file);
+        assert_eq!(round_tripped.constraints, formula.constraints);
+    }
+
+    #[test]
+    #[serial]
+    fn test_not_equal_propagation() {
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 != 1;").expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+
+        // x1 = 1 reaches the degree, x2 must be true as well
+        let mut constraint = formula.constraints[0].clone();
+        let result = constraint.propagate(
+            Literal {
+                index: 0,
+                factor: 1,
+                positive: true,
+            },
+            FirstDecision,
+            1,
+        );
+        assert!(matches!(
+            result,
+            ImpliedLiteral(Literal {
+                index: 1,
+                positive: true,
+                ..
+            })
+        ));
+
+        // x1 = 0 only reaches the degree if x2 is true, so x2 must be false
+        let mut constraint = formula.constraints[0].clone();
+        let result = constraint.propagate(
+            Literal {
+                index: 0,
+                factor: 1,
+                positive: false,
+            },
+            FirstDecision,
+            1,
+        );
+        assert!(matches!(
+            result,
+            ImpliedLiteral(Literal {
+                index: 1,
+                positive: false,
+                ..
+            })
+        ));
+
+        let mut solver = Solver::new(formula);
+        assert_eq!(solver.solve().model_count, BigUint::from(2u32));
+        // without the propagation, x2 would be decided in both branches of x1
+        assert_eq!(solver.statistics.decisions(), 1);
+
+        // after x1 = 1 both values of x3 avoid the degree
+        let opb_file =
+            parse("#variable= 3 #constraint= 1\nx1 + 2 x3 != 2;").expect("error while parsing");
+        let mut constraint = PseudoBooleanFormula::new(&opb_file).constraints[0].clone();
+        let result = constraint.propagate(
+            Literal {
+                index: 0,
+                factor: 1,
+                positive: true,
+            },
+            FirstDecision,
+            1,
+        );
+        assert!(matches!(result, NothingToPropagated));
+    }
+
+    #[test]
+    #[serial]
+    fn test_is_trivially_unsat() {
+        let opb_file = parse("#variable= 3 #constraint= 2\n2 x1 + x2 >= 4;\nx2 + x3 >= 1;")
+            .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        assert!(formula.is_trivially_unsat());
+        let mut solver = Solver::new(formula);
+        assert!(solver.solve().model_count.is_zero());
+
+        let opb_file = parse("#variable= 3 #constraint= 2\n2 x1 + x2 >= 3;\nx2 + x3 >= 1;")
+            .expect("error while parsing");
+        let mut formula = PseudoBooleanFormula::new(&opb_file);
+        assert!(!formula.is_trivially_unsat());
+
+        formula.constraints[0].degree = 4;
+        assert!(formula.is_trivially_unsat());
+    }
+
+    /// A writer that can still be read after it was moved into the solver.
+    struct SharedBuffer(Rc<std::cell::RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_trace() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let buffer = Rc::new(std::cell::RefCell::new(Vec::new()));
+        solver.set_trace_writer(Box::new(SharedBuffer(Rc::clone(&buffer))));
+        let model_count = solver.solve().model_count;
+
+        let trace = String::from_utf8(buffer.borrow().clone()).expect("invalid trace");
+        assert!(trace.lines().any(|line| line.starts_with("SPLIT k=")));
+        assert!(trace.lines().any(|line| line.starts_with("LEAF count=")));
+        let combine_lines: Vec<&str> = trace
+            .lines()
+            .filter(|line| line.starts_with("COMBINE "))
+            .collect();
+        // every step can be checked on its own
+        for line in &combine_lines {
+            let (operation, result) = line.split_once(" => ").unwrap();
+            let result = BigUint::from_str(result).unwrap();
+            let terms = operation
+                .split(' ')
+                .skip(2)
+                .filter(|term| *term != "+" && *term != "*")
+                .map(|term| BigUint::from_str(term).unwrap());
+            if operation.contains(" + ") {
+                assert_eq!(terms.sum::<BigUint>(), result);
+            } else {
+                assert_eq!(terms.product::<BigUint>(), result);
+            }
+        }
+        let top_level_combine = combine_lines.last().unwrap();
+        assert!(top_level_combine.ends_with(&format!(" => {model_count}")));
+    }
+
+    #[test]
+    #[serial]
+    fn test_variable_name() {
+        let opb_file = parse("#variable= 3 #constraint= 2\nb + a >= 1;\n~c >= 1;")
+            .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula);
+        let result = solver.solve();
+        assert_eq!(result.variable_name(0), Some("b"));
+        assert_eq!(result.variable_name(3), None);
+
+        // the only literal of c in the d4 output is -3
+        let (ddnnf, _) = DDNNFPrinter::new(result.ddnnf.as_ref().unwrap().canonicalize()).print();
+        let c_literals: Vec<i64> = ddnnf
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_digit()))
+            .flat_map(|line| line.split(' ').skip(2).map(|l| l.parse::<i64>().unwrap()))
+            .filter(|literal| *literal != 0)
+            .filter(|literal| result.variable_name(literal.unsigned_abs() as u32 - 1) == Some("c"))
+            .collect();
+        assert!(!c_literals.is_empty());
+        assert!(c_literals.iter().all(|literal| *literal == -3));
+    }
+
+    #[test]
+    #[serial]
+    fn test_witness() {
+        let opb_file = parse("#variable= 4 #constraint= 1\n3 x2 + x3 + x4 + x5 >= 3;")
+            .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let witness = solver.solve().witness().expect("formula is satisfiable");
+        assert_eq!(witness.len(), opb_file.max_name_index as usize);
+        let assignment: Vec<bool> = witness.iter().map(|(_, value)| *value).collect();
+        assert!(opb_file.evaluate(&assignment));
+
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let witness = solver.solve().witness().expect("formula is satisfiable");
+        let assignment: Vec<bool> = witness.iter().map(|(_, value)| *value).collect();
+        assert!(opb_file.evaluate(&assignment));
+
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 >= 3;").expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert!(solver.solve().witness().is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn test_try_from() {
+        let opb = "#variable= 5 #constraint= 2\nx1 + x2 >= 1;\n3 x2 + x3 + x4 + x5 >= 3;";
+        let opb_file = parse(opb).expect("error while parsing");
+        let formula = PseudoBooleanFormula::try_from(&opb_file).expect("cannot build formula");
+        assert_eq!(formula.constraints.len(), 2);
+        assert_eq!(
+            Solver::new(formula).solve().model_count,
+            BigUint::from(17u32)
+        );
+        let formula: PseudoBooleanFormula = opb.try_into().expect("cannot build formula");
+        assert_eq!(
+            Solver::new(formula).solve().model_count,
+            BigUint::from(17u32)
+        );
+
+        let Err(error) = PseudoBooleanFormula::try_from("#variable= 2 #constraint= 1\nx1 + >= 1;")
+        else {
+            panic!("a malformed file must not be accepted");
+        };
+        assert!(error.starts_with("Parsing error"));
+        let max = i128::MAX;
+        let opb_file = parse(&format!(
+            "#variable= 1 #constraint= 1\n{max} x1 + {max} x1 >= 1;"
+        ))
+        .expect("error while parsing");
+        assert!(PseudoBooleanFormula::try_from(&opb_file).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_saturation() {
+        let opb_file = parse("#variable= 4 #constraint= 1\n3 x2 + x3 + x4 + x5 >= 3;")
+            .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let x2 = *formula.name_map.get_by_left("x2").unwrap() as usize;
+        let constraint = &formula.constraints[0];
+        assert_eq!(constraint.literals[&x2].factor, 3);
+        assert_eq!(constraint.factor_sum, 6);
+        assert_eq!(constraint.max_literal.factor, 3);
+        assert_eq!(
+            Solver::new(formula).solve().model_count,
+            brute_force_count(&opb_file)
+        );
+
+        let opb_file =
+            parse("#variable= 4 #constraint= 2\n5 x1 + 2 x2 + x3 >= 2;\n7 ~x4 + x1 != 3;")
+                .expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let x1 = *formula.name_map.get_by_left("x1").unwrap() as usize;
+        let x4 = *formula.name_map.get_by_left("x4").unwrap() as usize;
+        let constraint = &formula.constraints[0];
+        assert_eq!(constraint.literals[&x1].factor, 2);
+        assert_eq!(constraint.unassigned_literals[&x1].factor, 2);
+        assert_eq!(constraint.factor_sum, 5);
+        assert_eq!(constraint.sum_unassigned, 5);
+        assert_eq!(constraint.max_literal.factor, 2);
+        // constraints with != are not saturated
+        assert_eq!(formula.constraints[1].literals[&x4].factor, 7);
+        assert_eq!(
+            Solver::new(formula).solve().model_count,
+            brute_force_count(&opb_file)
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_coefficient_overflow() {
+        let max = i128::MAX;
+        let opb_file =
+            parse(format!("#variable= 2 #constraint= 1\n{max} x1 + {max} x2 >= {max};").as_str())
+                .expect("error while parsing");
+        let formula = PseudoBooleanFormula::try_new(&opb_file).expect("sum fits into an u128");
+        assert_eq!(formula.constraints[0].factor_sum, u128::MAX - 1);
+        let mut solver = Solver::new(formula);
+        assert_eq!(solver.solve().model_count, BigUint::from(3u32));
+
+        // the sum of the factors is larger than u128::MAX
+        let opb_file = parse(
+            format!("#variable= 3 #constraint= 1\n{max} x1 + {max} x2 + {max} x3 >= 1;").as_str(),
+        )
+        .expect("error while parsing");
+        let error = PseudoBooleanFormula::try_new(&opb_file).unwrap_err();
+        assert!(error.contains("sum of the coefficients"));
+
+        // merging the summands of x1 overflows
+        let opb_file =
+            parse(format!("#variable= 1 #constraint= 1\n{max} x1 + {max} x1 >= 1;").as_str())
+                .expect("error while parsing");
+        assert!(PseudoBooleanFormula::try_new(&opb_file).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_dlcs_scores() {
+        let opb_file =
+            parse("#variable= 5 #constraint= 2\nx1 + x2 >= 1;\n3 x2 + x3 + x4 + x5 >= 3;")
+                .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_branching_heuristic(BranchingHeuristic::Dlcs);
+        assert_eq!(solver.solve().model_count, BigUint::from(17_u32));
+
+        // the sum of the true literals exceeds the degree after two decisions
+        let opb_file =
+            parse("#variable= 4 #constraint= 2\nx1 + x2 + x3 != 1;\nx1 + x2 + x3 + x4 != 0;")
+                .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_branching_heuristic(BranchingHeuristic::Dlcs);
+        assert_eq!(solver.solve().model_count, BigUint::from(9_u32));
+        assert!(solver
+            .dlcs_scores
+            .iter()
+            .all(|score| score.is_finite() && *score >= 0.0));
+    }
+
+    #[test]
+    #[serial]
+    fn test_tie_breaking_by_index() {
+        let opb_file = parse("#variable= 4 #constraint= 2\nx1 + x2 >= 1;\nx3 + x4 >= 1;")
+            .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.vsids_scores = vec![1.0, 5.0, 1.0, 5.0];
+
+        // the candidates of the last propagation are visited in the order they were found
+        solver.next_variables = vec![3, 2, 1];
+        assert_eq!(solver.get_next_variable(), Some(1));
+        solver.next_variables = vec![1, 3];
+        assert_eq!(solver.get_next_variable(), Some(1));
+
+        solver.next_variables.clear();
+        assert_eq!(solver.get_next_variable(), Some(1));
+        solver.vsids_scores = vec![1.0, 1.0, 1.0, 1.0];
+        assert_eq!(solver.get_next_variable(), Some(0));
+
+        // a higher score still wins over a smaller index
+        solver.vsids_scores = vec![1.0, 1.0, 1.0, 5.0];
+        solver.next_variables = vec![0, 3];
+        assert_eq!(solver.get_next_variable(), Some(3));
+
+        assert_eq!(better_candidate(Some((2.0, 4)), (2.0, 1)), Some((2.0, 1)));
+        assert_eq!(better_candidate(Some((2.0, 1)), (2.0, 4)), Some((2.0, 1)));
+        assert_eq!(better_candidate(None, (0.0, 7)), Some((0.0, 7)));
+    }
+
+    #[test]
+    #[serial]
+    fn test_chained_constraints() {
+        let chained = parse(
+            "#variable= 4 #constraint= 2\n2 <= x1 + 2 x2 + ~x3 + x4 <= 3;\n-1 < x1 - x4 < 1;",
+        )
+        .expect("error while parsing");
+        let spelled_out = parse(
+            "#variable= 4 #constraint= 4\nx1 + 2 x2 + ~x3 + x4 >= 2;\nx1 + 2 x2 + ~x3 + x4 <= 3;\nx1 - x4 > -1;\nx1 - x4 < 1;",
+        )
+        .expect("error while parsing");
+        let model_count = Solver::new(PseudoBooleanFormula::new(&chained))
+            .solve()
+            .model_count;
+        assert_eq!(model_count, brute_force_count(&chained));
+        assert_eq!(
+            model_count,
+            Solver::new(PseudoBooleanFormula::new(&spelled_out))
+                .solve()
+                .model_count
+        );
+    }
+
+    #[test]
+    fn test_power_of_two() {
+        for exponent in [0, 1, 2, 17, 31] {
+            assert_eq!(power_of_two(exponent), BigUint::from(2_u32.pow(exponent)));
+        }
+        for exponent in [32, 64, 100, 1000, 65537] {
+            assert_eq!(power_of_two(exponent), BigUint::from(2_u32).pow(exponent));
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::solving::ddnnf::DDNNFPrinter;
-    use p2d_opb::parse;
-    use serial_test::serial;
-    use std::fs;
-    use std::str::FromStr;
+    #[test]
+    fn test_constraints_of() {
+        let opb_file = parse(
+            "#variable= 4 #constraint= 4\nx1 + x2 >= 1;\n2 x3 + x4 >= 1;\nx1 + ~x3 = 1;\nx2 + x4 >= 1;",
+        )
+        .expect("error while parsing");
+        let mut formula = PseudoBooleanFormula::new(&opb_file);
+        let x1 = *formula.name_map.get_by_left("x1").unwrap();
+        let x3 = *formula.name_map.get_by_left("x3").unwrap();
+        // the equation with = is split into two constraints
+        assert_eq!(formula.constraints_of(x1).count(), 3);
+        assert_eq!(formula.constraints_of(x3).count(), 3);
+        assert!(formula
+            .constraints_of(x1)
+            .all(|constraint| constraint.literals.contains_key(&(x1 as usize))));
+        assert_eq!(formula.constraints_of(100).count(), 0);
+
+        for constraint in formula.constraints_of_mut(x3) {
+            constraint.propagations += 1;
+        }
+        let propagations: Vec<u32> = formula.constraints.iter().map(|c| c.propagations).collect();
+        assert_eq!(propagations.iter().sum::<u32>(), 3);
+        assert!(formula
+            .constraints_of(x3)
+            .all(|constraint| constraint.propagations == 1));
+    }
 
     #[test]
     #[serial]
-    fn test_ex_1() {
+    fn test_variable_order() {
         let opb_file =
             parse("#variable= 5 #constraint= 2\nx1 + x2 >= 0;\n3 x2 + x3 + x4 + x5 >= 3;")
                 .expect("error while parsing");
         let formula = PseudoBooleanFormula::new(&opb_file);
+        for order in [vec![0, 1, 2, 3, 4], vec![4, 3, 2, 1, 0], vec![3]] {
+            let mut solver = Solver::new(formula.clone());
+            solver.set_variable_order(order);
+            assert_eq!(solver.solve().model_count, BigUint::from(18_u32));
+        }
+
+        // x1 only occurs in a satisfied constraint, so x4 is the first eligible variable
         let mut solver = Solver::new(formula);
-        let model_count = solver.solve().model_count;
-        assert_eq!(model_count, BigUint::from(18 as u32));
+        solver.set_variable_order(vec![0, 3, 2]);
+        solver.next_variables = vec![2, 1];
+        assert_eq!(solver.get_next_variable(), Some(3));
+        // variables that are not in the order are left to the cut and the heuristic
+        solver.set_variable_order(vec![0]);
+        assert_eq!(solver.get_next_variable(), Some(1));
+        solver.set_variable_order(Vec::new());
+        assert_eq!(solver.get_next_variable(), Some(1));
+
+        // a variable added after the order was set has no position in it
+        let cnf_file =
+            p2d_opb::parse_cnf("p cnf 3 2\n1 2 0\n2 3 0\n").expect("error while parsing");
+        let mut opb_file = p2d_opb::parse_cnf("p cnf 2 1\n1 2 0\n").expect("error while parsing");
+        opb_file.name_map = cnf_file.name_map.clone();
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_variable_order(vec![1, 0]);
+        solver
+            .add_constraint(&cnf_file.equations[1])
+            .expect("error while adding the constraint");
+        assert_eq!(solver.solve().model_count, BigUint::from(5u32));
     }
 
     #[test]
     #[serial]
-    fn test_ex_2() {
+    fn test_hash_consing() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let formula = PseudoBooleanFormula::new(&opb_file);
+        let mut solver = Solver::new(formula.clone());
+        solver.set_hash_consing(false);
+        let without = solver.solve();
+        let with = Solver::new(formula).solve();
+        let without_ddnnf = without.ddnnf.as_ref().unwrap();
+        let with_ddnnf = with.ddnnf.as_ref().unwrap();
+        assert!(with_ddnnf.node_count() < without_ddnnf.node_count());
+        assert_eq!(with.model_count, without.model_count);
+        assert_eq!(with_ddnnf.model_count(), with.model_count);
+        assert_eq!(with_ddnnf.validate(), Ok(()));
+
+        // the literal leaves of the symmetric branches of an exactly-one constraint are shared
         let opb_file =
-            parse("#variable= 5 #constraint= 2\nx1 + x2 >= 1;\n3 x2 + x3 + x4 + x5 >= 3;")
-                .expect("error while parsing");
+            parse("#variable= 3 #constraint= 1\nx1 + x2 + x3 = 1;").expect("error while parsing");
         let formula = PseudoBooleanFormula::new(&opb_file);
-        let mut solver = Solver::new(formula);
-        let model_count = solver.solve().model_count;
-        assert_eq!(model_count, BigUint::from(17 as u32));
+        let mut solver = Solver::new(formula.clone());
+        solver.set_hash_consing(false);
+        let without = solver.solve().ddnnf.unwrap();
+        let with = Solver::new(formula).solve().ddnnf.unwrap();
+        assert!(with.node_count() < without.node_count());
+        assert_eq!(with.model_count(), BigUint::from(3_u32));
     }
 
     #[test]
     #[serial]
-    fn test_ex_3() {
-        let file_content =
-            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
-        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+    fn test_model_count() {
+        let opb_file =
+            parse("#variable= 3 #constraint= 1\n2 x + y + z >= 2;\n").expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert_eq!(solver.model_count(), BigUint::from(5_u32));
+        // the d-DNNF is still built by solve
+        assert!(solver.solve().ddnnf.is_some());
+    }
+
+    #[test]
+    #[serial]
+    fn test_unsat_core() {
+        // x1 has to be true for the first and false for the second constraint
+        let opb_file =
+            parse("#variable= 3 #constraint= 3\nx3 + x2 >= 1;\nx1 + x2 >= 2;\n~x1 >= 1;")
+                .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_unsat_core(true);
+        let result = solver.solve();
+        assert_eq!(result.model_count, BigUint::zero());
+        assert_eq!(result.unsat_core, Some(vec![1, 2]));
+
+        // the indexes refer to the equations, also if an earlier one is dropped as a tautology or
+        // an equation with = becomes two constraints
+        let opb_file = parse("#variable= 2 #constraint= 3\nx2 >= 0;\nx1 >= 1;\n~x1 >= 1;")
+            .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_unsat_core(true);
+        assert_eq!(solver.solve().unsat_core, Some(vec![1, 2]));
+        let opb_file = parse("#variable= 2 #constraint= 3\nx1 + x2 = 1;\nx2 >= 1;\nx1 >= 1;")
+            .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_unsat_core(true);
+        assert_eq!(solver.solve().unsat_core, Some(vec![0, 1, 2]));
+
+        // a constraint that no assignment satisfies is not part of the formula, but of the core
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 >= 3;").expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_unsat_core(true);
+        let result = solver.solve();
+        assert_eq!(result.model_count, BigUint::zero());
+        assert_eq!(result.unsat_core, Some(vec![0]));
+
+        // the pigeonhole principle for three pigeons and two holes is only refuted by the search
+        let equations = [
+            "x1 + x2 >= 1;",
+            "x3 + x4 >= 1;",
+            "x5 + x6 >= 1;",
+            "~x1 + ~x3 + ~x5 >= 2;",
+            "~x2 + ~x4 + ~x6 >= 2;",
+            "x7 + x8 >= 1;",
+        ];
+        let opb_file = parse(&format!(
+            "#variable= 8 #constraint= 6\n{}",
+            equations.join("\n")
+        ))
+        .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_unsat_core(true);
+        let unsat_core = solver
+            .solve()
+            .unsat_core
+            .expect("the formula has no models");
+        assert!(!unsat_core.contains(&5));
+        // the constraints of the core have no model on their own
+        let core_equations: Vec<&str> = unsat_core.iter().map(|i| equations[*i]).collect();
+        let opb_file = parse(&format!(
+            "#variable= 8 #constraint= {}\n{}",
+            core_equations.len(),
+            core_equations.join("\n")
+        ))
+        .expect("error while parsing");
+        assert_eq!(
+            Solver::new(PseudoBooleanFormula::new(&opb_file)).model_count(),
+            BigUint::zero()
+        );
+
+        // no core is reported for a satisfiable formula or without recording
+        let opb_file =
+            parse("#variable= 2 #constraint= 1\nx1 + x2 >= 1;").expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_unsat_core(true);
+        assert_eq!(solver.solve().unsat_core, None);
+        let opb_file =
+            parse("#variable= 1 #constraint= 2\nx1 >= 1;\n~x1 >= 1;").expect("error while parsing");
+        assert_eq!(
+            Solver::new(PseudoBooleanFormula::new(&opb_file))
+                .solve()
+                .unsat_core,
+            None
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_constraint_variables() {
+        let opb_file = parse("#variable= 4 #constraint= 1\nx4 + 2 ~x2 + 3 x1 >= 2;")
+            .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let x1 = *solver
+            .pseudo_boolean_formula
+            .name_map
+            .get_by_left("x1")
+            .unwrap();
+        let x2 = *solver
+            .pseudo_boolean_formula
+            .name_map
+            .get_by_left("x2")
+            .unwrap();
+        let x4 = *solver
+            .pseudo_boolean_formula
+            .name_map
+            .get_by_left("x4")
+            .unwrap();
+        let constraint = &solver.pseudo_boolean_formula.constraints[0];
+        assert_eq!(constraint.variables().collect::<Vec<_>>(), vec![x4, x2, x1]);
+        assert_eq!(
+            constraint.unassigned_variables().collect::<Vec<_>>(),
+            vec![x4, x2, x1]
+        );
+
+        assert_eq!(solver.propagate(x4, true, FirstDecision), None);
+        let constraint = &solver.pseudo_boolean_formula.constraints[0];
+        assert_eq!(constraint.variables().collect::<Vec<_>>(), vec![x4, x2, x1]);
+        assert_eq!(
+            constraint.unassigned_variables().collect::<Vec<_>>(),
+            vec![x2, x1]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_declared_variables() {
+        // the header declares four variables, but only a and b appear
+        let opb_file = parse("#variable= 4 #constraint= 2\nb + a >= 1;\nb + ~b >= 1;")
+            .expect("error while parsing");
         let formula = PseudoBooleanFormula::new(&opb_file);
+        assert_eq!(formula.number_variables, 4);
+        // the indexes follow the order in which the names appear
+        assert_eq!(formula.index_of("b"), Some(0));
+        assert_eq!(formula.index_of("a"), Some(1));
+        assert_eq!(formula.index_of("c"), None);
+        assert_eq!(formula.name_of(1), Some("a"));
+        assert_eq!(formula.name_of(3), None);
+
         let mut solver = Solver::new(formula);
         let result = solver.solve();
-        //let mut printer = DDNNFPrinter{true_sink_id: None, false_sink_id: None, ddnnf: result.ddnnf, current_node_id: 0, id_map: HashMap::new(), edge_counter: 0, node_counter: 0};
-        //let ddnnf = printer.print();
-        //let ddnnf = result.ddnnf.get_d4_string_representation();
-        //fs::write("berkely_p2d.d4", ddnnf);
-        let model_count = result.model_count;
-        println!("{:#?}", solver.statistics);
-        assert_eq!(model_count, BigUint::from_str(&"63552545718785").unwrap());
+        assert_eq!(result.model_count, BigUint::from(3_u32 * 4));
+        assert_eq!(result.ddnnf.unwrap().model_count(), result.model_count);
+
+        // a tautology over the declared variables has all of their models
+        let opb_file =
+            parse("#variable= 3 #constraint= 1\nx1 + ~x1 >= 1;").expect("error while parsing");
+        assert_eq!(
+            Solver::new(PseudoBooleanFormula::new(&opb_file)).model_count(),
+            BigUint::from(8_u32)
+        );
+
+        // a header must not make the solver allocate billions of variables
+        let opb_file =
+            parse("#variable= 4000000000 #constraint= 1\nx1 >= 1;").expect("error while parsing");
+        assert_eq!(
+            PseudoBooleanFormula::try_new(&opb_file).err(),
+            Some(
+                "the header declares 4000000000 variables, but only 1 are used, at most 1048576 \
+                 unused variables are accepted (see --max-unused-variables)"
+                    .to_string()
+            )
+        );
+
+        // the limit of unused variables can be chosen
+        let opb_file = parse("#variable= 5 #constraint= 1\nx1 >= 1;").expect("error while parsing");
+        assert!(PseudoBooleanFormula::try_new_with_max_unused_variables(&opb_file, 3).is_err());
+        let formula = PseudoBooleanFormula::try_new_with_max_unused_variables(&opb_file, 4)
+            .expect("the header declares 4 unused variables");
+        assert_eq!(Solver::new(formula).model_count(), BigUint::from(16_u32));
     }
 
     #[test]
     #[serial]
-    fn test_ex_4() {
-        let file_content =
-            fs::read_to_string("./test_models/financialservices01.opb").expect("cannot read file");
-        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+    fn test_objective_heuristic() {
+        let opb_file = parse(
+            "#variable= 5 #constraint= 2\nmin: 5 x5 -3 ~x4 + x1 + x5;\nx1 + x2 >= 1;\n3 x2 + x3 + x4 + x5 >= 3;",
+        )
+        .expect("error while parsing");
         let formula = PseudoBooleanFormula::new(&opb_file);
+        let x5 = *formula.name_map.get_by_left("x5").unwrap();
+        let x4 = *formula.name_map.get_by_left("x4").unwrap();
         let mut solver = Solver::new(formula);
-        let model_count = solver.solve().model_count;
-        println!("{:#?}", solver.statistics);
-        assert_eq!(model_count, BigUint::from_str("97451212554676").unwrap());
+        solver.set_branching_heuristic(BranchingHeuristic::Objective);
+        assert_eq!(solver.objective_scores[x5 as usize], 6.0);
+        assert_eq!(solver.objective_scores[x4 as usize], 3.0);
+        let best = (0..5)
+            .max_by(|a, b| {
+                solver
+                    .branching_score(*a)
+                    .total_cmp(&solver.branching_score(*b))
+            })
+            .unwrap();
+        assert_eq!(best, x5);
+        assert_eq!(solver.solve().model_count, brute_force_count(&opb_file));
+
+        // without an objective the heuristic falls back to VSIDS
+        let opb_file =
+            parse("#variable= 5 #constraint= 2\nx1 + x2 >= 1;\n3 x2 + x3 + x4 + x5 >= 3;")
+                .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_branching_heuristic(BranchingHeuristic::Objective);
+        assert!(solver.objective_scores.is_empty());
+        assert_eq!(solver.branching_score(0), solver.vsids_scores[0]);
+        assert_eq!(solver.solve().model_count, BigUint::from(17_u32));
     }
 
     #[test]
     #[serial]
-    fn test_ex_5() {
+    fn test_current_assignment() {
         let opb_file =
-            parse("#variable= 3 #constraint= 1\n2 x + y + z >= 2;\n").expect("error while parsing");
+            parse("#variable= 3 #constraint= 3\nx1 >= 1;\n~x1 + ~x2 >= 1;\nx2 + x3 >= 1;")
+                .expect("error while parsing");
         let formula = PseudoBooleanFormula::new(&opb_file);
+        let x1 = *formula.name_map.get_by_left("x1").unwrap() as usize;
+        let x2 = *formula.name_map.get_by_left("x2").unwrap() as usize;
+        let x3 = *formula.name_map.get_by_left("x3").unwrap() as usize;
         let mut solver = Solver::new(formula);
-        let result = solver.solve();
-        //let mut printer = DDNNFPrinter{true_sink_id: None, false_sink_id: None, ddnnf: result.ddnnf, current_node_id: 0, id_map: HashMap::new()};
-        //let ddnnf = printer.print();
-        //let ddnnf = result.ddnnf.get_d4_string_representation();
-        //fs::write("test.d4", ddnnf);
-        let model_count = result.model_count;
-        println!("{:#?}", solver.statistics);
-        assert_eq!(model_count, BigUint::from(5 as u32));
+        assert_eq!(solver.current_assignment(), vec![None; 3]);
+        assert_eq!(solver.solve().model_count, BigUint::one());
+        let assignment = solver.current_assignment();
+        assert_eq!(assignment[x1], Some(true));
+        assert_eq!(assignment[x2], Some(false));
+        assert_eq!(assignment[x3], Some(true));
+
+        // only the implied literals remain, the decisions are undone
+        let opb_file = parse("#variable= 3 #constraint= 2\nx1 >= 1;\nx2 + x3 >= 1;")
+            .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert_eq!(solver.solve().model_count, BigUint::from(3_u32));
+        assert_eq!(solver.current_assignment(), vec![Some(true), None, None]);
+
+        let opb_file =
+            parse("#variable= 1 #constraint= 2\nx1 >= 1;\n~x1 >= 1;").expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert!(solver.solve().model_count.is_zero());
+        assert_eq!(solver.current_assignment(), vec![None]);
+
+        // a variable added with a constraint has no value until the next search
+        let cnf_file = p2d_opb::parse_cnf("p cnf 3 2\n1 0\n-1 3 0\n").expect("error while parsing");
+        let mut opb_file = p2d_opb::parse_cnf("p cnf 1 1\n1 0\n").expect("error while parsing");
+        opb_file.name_map = cnf_file.name_map.clone();
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert_eq!(solver.solve().model_count, BigUint::one());
+        solver
+            .add_constraint(&cnf_file.equations[1])
+            .expect("error while adding the constraint");
+        assert_eq!(solver.current_assignment(), vec![Some(true), None, None]);
+        assert_eq!(solver.solve().model_count, BigUint::from(2_u32));
+        assert_eq!(
+            solver.current_assignment(),
+            vec![Some(true), None, Some(true)]
+        );
     }
 
     #[test]
     #[serial]
-    fn test_ex_6() {
-        let file_content =
-            fs::read_to_string("./test_models/automotive2_4.opb").expect("cannot read file");
-        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+    fn test_conflict_index() {
+        let opb_file = parse("#variable= 4 #constraint= 2\nx3 + x4 >= 1;\nx1 + x2 >= 2;")
+            .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let x1 = solver.pseudo_boolean_formula.index_of("x1").unwrap();
+        let x3 = solver.pseudo_boolean_formula.index_of("x3").unwrap();
+        let index = solver.pseudo_boolean_formula.constraints[1].index;
+        assert_eq!(index, NormalConstraintIndex(1));
+        assert!(matches!(
+            solver.pseudo_boolean_formula.constraints[1].clone().propagate(
+                Literal {
+                    index: x1,
+                    positive: false,
+                    factor: 0,
+                },
+                FirstDecision,
+                1,
+            ),
+            Conflict(conflict_index) if conflict_index == index
+        ));
+
+        assert_eq!(solver.propagate(x3, false, FirstDecision), None);
+        assert_eq!(solver.propagate(x1, false, FirstDecision), Some(index));
+    }
+
+    #[test]
+    #[serial]
+    fn test_auxiliary_variables() {
+        let opb_file =
+            parse("#variable= 3 #constraint= 1\nx1 + x2 + x3 >= 2;").expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let x3 = solver.pseudo_boolean_formula.index_of("x3").unwrap();
+        assert_eq!(solver.solve().model_count, BigUint::from(4_u32));
+        // x1 = x2 = 1 is counted once, whatever the value of x3
+        solver.set_auxiliary(&BTreeSet::from([x3]));
+        assert_eq!(solver.solve().model_count, BigUint::from(3_u32));
+        assert_eq!(
+            solver
+                .solve_projected(&BTreeSet::from([0, 1, 2]))
+                .model_count,
+            BigUint::from(3_u32)
+        );
+        solver.set_auxiliary(&BTreeSet::new());
+        assert_eq!(solver.solve().model_count, BigUint::from(4_u32));
+
+        // a free auxiliary variable does not double the count
+        let opb_file =
+            parse("#variable= 3 #constraint= 1\nx1 + x2 >= 1;").expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert_eq!(solver.solve().model_count, BigUint::from(6_u32));
+        solver.set_auxiliary(&BTreeSet::from([2]));
+        assert_eq!(solver.solve().model_count, BigUint::from(3_u32));
+    }
+
+    #[test]
+    #[serial]
+    fn test_zero_factor() {
+        for input in [
+            "#variable= 2 #constraint= 1\n0 x1 + x2 >= 1;",
+            "#variable= 2 #constraint= 1\nx1 + x2 -1 x1 >= 1;",
+            "#variable= 2 #constraint= 1\nx2 + 0 x1 >= 1;",
+        ] {
+            let opb_file = parse(input).expect("error while parsing");
+            let formula = PseudoBooleanFormula::new(&opb_file);
+            let x1 = formula.index_of("x1").unwrap();
+            let x2 = formula.index_of("x2").unwrap();
+            assert_eq!(formula.number_variables, 2);
+            assert_eq!(formula.constraints_of(x1).count(), 0);
+            assert_eq!(formula.constraints_of(x2).count(), 1);
+            assert_eq!(formula.constraints[0].literals.len(), 1);
+            assert_eq!(
+                Solver::new(formula).solve().model_count,
+                BigUint::from(2_u32)
+            );
+        }
+
+        // the variable of a zero factor is still counted if it appears nowhere else
+        let opb_file =
+            parse("#variable= 1 #constraint= 1\n0 x1 + x2 >= 1;").expect("error while parsing");
         let formula = PseudoBooleanFormula::new(&opb_file);
-        let mut solver = Solver::new(formula);
-        let result = solver.solve();
-        //let mut printer = DDNNFPrinter{true_sink_id: None, false_sink_id: None, ddnnf: result.ddnnf, current_node_id: 0, id_map: HashMap::new()};
-        //let ddnnf = printer.print();
-        //let ddnnf = result.ddnnf.get_d4_string_representation();
-        //fs::write("automotive2_p2d.d4", ddnnf);
-        let model_count = result.model_count;
-        println!("{:#?}", solver.statistics);
-        assert_eq!(model_count, BigUint::from_str("16505272636520770608049807336686263419262278171474896528902674080188226535986513386206222739154199990312304316432375708419908334951120777840761446056501033491673756322502123336090943486436039243372030766943458602037261070847529674534356018156008670682187009867114669183165589812678347677020009178324343716516097209109845184348679968274326123049227527790019157116786715333025963056661497445641173800199765222163167371496529076598275345593840432679060593082091562556148743367163011059914376453848874833624216454940443543476903147239713725910883379897186772787280371367887760273478656423910102759489682512679566900002943975655597096674268679680101882972677272515371297444691753104874195657464993976495326679318657622295700861777088118982149971100416087768578981508055766733740078413795875538473667538095783126142950285621270589214044781390019682483886583359849938540211221775670172765581722321182214883760887169041797021188330713322356432125673511102447057280896884295376155649470685335338495258057322025865111781429202794739966258303407257483764514048109066413495739887120721093956731137104071984616616093530304438776638066291197761951034921410607293591331155786344517409313802138987056145557947322022252231548896287559556403966183750725000574198535237943080891660398515976002019199247649442832823641555125736303883310186456855445612857146873733447167431344738817867253190162116602107467483579427839512688474377395370679756390400000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000").unwrap());
+        assert_eq!(formula.number_variables, 2);
+        assert_eq!(
+            Solver::new(formula).solve().model_count,
+            BigUint::from(2_u32)
+        );
     }
 
     #[test]
     #[serial]
-    fn test_ex_7() {
+    fn test_vsids_decay() {
         let file_content =
-            fs::read_to_string("./test_models/automotive01.opb").expect("cannot read file");
+            fs::read_to_string("./test_models/financialservices01.opb").expect("cannot read file");
         let opb_file = parse(file_content.as_str()).expect("error while parsing");
-        let formula = PseudoBooleanFormula::new(&opb_file);
-        let mut solver = Solver::new(formula);
-        let result = solver.solve();
-        //let mut printer = DDNNFPrinter{true_sink_id: None, false_sink_id: None, ddnnf: result.ddnnf, current_node_id: 0, id_map: HashMap::new()};
-        //let ddnnf = printer.print();
-        //let ddnnf = result.ddnnf.get_d4_string_representation();
-        //fs::write("automotive2_p2d.d4", ddnnf);
-        let model_count = result.model_count;
-        println!("{:#?}", solver.statistics);
-        assert_eq!(model_count, BigUint::from_str("54337953889526644797436357304783500234473556203012469981705794070419609376066883019863858681556047971579366711252721976681982553481954710208375451836305175948768348959659511355551303323044387225600000000000000000000000").unwrap());
+        for (decay, bump) in [(1.0, 1.0), (0.95, 1.0), (0.8, 2.0), (0.5, 0.5)] {
+            let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+            solver.set_vsids_decay(decay);
+            solver.set_vsids_bump(bump);
+            assert_eq!(
+                solver.solve().model_count,
+                BigUint::from_str("97451212554676").unwrap()
+            );
+        }
     }
 
     #[test]
     #[serial]
-    fn test_ex_8() {
+    fn test_stats_only() {
         let file_content =
-            fs::read_to_string("./test_models/busybox.opb").expect("cannot read file");
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
         let opb_file = parse(file_content.as_str()).expect("error while parsing");
-        let formula = PseudoBooleanFormula::new(&opb_file);
-        let mut solver = Solver::new(formula);
+        let mut counting_solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        counting_solver.set_count_only(true);
+        let model_count = counting_solver.solve().model_count;
+
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        solver.set_stats_only(true);
         let result = solver.solve();
-        //let mut printer = DDNNFPrinter{true_sink_id: None, false_sink_id: None, ddnnf: result.ddnnf, current_node_id: 0, id_map: HashMap::new()};
-        //let ddnnf = printer.print();
-        //let ddnnf = result.ddnnf.get_d4_string_representation();
-        //fs::write("automotive2_p2d.d4", ddnnf);
-        let model_count = result.model_count;
-        println!("{:#?}", solver.statistics);
-        assert_eq!(model_count, BigUint::from_str("3599239755983329331332100508562451780508192148493160801718199944973008026807919208513108710328389951098075842967611059200000000000000000000000").unwrap());
+        assert!(result.model_count.is_zero());
+        assert!(result.ddnnf.is_none());
+        // the search is the same as when counting
+        assert!(solver.statistics.decisions() > 0);
+        assert_eq!(
+            solver.statistics.decisions(),
+            counting_solver.statistics.decisions()
+        );
+        assert_eq!(
+            solver.statistics.cache_hits(),
+            counting_solver.statistics.cache_hits()
+        );
+        assert_eq!(
+            solver.statistics.cache_entries(),
+            counting_solver.statistics.cache_entries()
+        );
+
+        solver.set_stats_only(false);
+        assert_eq!(solver.solve().model_count, model_count);
     }
 
     #[test]
     #[serial]
-    fn test_ex_9() {
-        let opb_file =
-            parse("#variable= 2 #constraint= 1\nx1 + x2 = 1;").expect("error while parsing");
-        let formula = PseudoBooleanFormula::new(&opb_file);
-        let mut solver = Solver::new(formula);
-        let model_count = solver.solve().model_count;
-        assert_eq!(model_count, BigUint::from(2 as u32));
+    fn test_search_statistics() {
+        for (path, has_conflicts) in [
+            // berkeleydb is solved by propagation alone, without a single conflict
+            ("./test_models/berkeleydb.opb", false),
+            ("./test_models/financialservices01.opb", true),
+        ] {
+            let file_content = fs::read_to_string(path).expect("cannot read file");
+            let opb_file = parse(file_content.as_str()).expect("error while parsing");
+            let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+            solver.solve();
+            let statistics = &solver.statistics;
+            assert!(statistics.decisions() > 0);
+            assert_eq!(statistics.conflicts() > 0, has_conflicts);
+            assert!(statistics.propagations() > 0);
+            assert!(statistics.backtracks() > 0);
+            // every conflict ends one of the two values of a decision
+            assert!(statistics.conflicts() <= 2 * statistics.decisions());
+            assert!(statistics.backtracks() <= statistics.decisions());
+        }
     }
 
     #[test]
     #[serial]
-    fn test_ex_10() {
+    fn test_load_hints() {
+        // the formula of test_ex_1
         let opb_file =
-            parse("#variable= 2 #constraint= 1\nx1 + x2 < 2;").expect("error while parsing");
-        let formula = PseudoBooleanFormula::new(&opb_file);
-        let mut solver = Solver::new(formula);
-        let model_count = solver.solve().model_count;
-        assert_eq!(model_count, BigUint::from(3 as u32));
+            parse("#variable= 5 #constraint= 2\nx1 + x2 >= 0;\n3 x2 + x3 + x4 + x5 >= 3;")
+                .expect("error while parsing");
+        let hints_file = crate::temp_file("hints.txt");
+        fs::write(
+            &hints_file,
+            "* order and phases\nx5 0\n\nx2 1\nx9 1\nx3 0\n",
+        )
+        .unwrap();
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let warnings = solver.load_hints(&hints_file).expect("cannot load hints");
+        assert_eq!(warnings, vec!["unknown variable x9 in line 5 is ignored"]);
+        let x5 = solver.pseudo_boolean_formula.index_of("x5").unwrap();
+        let x2 = solver.pseudo_boolean_formula.index_of("x2").unwrap();
+        assert_eq!(solver.saved_phases[x5 as usize], Some(false));
+        assert_eq!(solver.variable_order[1], x2);
+        assert_eq!(solver.solve().model_count, BigUint::from(18_u32));
+
+        fs::write(&hints_file, "x5 0\nx2 yes\n").unwrap();
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert_eq!(
+            solver.load_hints(&hints_file),
+            Err("invalid hint in line 2: x2 yes, expected a variable and 1 or 0".to_string())
+        );
+        assert!(solver.variable_order.is_empty());
+        fs::remove_file(&hints_file).unwrap();
+        assert!(solver.load_hints(&hints_file).is_err());
     }
 
     #[test]
     #[serial]
-    fn test_ex_11() {
+    fn test_constraint_states() {
         let opb_file =
-            parse("#variable= 2 #constraint= 1\nx1 + x2 > 1;").expect("error while parsing");
+            parse("#variable= 3 #constraint= 2\n2 x1 + x2 + x3 >= 2;\nx1 + x2 + x3 != 1;")
+                .expect("error while parsing");
         let formula = PseudoBooleanFormula::new(&opb_file);
-        let mut solver = Solver::new(formula);
-        let model_count = solver.solve().model_count;
-        assert_eq!(model_count, BigUint::from(1 as u32));
+        let x1 = formula.index_of("x1").unwrap();
+        let x2 = formula.index_of("x2").unwrap();
+        let x3 = formula.index_of("x3").unwrap();
+        let state = |constraint_index: usize, literals: &[(u32, bool)]| {
+            let mut constraint = formula.constraints[constraint_index].clone();
+            for (index, positive) in literals {
+                constraint.propagate(
+                    Literal {
+                        index: *index,
+                        positive: *positive,
+                        factor: 0,
+                    },
+                    FirstDecision,
+                    1,
+                );
+            }
+            (constraint.is_satisfied(), constraint.is_violated())
+        };
+        let (satisfied, violated, open) = ((true, false), (false, true), (false, false));
+
+        assert_eq!(formula.constraints[0].constraint_type, GreaterEqual);
+        assert_eq!(state(0, &[]), open);
+        assert_eq!(state(0, &[(x2, true)]), open);
+        assert_eq!(state(0, &[(x1, true)]), satisfied);
+        assert_eq!(state(0, &[(x2, true), (x3, true)]), satisfied);
+        assert_eq!(state(0, &[(x1, false), (x2, false)]), violated);
+
+        // a constraint with != is only decided when all of its literals are assigned
+        assert_eq!(formula.constraints[1].constraint_type, NotEqual);
+        assert_eq!(state(1, &[]), open);
+        assert_eq!(state(1, &[(x1, true), (x2, true)]), open);
+        assert_eq!(state(1, &[(x1, true), (x2, true), (x3, false)]), satisfied);
+        assert_eq!(
+            state(1, &[(x1, false), (x2, false), (x3, false)]),
+            satisfied
+        );
+        assert_eq!(state(1, &[(x1, true), (x2, false), (x3, false)]), violated);
     }
 
     #[test]
     #[serial]
-    fn test_ex_12() {
-        let opb_file =
-            parse("#variable= 2 #constraint= 1\nx1 + x2 != 1;").expect("error while parsing");
+    fn test_unit_literals() {
+        let opb_file = parse("#variable= 3 #constraint= 3\n2 x >= 2;\nx + y + z >= 1;\ny != 1;")
+            .expect("error while parsing");
         let formula = PseudoBooleanFormula::new(&opb_file);
+        let x = formula.index_of("x").unwrap();
+        let y = formula.index_of("y").unwrap();
+        assert_eq!(
+            formula.unit_literals(),
+            vec![
+                (x, true, NormalConstraintIndex(0)),
+                (y, false, NormalConstraintIndex(2))
+            ]
+        );
+
+        // only z is free
         let mut solver = Solver::new(formula);
-        let model_count = solver.solve().model_count;
-        assert_eq!(model_count, BigUint::from(2 as u32));
+        assert_eq!(solver.solve().model_count, BigUint::from(2u32));
+        assert_eq!(
+            solver.current_assignment(),
+            vec![Some(true), Some(false), None]
+        );
+        solver.reset();
+        assert_eq!(solver.solve().model_count, BigUint::from(2u32));
+
+        let opb_file =
+            parse("#variable= 1 #constraint= 2\n2 x >= 2;\n-x >= 0;").expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert!(solver.solve().model_count.is_zero());
     }
 
     #[test]
     #[serial]
-    fn test_ex_13() {
-        let opb_file = parse("#variable= 1 #constraint= 1\nx1 >= 0;").expect("error while parsing");
+    fn test_implied_literals() {
+        let opb_file = parse("#variable= 4 #constraint= 1\n3 x2 + x3 + x4 + x5 >= 3;")
+            .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        // x3, x4 and x5 together reach the degree, so no literal is implied
+        assert_eq!(solver.implied_literals(), (true, Vec::new()));
+
+        let opb_file = parse("#variable= 4 #constraint= 2\n3 x2 + x3 + x4 + x5 >= 3;\n-1 x3 >= 0;")
+            .expect("error while parsing");
         let formula = PseudoBooleanFormula::new(&opb_file);
+        let x2 = formula.index_of("x2").unwrap();
+        let x3 = formula.index_of("x3").unwrap();
         let mut solver = Solver::new(formula);
-        let result = solver.solve();
-        let mut printer = DDNNFPrinter {
-            true_sink_id: None,
-            false_sink_id: None,
-            ddnnf: result.ddnnf,
-            current_node_id: 0,
-            id_map: HashMap::new(),
-            edge_counter: 0,
-            node_counter: 0,
-        };
-        let ddnnf = printer.print();
-        assert_eq!(ddnnf, "t 1 0\n");
+        let expected = (true, vec![(x3, false), (x2, true)]);
+        assert_eq!(solver.implied_literals(), expected);
+        // the solver is reset, so the literals are reported again and the count is unchanged
+        assert_eq!(solver.implied_literals(), expected);
+        assert_eq!(solver.solve().model_count, BigUint::from(4u32));
+
+        let opb_file = parse(
+            "#variable= 4 #constraint= 3\n3 x2 + x3 + x4 + x5 >= 3;\n-1 x3 >= 0;\n-1 x2 >= 0;",
+        )
+        .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert!(!solver.implied_literals().0);
+        assert!(solver.solve().model_count.is_zero());
     }
 
     #[test]
     #[serial]
-    fn test_ex_14() {
-        let opb_file = parse("#variable= 1 #constraint= 1\nx1 > 1;").expect("error while parsing");
+    fn test_connected_components() {
+        let opb_file =
+            parse("#variable= 6 #constraint= 3\nx1 + x2 >= 1;\nx2 + 2 x3 >= 2;\nx4 + ~x5 >= 1;")
+                .expect("error while parsing");
         let formula = PseudoBooleanFormula::new(&opb_file);
-        let mut solver = Solver::new(formula);
-        let result = solver.solve();
-        let mut printer = DDNNFPrinter {
-            true_sink_id: None,
-            false_sink_id: None,
-            ddnnf: result.ddnnf,
-            current_node_id: 0,
-            id_map: HashMap::new(),
-            edge_counter: 0,
-            node_counter: 0,
-        };
-        let ddnnf = printer.print();
-        assert_eq!(ddnnf, "o 1 0\nf 2 0\n1 2 1 0\n");
+        let index = |name: &str| formula.index_of(name).unwrap();
+        let mut first = vec![index("x1"), index("x2"), index("x3")];
+        let mut second = vec![index("x4"), index("x5")];
+        first.sort_unstable();
+        second.sort_unstable();
+
+        // the sixth variable of the header is in no constraint
+        assert_eq!(formula.connected_components(), vec![first, second, vec![5]]);
+        assert!(formula.are_connected(index("x1"), index("x3")));
+        assert!(formula.are_connected(index("x5"), index("x4")));
+        assert!(formula.are_connected(5, 5));
+        assert!(!formula.are_connected(index("x1"), index("x4")));
+        assert!(!formula.are_connected(index("x3"), 5));
+        assert!(!formula.are_connected(index("x1"), 6));
     }
 
     #[test]
     #[serial]
-    fn test_ex_15() {
+    fn test_formula_display() {
+        use p2d_opb::{EquationKind, Summand};
+
         let opb_file =
-            parse("#variable= 2 #constraint= 1\nx1 + x2 >= 1;").expect("error while parsing");
+            parse("#variable= 3 #constraint= 1\nx1 + x2 = 1;").expect("error while parsing");
+        let mut formula = PseudoBooleanFormula::new(&opb_file);
+        // the equation is split into >= and <=, the latter is negated
+        assert_eq!(formula.num_constraints(), 2);
+        assert_eq!(formula.num_variables(), 3);
+        assert_eq!(
+            formula.to_string(),
+            "* #variable= 3 #constraint= 2\n+1 x1 +1 x2 >= 1;\n+1 ~x1 +1 ~x2 >= 1;\n"
+        );
+
+        // the factor is saturated to the degree, the third variable has no name
+        formula
+            .add_constraint(&Equation {
+                lhs: vec![
+                    Summand {
+                        variable_index: 0,
+                        factor: 3,
+                        positive: true,
+                    },
+                    Summand {
+                        variable_index: 2,
+                        factor: 1,
+                        positive: true,
+                    },
+                ],
+                rhs: 2,
+                kind: EquationKind::Ge,
+            })
+            .unwrap();
+        assert_eq!(
+            formula.to_string().lines().last(),
+            Some("+2 x1 +1 _x3 >= 2;")
+        );
+
+        // the name of the unnamed third variable is already taken
+        let opb_file =
+            parse("#variable= 3 #constraint= 1\n_x3 + x1 >= 1;").expect("error while parsing");
+        let mut formula = PseudoBooleanFormula::new(&opb_file);
+        formula
+            .add_constraint(&Equation {
+                lhs: vec![Summand {
+                    variable_index: 2,
+                    factor: 1,
+                    positive: true,
+                }],
+                rhs: 1,
+                kind: EquationKind::Ge,
+            })
+            .unwrap();
+        assert_eq!(
+            formula.to_string(),
+            "* #variable= 3 #constraint= 2\n+1 _x3 +1 x1 >= 1;\n+1 _x3_ >= 1;\n"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_solve_all_counts() {
+        let opb_file =
+            parse("#variable= 7 #constraint= 3\nx1 + x2 >= 1;\nx3 + x4 + x5 >= 2;\nx7 >= 1;")
+                .expect("error while parsing");
         let formula = PseudoBooleanFormula::new(&opb_file);
+        let index = |name: &str| formula.index_of(name).unwrap();
+        let mut first = vec![index("x1"), index("x2")];
+        let mut second = vec![index("x3"), index("x4"), index("x5")];
+        first.sort_unstable();
+        second.sort_unstable();
         let mut solver = Solver::new(formula);
-        let result = solver.solve();
-        let mut printer = DDNNFPrinter {
-            true_sink_id: None,
-            false_sink_id: None,
-            ddnnf: result.ddnnf,
-            current_node_id: 0,
-            id_map: HashMap::new(),
-            edge_counter: 0,
-            node_counter: 0,
+
+        let component_counts = solver.solve_all_counts();
+        assert_eq!(
+            component_counts,
+            vec![(first, BigUint::from(3u32)), (second, BigUint::from(4u32))]
+        );
+        // x7 is implied, the sixth variable of the header is free
+        let (_, implied_literals) = solver.implied_literals();
+        let component_variables: usize = component_counts.iter().map(|(v, _)| v.len()).sum();
+        let free_variables = 7 - component_variables - implied_literals.len();
+        assert_eq!(free_variables, 1);
+        let product: BigUint = component_counts
+            .into_iter()
+            .map(|(_, count)| count)
+            .product();
+        assert_eq!(product << free_variables, solver.solve().model_count);
+
+        // the counts of the components of a larger model
+        let opb_file = parse(
+            fs::read_to_string("./test_models/berkeleydb.opb")
+                .expect("cannot read file")
+                .as_str(),
+        )
+        .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let component_counts = solver.solve_all_counts();
+        let component_variables: usize = component_counts.iter().map(|(v, _)| v.len()).sum();
+        let free_variables = solver.pseudo_boolean_formula.number_variables as usize
+            - component_variables
+            - solver.implied_literals().1.len();
+        let product: BigUint = component_counts
+            .into_iter()
+            .map(|(_, count)| count)
+            .product();
+        assert_eq!(product << free_variables, solver.solve().model_count);
+
+        // the sub-solvers use the settings of the solver
+        let mut seeded_solver = Solver::with_seed(PseudoBooleanFormula::new(&opb_file), 7);
+        seeded_solver.set_cache_capacity(10);
+        assert_eq!(seeded_solver.solve_all_counts(), solver.solve_all_counts());
+        let cancel_flag = Arc::new(AtomicBool::new(true));
+        let mut cancelled_solver =
+            Solver::with_cancel(PseudoBooleanFormula::new(&opb_file), cancel_flag);
+        let cancelled = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            cancelled_solver.solve_all_counts()
+        }));
+        assert!(cancelled.is_err());
+
+        let opb_file = parse("#variable= 2 #constraint= 2\nx1 >= 1;\n-1 x1 >= 0;")
+            .expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        assert_eq!(
+            solver.solve_all_counts(),
+            vec![(vec![0, 1], BigUint::zero())]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_unsat_constraint_balance() {
+        let file_content =
+            fs::read_to_string("./test_models/berkeleydb.opb").expect("cannot read file");
+        let opb_file = parse(file_content.as_str()).expect("error while parsing");
+        let mut solver = Solver::new(PseudoBooleanFormula::new(&opb_file));
+        let number_constraints = solver.pseudo_boolean_formula.constraints.len();
+        let unsatisfied = |solver: &Solver| {
+            solver
+                .pseudo_boolean_formula
+                .constraints
+                .iter()
+                .filter(|constraint| !constraint.is_satisfied())
+                .count()
         };
-        let ddnnf = printer.print();
-        assert_eq!(ddnnf, "o 1 0\nt 2 0\n1 2 2 -1 0\n1 2 1 0\n");
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..20_000 {
+            let unassigned: Vec<u32> = (0..solver.pseudo_boolean_formula.number_variables)
+                .filter(|index| solver.assignments[*index as usize].is_none())
+                .collect();
+            if !unassigned.is_empty() && rng.gen_bool(0.6) {
+                let variable_index = unassigned[rng.gen_range(0..unassigned.len())];
+                solver.decision_level += 1;
+                // a conflict keeps the assignments made so far, they are undone below
+                solver.propagate(variable_index, rng.gen_bool(0.5), FirstDecision);
+            } else {
+                for _ in 0..rng.gen_range(1..=8) {
+                    if solver.assignment_stack.is_empty() {
+                        break;
+                    }
+                    solver.undo_last_assignment();
+                }
+            }
+            assert!(solver.number_unsat_constraints <= number_constraints);
+            assert_eq!(solver.number_unsat_constraints, unsatisfied(&solver));
+        }
+        while !solver.assignment_stack.is_empty() {
+            solver.undo_last_assignment();
+        }
+        assert_eq!(solver.number_unsat_constraints, number_constraints);
+        solver.decision_level = 0;
+        assert_eq!(
+            solver.solve().model_count,
+            BigUint::from_str("63552545718785").unwrap()
+        );
     }
 }
@@ -0,0 +1,57 @@
+use p2d::read_formula;
+use p2d::solving::solver::Solver;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts the allocations of the whole test binary, so it must only contain one test
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the result of the function and the number of allocations it made
+fn count_allocations<T>(function: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let result = function();
+    (result, ALLOCATIONS.load(Ordering::Relaxed) - before)
+}
+
+#[test]
+fn test_arena_allocations() {
+    let formula =
+        read_formula("./test_models/financialservices01.opb").expect("cannot read formula");
+    let mut solver = Solver::new(formula);
+    solver.set_progress_callback(Box::new(|_| {}));
+    let (result, solve) = count_allocations(|| solver.solve());
+    let ddnnf = result.ddnnf.expect("the solver built no d-DNNF");
+
+    let (count, model_count) = count_allocations(|| ddnnf.model_count());
+    let (arena, to_arena) = count_allocations(|| ddnnf.to_arena());
+    let (arena_count, arena_model_count) = count_allocations(|| arena.model_count());
+    let (dot, to_dot) = count_allocations(|| ddnnf.to_dot());
+    let (arena_dot, arena_to_dot) = count_allocations(|| arena.to_dot());
+    eprintln!(
+        "allocations on financialservices01 ({} nodes): solve {solve}, to_arena {to_arena}, \
+         model_count {model_count} / {arena_model_count} (arena), \
+         to_dot {to_dot} / {arena_to_dot} (arena)",
+        ddnnf.node_count()
+    );
+    assert_eq!(arena_count, count);
+    assert_eq!(arena_dot, dot);
+    // the circuit is copied into the arena, which only costs the allocations of the copy
+    assert_eq!(model_count, to_arena + arena_model_count);
+    assert_eq!(to_dot, to_arena + arena_to_dot);
+}
@@ -0,0 +1,242 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Returns a path in the temporary directory for a file of a test. The process id keeps the files
+/// of concurrent test runs apart, the test removes the file when it is done.
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("p2d_cli_test_{}_{name}", std::process::id()))
+}
+
+#[test]
+fn test_missing_input_file() {
+    let output = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .arg("./test_models/does_not_exist.opb")
+        .output()
+        .expect("cannot run p2d");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("cannot read file ./test_models/does_not_exist.opb"));
+    assert!(!stderr.contains("panicked"));
+}
+
+#[test]
+fn test_ddnnf_mode_without_output_file() {
+    let output = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .args(["./test_models/berkeleydb.opb", "-m", "ddnnf"])
+        .output()
+        .expect("cannot run p2d");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("requires an output file"));
+    assert!(!stderr.contains("panicked"));
+}
+
+#[test]
+fn test_ddnnf_formats() {
+    let dot_file = temp_file("ddnnf.dot");
+    let status = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .args([
+            "./test_models/berkeleydb.opb",
+            "-m",
+            "ddnnf",
+            "--format",
+            "dot",
+            "-o",
+        ])
+        .arg(&dot_file)
+        .output()
+        .expect("cannot run p2d")
+        .status;
+    assert!(status.success());
+    let dot = std::fs::read_to_string(&dot_file).expect("cannot read output file");
+    std::fs::remove_file(&dot_file).expect("cannot remove output file");
+    assert!(dot.starts_with("digraph"));
+
+    let json_file = temp_file("ddnnf.json");
+    let status = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .args([
+            "./test_models/berkeleydb.opb",
+            "-m",
+            "ddnnf",
+            "--format",
+            "json",
+            "-o",
+        ])
+        .arg(&json_file)
+        .output()
+        .expect("cannot run p2d")
+        .status;
+    assert!(status.success());
+    let json = std::fs::read_to_string(&json_file).expect("cannot read output file");
+    std::fs::remove_file(&json_file).expect("cannot remove output file");
+    let value: serde_json::Value = serde_json::from_str(&json).expect("invalid json");
+    assert!(value["nodes"].is_array());
+}
+
+#[test]
+fn test_format_without_ddnnf_mode() {
+    let output = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .args(["./test_models/berkeleydb.opb", "--format", "dot"])
+        .output()
+        .expect("cannot run p2d");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("only be chosen in mode ddnnf"));
+}
+
+#[test]
+fn test_json_model_count() {
+    let output = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .args(["./test_models/berkeleydb.opb", "--json"])
+        .output()
+        .expect("cannot run p2d");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("invalid json");
+    assert_eq!(value["model_count"], "63552545718785");
+    assert!(value["statistics"]["cache_hits"].is_number());
+}
+
+#[test]
+fn test_gzip_input() {
+    let opb = std::fs::read("./test_models/berkeleydb.opb").expect("cannot read input file");
+    let gzip_file = temp_file("berkeleydb.opb.gz");
+    let mut encoder = GzEncoder::new(
+        std::fs::File::create(&gzip_file).expect("cannot create gzip file"),
+        Compression::default(),
+    );
+    encoder.write_all(&opb).expect("cannot compress input file");
+    encoder.finish().expect("cannot compress input file");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .arg(&gzip_file)
+        .arg("--json")
+        .output()
+        .expect("cannot run p2d");
+    std::fs::remove_file(&gzip_file).expect("cannot remove gzip file");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("invalid json");
+    assert_eq!(value["model_count"], "63552545718785");
+}
+
+#[test]
+fn test_multiple_input_files() {
+    let output = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .args([
+            "./test_models/berkeleydb.opb",
+            "./test_models/does_not_exist.opb",
+            "./test_models/financialservices01.opb",
+            "--jobs",
+            "2",
+        ])
+        .output()
+        .expect("cannot run p2d");
+    // the missing file is reported, but the other files are still counted
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("cannot read file ./test_models/does_not_exist.opb"));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.contains(&"./test_models/berkeleydb.opb: 63552545718785"));
+    assert!(lines.contains(&"./test_models/financialservices01.opb: 97451212554676"));
+}
+
+#[test]
+fn test_wcnf_input() {
+    let count = |file_name: &str, content: &str| {
+        let file = temp_file(file_name);
+        std::fs::write(&file, content).expect("cannot write input file");
+        let output = Command::new(env!("CARGO_BIN_EXE_p2d"))
+            .arg(&file)
+            .arg("--json")
+            .output()
+            .expect("cannot run p2d");
+        std::fs::remove_file(&file).expect("cannot remove input file");
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let value: serde_json::Value = serde_json::from_str(&stdout).expect("invalid json");
+        value["model_count"].clone()
+    };
+    // the soft clauses with a weight below 10 do not change the models
+    let wcnf = count(
+        "input.wcnf",
+        "p wcnf 4 5 10\n10 1 -2 0\n3 -1 0\n10 2 3 4 0\n1 -4 -3 0\n12 -1 -4 0\n",
+    );
+    let cnf = count("input.cnf", "p cnf 4 3\n1 -2 0\n2 3 4 0\n-1 -4 0\n");
+    assert_eq!(wcnf, cnf);
+    assert_eq!(wcnf, "6");
+}
+
+#[test]
+fn test_verify() {
+    let output = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .args(["./test_models/berkeleydb.opb", "--verify", "--json"])
+        .output()
+        .expect("cannot run p2d");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("invalid json");
+    assert_eq!(value["model_count"], "63552545718785");
+}
+
+#[test]
+fn test_stats_only() {
+    // the formula of test_ex_1 in the solver
+    let input_file = temp_file("stats_only.opb");
+    std::fs::write(
+        &input_file,
+        "#variable= 5 #constraint= 2\nx1 + x2 >= 0;\n3 x2 + x3 + x4 + x5 >= 3;\n",
+    )
+    .expect("cannot write input file");
+    let output = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .arg(&input_file)
+        .args(["--stats-only", "--json"])
+        .output()
+        .expect("cannot run p2d");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("invalid json");
+    assert!(value.get("model_count").is_none());
+    assert!(value["statistics"]["decisions"].as_u64().unwrap() > 0);
+    assert!(value["statistics"]["cache_entries"].as_u64().unwrap() > 0);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .arg(&input_file)
+        .args(["--stats-only", "--verify"])
+        .output()
+        .expect("cannot run p2d");
+    std::fs::remove_file(&input_file).expect("cannot remove input file");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_max_unused_variables() {
+    let input_file = temp_file("unused_variables.opb");
+    std::fs::write(&input_file, "#variable= 5 #constraint= 1\nx1 >= 1;")
+        .expect("cannot write input file");
+    let output = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .arg(&input_file)
+        .args(["--max-unused-variables", "3"])
+        .output()
+        .expect("cannot run p2d");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--max-unused-variables"));
+    assert!(!stderr.contains("panicked"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_p2d"))
+        .arg(&input_file)
+        .args(["--max-unused-variables", "4", "--json"])
+        .output()
+        .expect("cannot run p2d");
+    std::fs::remove_file(&input_file).expect("cannot remove input file");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value: serde_json::Value = serde_json::from_str(&stdout).expect("invalid json");
+    assert_eq!(value["model_count"], "16");
+}
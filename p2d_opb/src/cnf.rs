@@ -0,0 +1,141 @@
+use super::{Equation, EquationKind, OPBFile, Summand};
+
+/// Parses a formula in the DIMACS CNF format.
+/// Every clause `l1 l2 ... 0` is converted into the constraint `l1 + l2 + ... >= 1` where negative
+/// literals become negated summands. The variable `n` is named `x{n}` and gets the index `n - 1`.
+/// # Arguments
+/// * `content` - The content of the CNF file
+/// # Returns
+/// The formula as an `OPBFile` or an error message
+pub fn parse_cnf(content: &str) -> Result<OPBFile, String> {
+    let mut opb_file = OPBFile::new();
+    let mut header_found = false;
+    let mut clause = Vec::new();
+
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('c') || line.starts_with('%') {
+            continue;
+        }
+        if line.starts_with('p') {
+            if header_found {
+                return Err(format!(
+                    "Parsing error! Second header in line {}",
+                    line_number + 1
+                ));
+            }
+            parse_header(line, &mut opb_file)?;
+            header_found = true;
+            continue;
+        }
+        if !header_found {
+            return Err(format!(
+                "Parsing error! Expected header before line {}",
+                line_number + 1
+            ));
+        }
+        for token in line.split_whitespace() {
+            let literal: i64 = token.parse().map_err(|_| {
+                format!(
+                    "Parsing error! {} in line {} is not a literal",
+                    token,
+                    line_number + 1
+                )
+            })?;
+            if literal == 0 {
+                opb_file.equations.push(clause_to_equation(&clause));
+                clause.clear();
+            } else if literal.unsigned_abs() > opb_file.number_variables as u64 {
+                return Err(format!(
+                    "Parsing error! Variable {} in line {} exceeds the number of variables",
+                    literal.unsigned_abs(),
+                    line_number + 1
+                ));
+            } else {
+                clause.push(literal);
+            }
+        }
+    }
+
+    if !header_found {
+        return Err("Parsing error! Missing header".to_string());
+    }
+    if !clause.is_empty() {
+        // the last clause may omit its terminating zero
+        opb_file.equations.push(clause_to_equation(&clause));
+    }
+    Ok(opb_file)
+}
+
+fn parse_header(line: &str, opb_file: &mut OPBFile) -> Result<(), String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    match parts.as_slice() {
+        ["p", "cnf", number_variables, number_constraints] => {
+            let number_variables: u32 = number_variables
+                .parse()
+                .map_err(|_| format!("Parsing error! {} is not a valid header", line))?;
+            opb_file.number_constraints = number_constraints
+                .parse()
+                .map_err(|_| format!("Parsing error! {} is not a valid header", line))?;
+            opb_file.number_variables = number_variables as usize;
+            for variable_index in 0..number_variables {
+                opb_file
+                    .name_map
+                    .insert(format!("x{}", variable_index + 1), variable_index);
+            }
+            opb_file.max_name_index = number_variables;
+            Ok(())
+        }
+        _ => Err(format!("Parsing error! {} is not a valid header", line)),
+    }
+}
+
+pub(crate) fn clause_to_equation(clause: &[i64]) -> Equation {
+    Equation {
+        lhs: clause
+            .iter()
+            .map(|literal| Summand {
+                variable_index: (literal.unsigned_abs() - 1) as u32,
+                factor: 1,
+                positive: *literal > 0,
+            })
+            .collect(),
+        rhs: 1,
+        kind: EquationKind::Ge,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cnf() {
+        let opb_file =
+            parse_cnf("c example\np cnf 3 2\n1 -2 0\n2 3\n-1 0\n").expect("failed to parse input");
+        assert_eq!(opb_file.number_variables, 3);
+        assert_eq!(opb_file.number_constraints, 2);
+        assert_eq!(opb_file.max_name_index, 3);
+        assert_eq!(opb_file.name_map.get_by_left("x2"), Some(&1));
+        assert_eq!(opb_file.equations.len(), 2);
+        let clause = &opb_file.equations[1];
+        assert_eq!(clause.kind, EquationKind::Ge);
+        assert_eq!(clause.rhs, 1);
+        assert_eq!(
+            clause
+                .lhs
+                .iter()
+                .map(|s| (s.variable_index, s.factor, s.positive))
+                .collect::<Vec<_>>(),
+            vec![(1, 1, true), (2, 1, true), (0, 1, false)]
+        );
+    }
+
+    #[test]
+    fn test_parse_cnf_errors() {
+        assert!(parse_cnf("1 2 0\n").is_err());
+        assert!(parse_cnf("p cnf 2\n1 2 0\n").is_err());
+        assert!(parse_cnf("p cnf 2 1\n1 3 0\n").is_err());
+        assert!(parse_cnf("p cnf 2 1\n1 a 0\n").is_err());
+    }
+}
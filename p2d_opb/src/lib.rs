@@ -1,16 +1,25 @@
+mod cnf;
 mod parser;
+mod wcnf;
 
-pub use parser::parse;
+pub use cnf::parse_cnf;
+pub use parser::{parse, parse_each, parse_reader};
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::io::{self, Write};
+pub use wcnf::parse_wcnf;
 
 use bimap::{BiHashMap, BiMap};
 
+#[derive(Debug, PartialEq)]
 pub struct OPBFile {
     pub name_map: BiMap<String, u32>,
     pub equations: Vec<Equation>,
     pub max_name_index: u32,
     pub number_constraints: usize,
     pub number_variables: usize,
+    /// The summands of the objective function (`min: ...;`), None if the file has no objective
+    pub objective: Option<Vec<Summand>>,
 }
 
 impl OPBFile {
@@ -21,10 +30,84 @@ impl OPBFile {
             max_name_index: 0,
             number_constraints: 0,
             number_variables: 0,
+            objective: None,
         }
     }
 }
 
+impl OPBFile {
+    /// Checks whether an assignment satisfies all equations.
+    /// # Arguments
+    /// * `assignment` - The value of each variable by its index
+    /// # Panics
+    /// Panics if the assignment contains less variables than used by the equations
+    pub fn evaluate(&self, assignment: &[bool]) -> bool {
+        self.equations
+            .iter()
+            .all(|equation| equation.evaluate(assignment))
+    }
+
+    /// Adds the equations of another file, e.g. to combine the fragments of a model that are
+    /// stored in separate files. Variables are identified by their names: a name that occurs in
+    /// both files is the same variable, every other variable of `other` (also the unnamed ones
+    /// of its header) gets a new index after all variables of this file, in the order of the
+    /// indexes in `other`. The objective functions are added up.
+    /// # Arguments
+    /// * `other` - The file whose equations are appended, its indexes are mapped to this file
+    pub fn merge(&mut self, other: &OPBFile) {
+        // unnamed variables of the header have indexes from max_name_index on, so new variables
+        // start after them
+        let mut next_index = self.max_name_index.max(self.number_variables as u32);
+        let mut index_map: HashMap<u32, u32> = HashMap::new();
+        let mut map_index = |index: u32, opb_file: &mut OPBFile| {
+            *index_map.entry(index).or_insert_with(|| {
+                let name = other.name_map.get_by_right(&index);
+                if let Some(existing_index) =
+                    name.and_then(|name| opb_file.name_map.get_by_left(name))
+                {
+                    return *existing_index;
+                }
+                let new_index = next_index;
+                next_index += 1;
+                if let Some(name) = name {
+                    opb_file.name_map.insert(name.clone(), new_index);
+                    opb_file.max_name_index = new_index + 1;
+                }
+                new_index
+            })
+        };
+        for index in 0..other.max_name_index.max(other.number_variables as u32) {
+            map_index(index, self);
+        }
+        let mut map_summands = |summands: &[Summand], opb_file: &mut OPBFile| -> Vec<Summand> {
+            summands
+                .iter()
+                .map(|summand| Summand {
+                    variable_index: map_index(summand.variable_index, opb_file),
+                    ..summand.clone()
+                })
+                .collect()
+        };
+        for equation in &other.equations {
+            let lhs = map_summands(&equation.lhs, self);
+            self.equations.push(Equation {
+                lhs,
+                rhs: equation.rhs,
+                kind: equation.kind.clone(),
+            });
+        }
+        if let Some(objective) = &other.objective {
+            let summands = map_summands(objective, self);
+            self.objective.get_or_insert_with(Vec::new).extend(summands);
+        }
+        self.number_variables = next_index as usize;
+        self.number_constraints += other.number_constraints;
+    }
+}
+
+/// Writes the header as a comment, the objective and one equation per line. The alternate form
+/// (`{:#}`) aligns the equations in columns: the factors are right-aligned, the n-th literals of
+/// all equations start in the same column and so do the relation operators and right-hand sides.
 impl Display for OPBFile {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(
@@ -32,6 +115,17 @@ impl Display for OPBFile {
             "* #variable= {} #constraint= {}",
             self.number_variables, self.number_constraints
         )?;
+        if let Some(objective) = &self.objective {
+            let summands = objective.iter().fold(String::new(), |mut output, summand| {
+                output.push(' ');
+                output.push_str(summand.to_string(&self.name_map).as_str());
+                output
+            });
+            writeln!(f, "min:{summands};")?;
+        }
+        if f.alternate() {
+            return self.fmt_aligned(f);
+        }
         self.equations
             .iter()
             .map(|equation| equation.to_string(&self.name_map))
@@ -39,7 +133,157 @@ impl Display for OPBFile {
     }
 }
 
-#[derive(Clone)]
+impl OPBFile {
+    /// Writes the file in a form that `parse` reads back into the same formula: the header, the
+    /// objective and one equation per line, with the same names, factors and right-hand sides. The
+    /// variable indexes of the parsed file may differ, as the parser numbers the variables in the
+    /// order of their first appearance. Unlike `Display`, the header is written with the number of
+    /// equations and at least as many variables as are used, and the file is checked before
+    /// anything is written.
+    /// # Arguments
+    /// * `output` - The writer that receives the lines
+    /// # Returns
+    /// An error of kind `InvalidInput` if the file has no equation, an equation has no summands, a
+    /// variable has no valid name or a number does not fit into an i128 after parsing, otherwise
+    /// the first error of the writer
+    pub fn write_to<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidInput, message);
+        if self.equations.is_empty() {
+            return Err(invalid(
+                "an OPB file needs at least one equation".to_string(),
+            ));
+        }
+        let mut number_variables = self.number_variables;
+        let summands = self.objective.iter().flatten();
+        for summand in summands.chain(self.equations.iter().flat_map(|equation| &equation.lhs)) {
+            let name = self
+                .name_map
+                .get_by_right(&summand.variable_index)
+                .ok_or_else(|| {
+                    invalid(format!("variable {} has no name", summand.variable_index))
+                })?;
+            if !is_valid_name(name) {
+                return Err(invalid(format!("{name} is not a valid variable name")));
+            }
+            // the parser reads the absolute value first, which does not fit for i128::MIN
+            if summand.factor == i128::MIN {
+                return Err(invalid(format!("the factor of {name} is too small")));
+            }
+            number_variables = number_variables.max(summand.variable_index as usize + 1);
+        }
+        for equation in &self.equations {
+            if equation.lhs.is_empty() {
+                return Err(invalid(
+                    "an equation needs at least one summand".to_string(),
+                ));
+            }
+            if equation.rhs == i128::MIN {
+                return Err(invalid("a right-hand side is too small".to_string()));
+            }
+        }
+
+        writeln!(
+            output,
+            "* #variable= {} #constraint= {}",
+            number_variables,
+            self.equations.len()
+        )?;
+        if let Some(objective) = &self.objective {
+            write!(output, "min:")?;
+            for summand in objective {
+                write!(output, " {}", summand.to_string(&self.name_map))?;
+            }
+            writeln!(output, ";")?;
+        }
+        for equation in &self.equations {
+            writeln!(output, "{}", equation.to_string(&self.name_map))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the equations aligned in columns, see `Display`.
+    fn fmt_aligned(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let summands: Vec<Vec<(String, String)>> = self
+            .equations
+            .iter()
+            .map(|equation| {
+                equation
+                    .lhs
+                    .iter()
+                    .map(|summand| {
+                        let literal = summand.to_string(&self.name_map);
+                        let (factor, literal) = literal.split_once(' ').unwrap_or_default();
+                        (factor.to_string(), literal.to_string())
+                    })
+                    .collect()
+            })
+            .collect();
+        let factor_width = summands
+            .iter()
+            .flatten()
+            .map(|(factor, _)| factor.len())
+            .max()
+            .unwrap_or(0);
+        let mut literal_widths = Vec::new();
+        for equation in &summands {
+            for (column, (_, literal)) in equation.iter().enumerate() {
+                if column == literal_widths.len() {
+                    literal_widths.push(0);
+                }
+                literal_widths[column] = literal_widths[column].max(literal.len());
+            }
+        }
+        let lhs: Vec<String> = summands
+            .iter()
+            .map(|equation| {
+                equation
+                    .iter()
+                    .zip(&literal_widths)
+                    .map(|((factor, literal), width)| {
+                        format!("{factor:>factor_width$} {literal:<width$}")
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        let lhs_width = lhs.iter().map(String::len).max().unwrap_or(0);
+        let rhs_width = self
+            .equations
+            .iter()
+            .map(|equation| equation.rhs.to_string().len())
+            .max()
+            .unwrap_or(0);
+        for (lhs, equation) in lhs.iter().zip(&self.equations) {
+            let kind = equation.kind.to_string();
+            writeln!(
+                f,
+                "{lhs:<lhs_width$} {kind:<2} {:>rhs_width$};",
+                equation.rhs
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks if the parser reads a name as one variable, see `var_name` in the grammar. A name
+/// without quotes starts with a letter or `_`, a quoted name keeps its quotes.
+fn is_valid_name(name: &str) -> bool {
+    if let Some(quoted) = name
+        .strip_prefix('"')
+        .and_then(|name| name.strip_suffix('"'))
+    {
+        return quoted
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || " -_./+,:$".contains(c));
+    }
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || "_./".contains(c))
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Equation {
     pub lhs: Vec<Summand>,
     pub rhs: i128,
@@ -56,6 +300,29 @@ impl Equation {
 
         format!("{}{} {};", lhs, self.kind, self.rhs)
     }
+
+    /// Checks whether an assignment satisfies the equation. A negated summand contributes its
+    /// factor if its variable is false.
+    /// # Arguments
+    /// * `assignment` - The value of each variable by its index
+    /// # Panics
+    /// Panics if the assignment contains less variables than used by the equation
+    pub fn evaluate(&self, assignment: &[bool]) -> bool {
+        let lhs: i128 = self
+            .lhs
+            .iter()
+            .filter(|summand| assignment[summand.variable_index as usize] == summand.positive)
+            .map(|summand| summand.factor)
+            .sum();
+        match self.kind {
+            EquationKind::Eq => lhs == self.rhs,
+            EquationKind::Ge => lhs >= self.rhs,
+            EquationKind::Le => lhs <= self.rhs,
+            EquationKind::G => lhs > self.rhs,
+            EquationKind::L => lhs < self.rhs,
+            EquationKind::NotEq => lhs != self.rhs,
+        }
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -81,7 +348,7 @@ impl Display for EquationKind {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Summand {
     pub variable_index: u32,
     pub factor: i128,
@@ -89,11 +356,13 @@ pub struct Summand {
 }
 
 impl Summand {
+    /// Creates the OPB representation of the summand, the factor always has a sign and a negated
+    /// variable is prefixed with `~`.
     pub fn to_string(&self, variable_map: &BiMap<String, u32>) -> String {
-        let mut output = format!("{} ", self.factor);
+        let mut output = format!("{:+} ", self.factor);
 
         if !self.positive {
-            output.push('-')
+            output.push('~')
         }
 
         output.push_str(
@@ -108,7 +377,7 @@ impl Summand {
 
 #[cfg(test)]
 mod test {
-    use crate::parse;
+    use crate::{Equation, EquationKind, OPBFile, Summand, parse, parse_reader};
 
     #[test]
     fn parse_and_display() {
@@ -117,11 +386,235 @@ x + 2 a + b + c >= 3;
 -1 d + e + 1 * f >= 1;"#;
 
         let expected = r#"* #variable= 7 #constraint= 2
-1 x 2 a 1 b 1 c >= 3;
--1 d 1 e 1 f >= 1;
++1 x +2 a +1 b +1 c >= 3;
+-1 d +1 e +1 f >= 1;
 "#;
 
         let parsed = parse(input).expect("failed to parse input");
         assert_eq!(parsed.to_string(), expected);
     }
+
+    #[test]
+    fn display_aligned() {
+        let input = r#"#variable= 4 #constraint= 2
+x + 20 long_name >= 3;
+-1 d + ~x + 3 long_name != -12;"#;
+
+        let expected = r#"* #variable= 4 #constraint= 2
+ +1 x +20 long_name               >=   3;
+ -1 d  +1 ~x         +3 long_name != -12;
+"#;
+
+        let parsed = parse(input).expect("failed to parse input");
+        assert_eq!(format!("{parsed:#}"), expected);
+        assert_eq!(
+            parse(&format!("{parsed:#}")).expect("failed to parse output"),
+            parsed
+        );
+        assert_eq!(
+            format!("{parsed}"),
+            "* #variable= 4 #constraint= 2\n+1 x +20 long_name >= 3;\n-1 d +1 ~x +3 long_name != -12;\n"
+        );
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let input = r#"* comment
+#variable= 5 #constraint= 3
+x + 2 ~a - b >= -1;
+-3 ~c + ~d - 1 * e != 2;
+~x + 4 e <= 3;"#;
+
+        let parsed = parse(input).expect("failed to parse input");
+        assert!(!parsed.equations[0].lhs[1].positive);
+        let displayed = parsed.to_string();
+        assert_eq!(displayed.lines().nth(2), Some("-3 ~c +1 ~d -1 e != 2;"));
+        assert_eq!(parse(&displayed).expect("failed to parse output"), parsed);
+        assert_eq!(
+            crate::parse_reader(displayed.as_bytes()).expect("failed to parse output"),
+            parsed
+        );
+    }
+
+    #[test]
+    fn evaluate() {
+        let input = r#"#variable= 3 #constraint= 2
+2 x + y >= 2;
+x + z != 2;"#;
+
+        let mut parsed = parse(input).expect("failed to parse input");
+        assert!(parsed.evaluate(&[true, false, false]));
+        assert!(!parsed.evaluate(&[true, false, true]));
+        assert!(!parsed.evaluate(&[false, true, true]));
+
+        // 3 ~y <= 0
+        parsed.equations.push(Equation {
+            lhs: vec![Summand {
+                variable_index: 1,
+                factor: 3,
+                positive: false,
+            }],
+            rhs: 0,
+            kind: EquationKind::Le,
+        });
+        assert!(!parsed.evaluate(&[true, false, false]));
+        assert!(parsed.evaluate(&[true, true, false]));
+    }
+
+    fn write(opb_file: &OPBFile) -> String {
+        let mut output = Vec::new();
+        opb_file
+            .write_to(&mut output)
+            .expect("failed to write file");
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn write_round_trip() {
+        // a xorshift generator keeps the test deterministic without further dependencies
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move |bound: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % bound
+        };
+        let names = [
+            "x",
+            "_y",
+            "a.b",
+            "v/2",
+            "\"with space\"",
+            "\"-1 + x\"",
+            "z9",
+        ];
+        let kinds = [
+            EquationKind::Eq,
+            EquationKind::Ge,
+            EquationKind::Le,
+            EquationKind::G,
+            EquationKind::L,
+            EquationKind::NotEq,
+        ];
+        let random_number = |next: &mut dyn FnMut(u64) -> u64| match next(4) {
+            0 => i128::MAX - next(100) as i128,
+            1 => -i128::MAX + next(100) as i128,
+            _ => next(201) as i128 - 100,
+        };
+
+        for _ in 0..200 {
+            let mut opb_file = OPBFile::new();
+            for (index, name) in names.iter().enumerate() {
+                opb_file.name_map.insert(name.to_string(), index as u32);
+            }
+            opb_file.number_variables = names.len();
+            let random_summands = |next: &mut dyn FnMut(u64) -> u64| {
+                (0..1 + next(5))
+                    .map(|_| Summand {
+                        variable_index: next(names.len() as u64) as u32,
+                        factor: random_number(next),
+                        positive: next(2) == 0,
+                    })
+                    .collect::<Vec<_>>()
+            };
+            if next(2) == 0 {
+                opb_file.objective = Some(random_summands(&mut next));
+            }
+            for _ in 0..1 + next(5) {
+                let lhs = random_summands(&mut next);
+                opb_file.equations.push(Equation {
+                    lhs,
+                    rhs: next(21) as i128 - 10,
+                    kind: kinds[next(kinds.len() as u64) as usize].clone(),
+                });
+            }
+            opb_file.number_constraints = opb_file.equations.len();
+
+            let written = write(&opb_file);
+            let parsed = parse(&written).expect("failed to parse written file");
+            assert_eq!(write(&parsed), written);
+            assert_eq!(parsed.equations.len(), opb_file.equations.len());
+            assert_eq!(parsed.number_variables, opb_file.number_variables);
+            assert_eq!(
+                parse_reader(written.as_bytes()).expect("failed to parse written file"),
+                parsed
+            );
+        }
+    }
+
+    #[test]
+    fn write_invalid() {
+        let opb_file = parse("#variable= 2 #constraint= 1\nx + ~y >= -3;").unwrap();
+        assert_eq!(
+            write(&opb_file),
+            "* #variable= 2 #constraint= 1\n+1 x +1 ~y >= -3;\n"
+        );
+
+        let mut unnamed = parse("#variable= 2 #constraint= 1\nx + ~y >= -3;").unwrap();
+        unnamed.equations[0].lhs[0].variable_index = 2;
+        let mut bad_name = parse("#variable= 2 #constraint= 1\nx + ~y >= -3;").unwrap();
+        bad_name.name_map.insert("1x".to_string(), 0);
+        let mut min_factor = parse("#variable= 2 #constraint= 1\nx + ~y >= -3;").unwrap();
+        min_factor.equations[0].lhs[1].factor = i128::MIN;
+        let mut min_rhs = parse("#variable= 2 #constraint= 1\nx + ~y >= -3;").unwrap();
+        min_rhs.equations[0].rhs = i128::MIN;
+        let mut empty_lhs = parse("#variable= 2 #constraint= 1\nx + ~y >= -3;").unwrap();
+        empty_lhs.equations[0].lhs.clear();
+        let mut no_equations = parse("#variable= 2 #constraint= 1\nx + ~y >= -3;").unwrap();
+        no_equations.equations.clear();
+
+        for opb_file in [
+            unnamed,
+            bad_name,
+            min_factor,
+            min_rhs,
+            empty_lhs,
+            no_equations,
+        ] {
+            let mut output = Vec::new();
+            let error = opb_file.write_to(&mut output).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+            assert!(output.is_empty());
+        }
+    }
+
+    fn count_models(opb_file: &OPBFile) -> usize {
+        let number_variables = opb_file
+            .max_name_index
+            .max(opb_file.number_variables as u32);
+        (0..1usize << number_variables)
+            .filter(|models| {
+                let assignment: Vec<bool> = (0..number_variables)
+                    .map(|index| models >> index & 1 == 1)
+                    .collect();
+                opb_file.evaluate(&assignment)
+            })
+            .count()
+    }
+
+    #[test]
+    fn merge() {
+        let mut merged = parse("#variable= 3 #constraint= 2\nx + y >= 1;\n~y + z >= 1;")
+            .expect("failed to parse input");
+        // y and z are shared, w is new and the header declares one more unnamed variable
+        let other =
+            parse("#variable= 4 #constraint= 1\n2 y + z + w >= 2;").expect("failed to parse input");
+        merged.merge(&other);
+
+        let expected =
+            parse("#variable= 5 #constraint= 3\nx + y >= 1;\n~y + z >= 1;\n2 y + z + w >= 2;")
+                .expect("failed to parse input");
+        assert_eq!(merged, expected);
+        assert_eq!(count_models(&merged), 10);
+        assert_eq!(count_models(&merged), count_models(&expected));
+
+        // merging a file with itself adds the same constraints again
+        let input = "#variable= 2 #constraint= 1\nmin: -1 a;\na + b >= 1;";
+        let mut twice = parse(input).unwrap();
+        twice.merge(&parse(input).unwrap());
+        assert_eq!(twice.max_name_index, 2);
+        assert_eq!(twice.equations[0], twice.equations[1]);
+        assert_eq!(twice.objective.as_ref().map(Vec::len), Some(2));
+        assert_eq!(count_models(&twice), 3);
+    }
 }
@@ -2,6 +2,7 @@ use super::{Equation, EquationKind, OPBFile, Summand};
 use pest::Parser;
 use pest::iterators::Pair;
 use pest_derive::Parser;
+use std::io::BufRead;
 
 #[derive(Parser)]
 #[grammar = "./src/opb.pest"] // points to the grammar file we created
@@ -18,6 +19,98 @@ pub fn parse(content: &str) -> Result<OPBFile, String> {
     }
 }
 
+/// Parses an OPB file line by line without reading the whole content into memory.
+/// Every line contains a comment (starting with `*`), the header, or a single equation like in the
+/// format accepted by `parse`.
+/// # Arguments
+/// * `reader` - The reader providing the content of the file
+/// # Returns
+/// The formula as an `OPBFile` or an error message
+pub fn parse_reader<R: BufRead>(reader: R) -> Result<OPBFile, String> {
+    let mut equations = Vec::new();
+    let mut opb_file = parse_each(reader, |equation, _| equations.push(equation))?;
+    opb_file.equations = equations;
+    Ok(opb_file)
+}
+
+/// Parses an OPB file line by line and passes every equation to a callback instead of storing it.
+/// The header has to appear before the first equation and the objective, like in `parse`, between
+/// the header and the first equation.
+/// # Arguments
+/// * `reader` - The reader providing the content of the file
+/// * `callback` - Receives every equation and the file parsed so far, which contains the header
+///   and the indexes of all variables seen so far
+/// # Returns
+/// The header and the variables of the file without the equations or an error message
+pub fn parse_each<R: BufRead>(
+    reader: R,
+    mut callback: impl FnMut(Equation, &OPBFile),
+) -> Result<OPBFile, String> {
+    let mut opb_file = OPBFile::new();
+    let mut header_found = false;
+    let mut equation_found = false;
+
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("Reading error! {}", e))?;
+        let line = line.trim_end_matches('\r');
+        let is_header = !header_found && OPBParser::parse(Rule::header_line, line).is_ok();
+        if line.trim().is_empty() || (line.starts_with('*') && !is_header) {
+            continue;
+        }
+        let rule = if header_found {
+            Rule::equation_line
+        } else {
+            Rule::header_line
+        };
+        let mut pairs = OPBParser::parse(rule, line)
+            .map_err(|e| format!("Parsing error in line {}! {}", line_number + 1, e))?;
+        let Some(pair) = pairs.next() else {
+            return Err(format!("Parsing error! Empty line {}.", line_number + 1));
+        };
+        if header_found {
+            for inner_rule in pair.into_inner() {
+                match inner_rule.as_rule() {
+                    Rule::equation => {
+                        let equation = parse_equation(inner_rule, &mut opb_file)?;
+                        callback(equation, &opb_file);
+                        equation_found = true;
+                    }
+                    Rule::chained_equation => {
+                        for equation in parse_chained_equation(inner_rule, &mut opb_file)? {
+                            callback(equation, &opb_file);
+                        }
+                        equation_found = true;
+                    }
+                    Rule::objective => {
+                        if opb_file.objective.is_some() {
+                            return Err(format!(
+                                "Parsing error! Second objective in line {}",
+                                line_number + 1
+                            ));
+                        }
+                        if equation_found {
+                            return Err(format!(
+                                "Parsing error! The objective in line {} has to follow the header",
+                                line_number + 1
+                            ));
+                        }
+                        opb_file.objective = Some(parse_objective(inner_rule, &mut opb_file)?);
+                    }
+                    _ => (),
+                }
+            }
+        } else {
+            parse_header(pair, &mut opb_file);
+            header_found = true;
+        }
+    }
+
+    if !header_found {
+        return Err("Parsing error! Missing header".to_string());
+    }
+    Ok(opb_file)
+}
+
 fn parse_opb_file(rule: Pair<Rule>) -> Result<OPBFile, String> {
     let mut opb_file = OPBFile::new();
 
@@ -32,9 +125,16 @@ fn parse_opb_file(rule: Pair<Rule>) -> Result<OPBFile, String> {
                     Err(e) => return Err(e),
                 }
             }
+            Rule::chained_equation => {
+                let equations = parse_chained_equation(inner_rule, &mut opb_file)?;
+                opb_file.equations.extend(equations);
+            }
             Rule::header => {
                 parse_header(inner_rule, &mut opb_file);
             }
+            Rule::objective => {
+                opb_file.objective = Some(parse_objective(inner_rule, &mut opb_file)?);
+            }
             Rule::EOI => (),
             _ => {
                 return Err(format!(
@@ -61,6 +161,17 @@ fn parse_header(rule: Pair<Rule>, opb_file: &mut OPBFile) {
     }
 }
 
+/// Parses the objective function `min: terms;`, its variables get indexes like the variables of
+/// the equations.
+/// # Returns
+/// The summands of the objective, an empty objective has no summands
+fn parse_objective(rule: Pair<Rule>, opb_file: &mut OPBFile) -> Result<Vec<Summand>, String> {
+    match rule.into_inner().next() {
+        Some(equation_side) => parse_equation_side(equation_side, opb_file),
+        None => Ok(Vec::new()),
+    }
+}
+
 fn parse_equation(rule: Pair<Rule>, opb_file: &mut OPBFile) -> Result<Equation, String> {
     let mut equation_side = None;
     let mut equation_kind = None;
@@ -99,6 +210,63 @@ fn parse_equation(rule: Pair<Rule>, opb_file: &mut OPBFile) -> Result<Equation,
     }
 }
 
+/// Parses an equation of the form `lower <= terms <= upper` (or with `<`) and expands it into an
+/// equation with `>=` (`>`) for the lower and one with `<=` (`<`) for the upper bound. The number
+/// of constraints of the file is increased by one, so it stays consistent with the equations.
+/// # Returns
+/// The equation for the lower bound followed by the one for the upper bound
+fn parse_chained_equation(
+    rule: Pair<Rule>,
+    opb_file: &mut OPBFile,
+) -> Result<Vec<Equation>, String> {
+    let equation_string = rule.as_str();
+    let mut bounds = Vec::new();
+    let mut kinds = Vec::new();
+    let mut equation_side = None;
+    for inner_rule in rule.into_inner() {
+        match inner_rule.as_rule() {
+            Rule::right_hand_side => bounds.push(parse_right_hand_side(inner_rule)?),
+            Rule::chain_kind => kinds.push(parse_equation_kind(inner_rule)?),
+            Rule::equation_side => {
+                equation_side = Some(parse_equation_side(inner_rule, opb_file)?);
+            }
+            _ => {
+                return Err(format!(
+                    "Parsing error! {} is not part of a chained equation",
+                    inner_rule.as_str()
+                ));
+            }
+        }
+    }
+
+    let (Some(lhs), [lower, upper], [lower_kind, upper_kind]) =
+        (equation_side, bounds.as_slice(), kinds.as_slice())
+    else {
+        return Err(format!(
+            "Parsing error! {} is not a complete chained equation",
+            equation_string
+        ));
+    };
+    // lower <= terms is the same as terms >= lower
+    let lower_kind = match lower_kind {
+        EquationKind::L => EquationKind::G,
+        _ => EquationKind::Ge,
+    };
+    opb_file.number_constraints += 1;
+    Ok(vec![
+        Equation {
+            lhs: lhs.clone(),
+            rhs: *lower,
+            kind: lower_kind,
+        },
+        Equation {
+            lhs,
+            rhs: *upper,
+            kind: upper_kind.clone(),
+        },
+    ])
+}
+
 fn parse_equation_side(rule: Pair<Rule>, opb_file: &mut OPBFile) -> Result<Vec<Summand>, String> {
     let mut equation_side = Vec::new();
     for inner_rule in rule.into_inner() {
@@ -111,6 +279,7 @@ fn parse_equation_side(rule: Pair<Rule>, opb_file: &mut OPBFile) -> Result<Vec<S
 fn parse_summand(rule: Pair<Rule>, opb_file: &mut OPBFile) -> Result<Summand, String> {
     let mut factor = 1;
     let mut sign = 1;
+    let mut positive = true;
     let mut var_name = None;
 
     let summand_string = rule.as_str();
@@ -125,6 +294,9 @@ fn parse_summand(rule: Pair<Rule>, opb_file: &mut OPBFile) -> Result<Summand, St
                     sign = -1;
                 }
             }
+            Rule::negation => {
+                positive = false;
+            }
             Rule::var_name => {
                 var_name = Some(inner_rule.as_str());
             }
@@ -150,7 +322,7 @@ fn parse_summand(rule: Pair<Rule>, opb_file: &mut OPBFile) -> Result<Summand, St
         Ok(Summand {
             factor: factor * sign,
             variable_index: var_index,
-            positive: true,
+            positive,
         })
     } else {
         Err(format!(
@@ -267,4 +439,184 @@ mod tests {
             Ok(_) => panic!("Expected an error, but got Ok instead."),
         }
     }
+
+    #[test]
+    fn test_compact_and_spaced_equations() {
+        let header = "#variable= 3 #constraint= 3\n";
+        let compact = parse(&format!("{header}x1+2x2>=1;\n-3x3+~x1!=-2;\n2*x2-x3<=1;\n"))
+            .expect("failed to parse compact input");
+        let spaced = parse(&format!(
+            "{header}x1 + 2 x2 >= 1 ;\n-3 x3 + ~x1 != -2 ;\n2 * x2 - x3 <= 1 ;\n"
+        ))
+        .expect("failed to parse spaced input");
+        let tabs = parse(&format!(
+            "{header}x1\t+\t2 x2\t>=\t1;\n-3 x3 +~x1!= -2\n 2 *x2  -x3<=1 ;\n"
+        ))
+        .expect("failed to parse input with tabs");
+        assert_eq!(compact, spaced);
+        assert_eq!(tabs, spaced);
+        assert_eq!(compact.equations.len(), 3);
+        assert_eq!(
+            compact.equations[0].lhs,
+            vec![
+                Summand {
+                    variable_index: 0,
+                    factor: 1,
+                    positive: true
+                },
+                Summand {
+                    variable_index: 1,
+                    factor: 2,
+                    positive: true
+                },
+            ]
+        );
+
+        // quoted names may still contain a +
+        let quoted = parse("#variable= 2 #constraint= 1\n\"a+b\"+2 \"c\">=1;\n")
+            .expect("failed to parse quoted names");
+        assert_eq!(quoted.equations[0].lhs.len(), 2);
+        assert!(quoted.name_map.contains_left("\"a+b\""));
+    }
+
+    #[test]
+    fn test_chained_equations() {
+        let chained =
+            parse("#variable= 3 #constraint= 2\n1 <= x1 + 2 x2 + ~x3 <= 2;\n-1 < x1 - x2 < 1;\n")
+                .expect("failed to parse chained equations");
+        let spelled_out = parse(
+            "#variable= 3 #constraint= 4\nx1 + 2 x2 + ~x3 >= 1;\nx1 + 2 x2 + ~x3 <= 2;\nx1 - x2 > -1;\nx1 - x2 < 1;\n",
+        )
+        .expect("failed to parse input");
+        assert_eq!(chained, spelled_out);
+        assert_eq!(chained.number_constraints, chained.equations.len());
+        assert_eq!(
+            parse_reader(
+                "#variable= 3 #constraint= 2\n1 <= x1 + 2 x2 + ~x3 <= 2;\n-1 < x1 - x2 < 1;\n"
+                    .as_bytes()
+            )
+            .expect("failed to parse chained equations"),
+            spelled_out
+        );
+
+        // only a lower and an upper bound are allowed
+        assert!(parse("#variable= 2 #constraint= 1\n1 >= x1 + x2 >= 0;\n").is_err());
+    }
+
+    #[test]
+    fn test_variable_names() {
+        let input = "#variable= 4 #constraint= 2\nfeature.sub_1 + 2 _hidden >= 1;\n-1 x.y/z + \"with-dash.1\" + feature.sub_1 <= 1;\n";
+        let parsed = parse(input).expect("failed to parse names");
+        assert_eq!(parsed.name_map.get_by_left("feature.sub_1"), Some(&0));
+        assert_eq!(parsed.name_map.get_by_left("_hidden"), Some(&1));
+        assert_eq!(parsed.name_map.get_by_left("x.y/z"), Some(&2));
+        assert_eq!(parsed.name_map.get_by_left("\"with-dash.1\""), Some(&3));
+        assert_eq!(parsed.equations[1].lhs[2].variable_index, 0);
+        let displayed = parsed.to_string();
+        assert_eq!(
+            displayed.lines().nth(1),
+            Some("+1 feature.sub_1 +2 _hidden >= 1;")
+        );
+        assert_eq!(parse(&displayed).expect("failed to parse output"), parsed);
+
+        // a name must not start with a digit, the digits are read as a factor or a constant
+        assert!(parse("#variable= 1 #constraint= 1\nx + 3 >= 1;\n").is_err());
+        let glued = parse("#variable= 1 #constraint= 1\n2x.1 >= 1;\n").expect("failed to parse");
+        assert_eq!(glued.equations[0].lhs[0].factor, 2);
+        assert!(glued.name_map.contains_left("x.1"));
+    }
+
+    #[test]
+    fn test_objective() {
+        let input = "* comment\n#variable= 3 #constraint= 1\nmin: -2 x3 + x1 ;\nx1 + x2 >= 1;\n";
+        let parsed = parse(input).expect("failed to parse objective");
+        assert_eq!(
+            parsed.objective,
+            Some(vec![
+                Summand {
+                    variable_index: 0,
+                    factor: -2,
+                    positive: true
+                },
+                Summand {
+                    variable_index: 1,
+                    factor: 1,
+                    positive: true
+                },
+            ])
+        );
+        // the variables of the objective get the first indexes
+        assert_eq!(parsed.name_map.get_by_left("x3"), Some(&0));
+        assert_eq!(parsed.equations.len(), 1);
+        assert_eq!(parsed.to_string().lines().nth(1), Some("min: -2 x3 +1 x1;"));
+        assert_eq!(
+            parse(&parsed.to_string()).expect("failed to parse output"),
+            parsed
+        );
+        assert_eq!(
+            parse_reader(input.as_bytes()).expect("failed to parse objective"),
+            parsed
+        );
+
+        let empty = parse("#variable= 1 #constraint= 1\nmin: ;\nx1 >= 1;\n")
+            .expect("failed to parse empty objective");
+        assert_eq!(empty.objective, Some(Vec::new()));
+        assert_eq!(
+            parse("#variable= 1 #constraint= 1\nx1 >= 1;\n")
+                .expect("failed to parse input")
+                .objective,
+            None
+        );
+        // the objective has to follow the header
+        let late_objective = "#variable= 1 #constraint= 1\nx1 >= 1;\nmin: x1;\n";
+        assert!(parse(late_objective).is_err());
+        assert_eq!(
+            parse_reader(late_objective.as_bytes()).err(),
+            Some("Parsing error! The objective in line 3 has to follow the header".to_string())
+        );
+        assert!(
+            parse_reader("#variable= 1 #constraint= 1\nmin: x1;\nmin: x1;\n".as_bytes()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let content = "* comment\n#variable= 4 #constraint= 3\nx1 + 2 x2 >= 1;\n\n-3 x3 + x1 != -2\nx4 + x2 <= 1;\n";
+        let streamed = parse_reader(content.as_bytes()).expect("failed to parse input");
+        assert_eq!(streamed, parse(content).expect("failed to parse input"));
+        assert_eq!(streamed.equations.len(), 3);
+
+        let mut number_equations = 0;
+        let header = parse_each(content.as_bytes(), |_, _| number_equations += 1)
+            .expect("failed to parse input");
+        assert_eq!(number_equations, 3);
+        assert!(header.equations.is_empty());
+        assert_eq!(header.number_constraints, 3);
+        assert_eq!(header.max_name_index, 4);
+    }
+
+    #[test]
+    fn test_parse_reader_errors() {
+        assert!(parse_reader("x1 + x2 >= 1;\n".as_bytes()).is_err());
+        assert!(parse_reader("* only a comment\n".as_bytes()).is_err());
+        let result = parse_reader("#variable= 2 #constraint= 1\nx1 + x2 _ 1;\n".as_bytes());
+        assert!(result.unwrap_err().starts_with("Parsing error in line 2!"));
+    }
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let content = "* comment\n#variable= 5 #constraint= 4 \nmin: -2 x3 + x1;\n\nx1 + 2 x2 >= 1;\n-3 x3 + x1 != -2 \n1 <= x4 + \"x 5\" <= 2;\nx4 + ~x2 <= 1\n";
+        let expected = parse(content).expect("failed to parse input");
+        for crlf_content in [
+            content.replace('\n', "\r\n"),
+            content.replace('\n', "\r\n").trim_end().to_string(),
+        ] {
+            assert_eq!(
+                parse(&crlf_content).expect("failed to parse CRLF"),
+                expected
+            );
+            let streamed = parse_reader(crlf_content.as_bytes()).expect("failed to parse CRLF");
+            assert_eq!(streamed, expected);
+        }
+    }
 }
@@ -0,0 +1,153 @@
+use super::OPBFile;
+use crate::cnf::clause_to_equation;
+
+/// Parses a weighted partial MaxSAT formula in the WCNF format with the header
+/// `p wcnf <variables> <clauses> <top>`.
+/// Every clause `w l1 l2 ... 0` with a weight `w` of at least `top` is a hard clause and converted
+/// into the constraint `l1 + l2 + ... >= 1` like a clause of a CNF, see `parse_cnf`. The soft clauses
+/// (weight below `top`) do not restrict the models and are skipped, so the formula only contains
+/// the hard part and `number_constraints` is the number of hard clauses.
+/// # Arguments
+/// * `content` - The content of the WCNF file
+/// # Returns
+/// The hard part of the formula as an `OPBFile` or an error message
+pub fn parse_wcnf(content: &str) -> Result<OPBFile, String> {
+    let mut opb_file = OPBFile::new();
+    let mut top = None;
+    // the weight of the current clause, None before its first token
+    let mut weight = None;
+    let mut clause = Vec::new();
+
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('c') || line.starts_with('%') {
+            continue;
+        }
+        if line.starts_with('p') {
+            if top.is_some() {
+                return Err(format!(
+                    "Parsing error! Second header in line {}",
+                    line_number + 1
+                ));
+            }
+            top = Some(parse_header(line, &mut opb_file)?);
+            continue;
+        }
+        let Some(top) = top else {
+            return Err(format!(
+                "Parsing error! Expected header before line {}",
+                line_number + 1
+            ));
+        };
+        for token in line.split_whitespace() {
+            let Some(clause_weight) = weight else {
+                weight = Some(token.parse::<u64>().map_err(|_| {
+                    format!(
+                        "Parsing error! {} in line {} is not a weight",
+                        token,
+                        line_number + 1
+                    )
+                })?);
+                continue;
+            };
+            let literal: i64 = token.parse().map_err(|_| {
+                format!(
+                    "Parsing error! {} in line {} is not a literal",
+                    token,
+                    line_number + 1
+                )
+            })?;
+            if literal == 0 {
+                if clause_weight >= top {
+                    opb_file.equations.push(clause_to_equation(&clause));
+                }
+                clause.clear();
+                weight = None;
+            } else if literal.unsigned_abs() > opb_file.number_variables as u64 {
+                return Err(format!(
+                    "Parsing error! Variable {} in line {} exceeds the number of variables",
+                    literal.unsigned_abs(),
+                    line_number + 1
+                ));
+            } else {
+                clause.push(literal);
+            }
+        }
+    }
+
+    let Some(top) = top else {
+        return Err("Parsing error! Missing header".to_string());
+    };
+    if let Some(clause_weight) = weight {
+        // the last clause may omit its terminating zero
+        if clause_weight >= top {
+            opb_file.equations.push(clause_to_equation(&clause));
+        }
+    }
+    opb_file.number_constraints = opb_file.equations.len();
+    Ok(opb_file)
+}
+
+/// Reads the number of variables and the weight of the hard clauses from the header.
+/// # Returns
+/// The weight `top` of the hard clauses
+fn parse_header(line: &str, opb_file: &mut OPBFile) -> Result<u64, String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let ["p", "wcnf", number_variables, number_clauses, top] = parts.as_slice() else {
+        return Err(format!("Parsing error! {} is not a valid header", line));
+    };
+    let invalid_header = |_| format!("Parsing error! {} is not a valid header", line);
+    let number_variables: u32 = number_variables.parse().map_err(invalid_header)?;
+    number_clauses.parse::<usize>().map_err(invalid_header)?;
+    let top = top.parse().map_err(invalid_header)?;
+    opb_file.number_variables = number_variables as usize;
+    for variable_index in 0..number_variables {
+        opb_file
+            .name_map
+            .insert(format!("x{}", variable_index + 1), variable_index);
+    }
+    opb_file.max_name_index = number_variables;
+    Ok(top)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_cnf;
+
+    /// Counts the models of a formula by trying all assignments.
+    fn count(opb_file: &OPBFile) -> usize {
+        (0..1_u32 << opb_file.number_variables)
+            .filter(|bits| {
+                let assignment: Vec<bool> = (0..opb_file.number_variables)
+                    .map(|variable| bits & (1 << variable) != 0)
+                    .collect();
+                opb_file.evaluate(&assignment)
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_parse_wcnf() {
+        let wcnf = parse_wcnf(
+            "c example\np wcnf 4 5 10\n10 1 -2 0\n3 -1 0\n10 2 3\n4 0\n1 -4 -3 0\n12 -1 -4\n",
+        )
+        .expect("failed to parse input");
+        let cnf =
+            parse_cnf("p cnf 4 3\n1 -2 0\n2 3 4 0\n-1 -4 0\n").expect("failed to parse input");
+        assert_eq!(wcnf, cnf);
+        assert_eq!(wcnf.number_constraints, 3);
+        assert_eq!(count(&wcnf), count(&cnf));
+        assert_eq!(count(&wcnf), 6);
+    }
+
+    #[test]
+    fn test_parse_wcnf_errors() {
+        assert!(parse_wcnf("10 1 2 0\n").is_err());
+        assert!(parse_wcnf("p wcnf 2 1\n1 1 2 0\n").is_err());
+        assert!(parse_wcnf("p wcnf 2 1 x\n1 1 2 0\n").is_err());
+        assert!(parse_wcnf("p wcnf 2 1 10\n10 1 3 0\n").is_err());
+        assert!(parse_wcnf("p wcnf 2 1 10\n-1 1 2 0\n").is_err());
+        assert!(parse_wcnf("p wcnf 2 1 10\np wcnf 2 1 10\n").is_err());
+    }
+}